@@ -21,6 +21,7 @@ local function enum(name, values, selected)
     }
 end
 local function file(name) return {name = name, type = "file"} end
+local function static_mesh(name) return {name = name, type = "static_mesh"} end
 
 -- Primitives: Construct new meshes based on common patterns
 local primitives = {
@@ -47,6 +48,77 @@ local primitives = {
         },
         outputs = {mesh("out_mesh")},
         returns = "out_mesh"
+    },
+    MakeUvSphere = {
+        label = "UV Sphere",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.uv_sphere(inputs.center, inputs.radius,
+                                                math.floor(inputs.rings),
+                                                math.floor(inputs.segments))
+            }
+        end,
+        inputs = {
+            v3("center", vector(0, 0, 0)), scalar("radius", 1.0, 0.0, 10.0),
+            scalar("rings", 12, 3, 64), scalar("segments", 24, 3, 128)
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh"
+    },
+    MakeIcosphere = {
+        label = "Icosphere",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.icosphere(inputs.center, inputs.radius,
+                                                math.floor(inputs.subdivisions))
+            }
+        end,
+        inputs = {
+            v3("center", vector(0, 0, 0)), scalar("radius", 1.0, 0.0, 10.0),
+            scalar("subdivisions", 2, 0, 6)
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh"
+    },
+    MakeCylinder = {
+        label = "Cylinder",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.cylinder(inputs.center, inputs.radius,
+                                               inputs.height,
+                                               math.floor(inputs.segments))
+            }
+        end,
+        inputs = {
+            v3("center", vector(0, 0, 0)), scalar("radius", 1.0, 0.0, 10.0),
+            scalar("height", 2.0, 0.0, 10.0), scalar("segments", 16, 3, 128)
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh"
+    },
+    MakeCone = {
+        label = "Cone",
+        op = function(inputs)
+            return {
+                out_mesh = Primitives.cone(inputs.center, inputs.base_radius,
+                                           inputs.height,
+                                           math.floor(inputs.segments))
+            }
+        end,
+        inputs = {
+            v3("center", vector(0, 0, 0)),
+            scalar("base_radius", 1.0, 0.0, 10.0),
+            scalar("height", 2.0, 0.0, 10.0), scalar("segments", 16, 3, 128)
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh"
+    },
+    StaticMesh = {
+        label = "Static mesh",
+        op = function(inputs) return {out_mesh = inputs.mesh_data} end,
+        inputs = {static_mesh("mesh_data")},
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh"
     }
 }
 
@@ -55,13 +127,15 @@ local edit_ops = {
     BevelEdges = {
         label = "Bevel edges",
         inputs = {
-            mesh("in_mesh"), selection("edges"), scalar("amount", 0.0, 0.0, 1.0)
+            mesh("in_mesh"), selection("edges"), scalar("amount", 0.0, 0.0, 1.0),
+            scalar("segments", 1, 1, 16), scalar("profile", 0.5, 0.0, 1.0)
         },
         outputs = {mesh("out_mesh")},
         returns = "out_mesh",
         op = function(inputs)
             local out_mesh = inputs.in_mesh:clone()
-            Ops.bevel(inputs.edges, inputs.amount, out_mesh)
+            Ops.bevel(inputs.edges, inputs.amount, math.floor(inputs.segments),
+                      inputs.profile, out_mesh)
             return {out_mesh = out_mesh}
         end
     },
@@ -71,12 +145,92 @@ local edit_ops = {
             mesh("in_mesh"), selection("vertices"),
             scalar("amount", 0.0, 0.0, 1.0)
         },
-        outputs = {mesh("out_mesh")},
+        outputs = {mesh("out_mesh"), selection("new_faces")},
         returns = "out_mesh",
         op = function(inputs)
             local out_mesh = inputs.in_mesh:clone()
-            Ops.chamfer(inputs.vertices, inputs.amount, out_mesh)
-            return {out_mesh = out_mesh}
+            local new_faces = Ops.chamfer(inputs.vertices, inputs.amount, out_mesh)
+            return {out_mesh = out_mesh, new_faces = new_faces}
+        end
+    },
+    Dual = {
+        label = "Dual",
+        inputs = {mesh("mesh")},
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs) return {out_mesh = Ops.dual(inputs.mesh)} end
+    },
+    Array = {
+        label = "Array",
+        inputs = {
+            mesh("mesh"), scalar("count", 2, 1, 100),
+            v3("offset", vector(1, 0, 0)), enum("merge", {"No", "Yes"}, 0)
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.array(inputs.mesh, math.floor(inputs.count),
+                                     inputs.offset, inputs.merge == "Yes")
+            }
+        end
+    },
+    Mirror = {
+        label = "Mirror",
+        inputs = {
+            mesh("mesh"), v3("origin", vector(0, 0, 0)),
+            v3("normal", vector(1, 0, 0)), enum("weld", {"Yes", "No"}, 0)
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.mirror(inputs.origin, inputs.normal,
+                                      inputs.weld == "Yes", inputs.mesh)
+            }
+        end
+    },
+    Symmetrize = {
+        label = "Symmetrize",
+        inputs = {
+            mesh("mesh"), enum("axis", {"X", "Y", "Z"}, 0),
+            enum("direction", {"Positive to negative", "Negative to positive"}, 0)
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.symmetrize(inputs.axis,
+                                          inputs.direction ==
+                                              "Positive to negative",
+                                          inputs.mesh)
+            }
+        end
+    },
+    Revolve = {
+        label = "Revolve",
+        inputs = {
+            mesh("profile"), v3("axis_origin", vector(0, 0, 0)),
+            v3("axis_dir", vector(0, 1, 0)), scalar("angle", 360.0, 0.0, 360.0),
+            scalar("segments", 16, 1, 128)
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.revolve(inputs.profile, inputs.axis_origin,
+                                       inputs.axis_dir, inputs.angle,
+                                       math.floor(inputs.segments))
+            }
+        end
+    },
+    Solidify = {
+        label = "Solidify",
+        inputs = {mesh("mesh"), scalar("thickness", 0.1, 0.0, 1.0)},
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs)
+            return {out_mesh = Ops.solidify(inputs.mesh, inputs.thickness)}
         end
     },
     ExtrudeFaces = {
@@ -92,6 +246,74 @@ local edit_ops = {
             return {out_mesh = out_mesh}
         end
     },
+    InsetFaces = {
+        label = "Inset faces",
+        inputs = {
+            mesh("in_mesh"), selection("faces"), scalar("amount", 0.1, 0.0, 1.0)
+        },
+        outputs = {mesh("out_mesh"), selection("inner_faces")},
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.in_mesh:clone()
+            local inner_faces = Ops.inset(inputs.faces, inputs.amount, out_mesh)
+            return {out_mesh = out_mesh, inner_faces = inner_faces}
+        end
+    },
+    Smooth = {
+        label = "Smooth",
+        inputs = {
+            mesh("in_mesh"), selection("vertices"),
+            scalar("iterations", 1, 0, 100), scalar("factor", 0.5, 0.0, 1.0),
+            enum("boundary", {"Pinned", "Free"}, 0)
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.in_mesh:clone()
+            Ops.smooth(inputs.vertices, math.floor(inputs.iterations),
+                       inputs.factor, inputs.boundary == "Pinned", out_mesh)
+            return {out_mesh = out_mesh}
+        end
+    },
+    Jitter = {
+        label = "Jitter",
+        inputs = {
+            mesh("in_mesh"), selection("vertices"),
+            scalar("amount", 0.1, 0.0, 1.0), scalar("seed", 0, 0, 1000)
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.in_mesh:clone()
+            Ops.jitter(inputs.vertices, inputs.amount, math.floor(inputs.seed), out_mesh)
+            return {out_mesh = out_mesh}
+        end
+    },
+    PokeFaces = {
+        label = "Poke faces",
+        inputs = {
+            mesh("in_mesh"), selection("faces"),
+            scalar("offset", 0.0, -1.0, 1.0)
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.in_mesh:clone()
+            Ops.poke(inputs.faces, inputs.offset, out_mesh)
+            return {out_mesh = out_mesh}
+        end
+    },
+    FillHoles = {
+        label = "Fill holes",
+        inputs = {mesh("in_mesh"), scalar("max_edges", 32, 3, 256)},
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.in_mesh:clone()
+            Ops.fill_holes(out_mesh, inputs.max_edges)
+            return {out_mesh = out_mesh}
+        end
+    },
     MergeMeshes = {
         label = "Merge meshes",
         inputs = {mesh("mesh_a"), mesh("mesh_b")},
@@ -103,6 +325,44 @@ local edit_ops = {
             return {out_mesh = out_mesh}
         end
     },
+    Symmetry = {
+        label = "Symmetry",
+        inputs = {
+            mesh("in_mesh"), enum("axis", {"None", "X", "Y", "Z"}, 1),
+            scalar("offset", 0.0, -100.0, 100.0)
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.in_mesh:clone()
+            Ops.set_symmetry(inputs.axis, inputs.offset, out_mesh)
+            return {out_mesh = out_mesh}
+        end
+    },
+    Transform = {
+        label = "Transform",
+        inputs = {
+            mesh("in_mesh"), v3("translate", vector(0, 0, 0)),
+            v3("rotate", vector(0, 0, 0)), v3("scale", vector(1, 1, 1))
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs)
+            local out_mesh = inputs.in_mesh:clone()
+            Ops.transform(inputs.translate, inputs.rotate, inputs.scale,
+                          out_mesh)
+            return {out_mesh = out_mesh}
+        end
+    },
+    Wireframe = {
+        label = "Wireframe",
+        inputs = {mesh("mesh"), scalar("thickness", 0.05, 0.001, 1.0)},
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs)
+            return {out_mesh = Ops.wireframe(inputs.mesh, inputs.thickness)}
+        end
+    },
     Subdivide = {
         label = "Subdivide",
         inputs = {
@@ -117,11 +377,34 @@ local edit_ops = {
                                          inputs.technique == "catmull-clark")
             }
         end
+    },
+    SubdivideFaces = {
+        label = "Subdivide faces",
+        inputs = {
+            mesh("mesh"), selection("faces"), scalar("iterations", 1, 1, 5)
+        },
+        outputs = {mesh("out_mesh")},
+        returns = "out_mesh",
+        op = function(inputs)
+            return {
+                out_mesh = Ops.subdivide_faces(inputs.faces,
+                                               math.floor(inputs.iterations),
+                                               inputs.mesh)
+            }
+        end
     }
 }
 
 -- Math: Nodes to perform vector or scalar math operations
 local math = {
+    Time = {
+        label = "Time",
+        inputs = {},
+        outputs = {scalar("time")},
+        -- The time changes without the graph changing
+        cacheable = false,
+        op = function(inputs) return {time = Time or 0.0} end
+    },
     MakeVector = {
         label = "MakeVector",
         inputs = {
@@ -57,12 +57,14 @@ impl AppWindow {
         self.root_viewport.update(&mut self.render_ctx);
         self.root_viewport.render(&mut self.render_ctx);
 
-        // Sleep for the remaining time to cap at 60Hz
         let elapsed = Instant::now().duration_since(frame_start_time);
-        //println!("elapsed {:?}", elapsed);
-        let remaining = Duration::from_secs_f32(1.0 / 60.0).saturating_sub(elapsed);
-        //println!("remaining {:?}", remaining);
-        spin_sleep::sleep(remaining);
+        self.root_viewport.record_frame_time(elapsed);
+
+        // Sleep for the remaining time to cap at the target frame rate
+        if let Some(fps) = self.root_viewport.target_fps {
+            let remaining = Duration::from_secs_f32(1.0 / fps as f32).saturating_sub(elapsed);
+            spin_sleep::sleep(remaining);
+        }
     }
 
     pub fn run_app(mut self, event_loop: EventLoop<()>) {
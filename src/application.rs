@@ -1,4 +1,9 @@
-use std::sync::Arc;
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::{
     lua_engine::LuaRuntime,
@@ -14,9 +19,19 @@ use egui_winit_platform::{Platform, PlatformDescriptor};
 
 use self::{
     app_viewport::AppViewport, application_context::ApplicationContext, graph_editor::GraphEditor,
-    inspector::InspectorTabs, root_ui::AppRootAction, viewport_3d::Viewport3d,
+    inspector::InspectorTabs, lua_console::LuaConsole, root_ui::AppRootAction,
+    thumbnail::ThumbnailReadback, timeline::Timeline, viewport_3d::Viewport3d,
 };
 
+/// The default frame rate cap of the application window
+pub const DEFAULT_TARGET_FPS: u32 = 60;
+
+/// The number of frames used to compute the frame time statistics
+const FRAME_TIME_SAMPLES: usize = 120;
+
+/// The default time between autosaves
+pub const DEFAULT_AUTOSAVE_INTERVAL: Duration = Duration::from_secs(60);
+
 pub struct RootViewport {
     platform: Platform,
     screen_descriptor: ScreenDescriptor,
@@ -28,9 +43,34 @@ pub struct RootViewport {
     offscreen_viewports: HashMap<OffscreenViewport, AppViewport>,
     inspector_tabs: InspectorTabs,
     diagnostics_open: bool,
+    /// The maximum number of frames per second the window renders. `None`
+    /// means the frame rate is not capped.
+    pub target_fps: Option<u32>,
+    /// The time spent updating and rendering each of the most recent frames,
+    /// excluding the frame rate cap. Oldest first.
+    frame_times: VecDeque<Duration>,
+    /// The path the current file was last saved to or loaded from
+    current_path: Option<PathBuf>,
+    /// When set, the file is saved to this path after rendering the next
+    /// frame, so a thumbnail of the 3d viewport can be stored along with it.
+    pending_save: Option<PathBuf>,
+    /// The error of the last save, if it failed, shown to the user until
+    /// dismissed.
+    save_error: Option<String>,
+    /// The time between autosaves. `None` disables autosaving.
+    pub autosave_interval: Option<Duration>,
+    last_autosave: Instant,
+    /// When set, an autosave newer than the current file was found, and the
+    /// user is asked whether to recover it.
+    recovery_prompt: Option<PathBuf>,
+    /// Tells other instances that the autosave of unsaved files of this
+    /// process is still in use, so they don't offer to recover it.
+    _session_lock: Option<serialization::SessionLock>,
     code_viewer_open: bool,
     code_viewer_code: Option<String>,
     lua_runtime: LuaRuntime,
+    timeline: Timeline,
+    lua_console: LuaConsole,
 }
 
 /// The application context is state that is global to an instance of blackjack.
@@ -66,6 +106,21 @@ pub mod inspector;
 /// highlighting support
 pub mod code_viewer;
 
+/// The global animation time, and the play / pause / scrub controls for it
+pub mod timeline;
+
+/// An interactive Lua console to run code against the active mesh
+pub mod lua_console;
+
+/// Gizmos to edit the active node's parameters from the 3d viewport
+pub mod gizmo;
+
+/// Picking of mesh elements by clicking on the 3d viewport
+pub mod picking;
+
+/// Reading back the 3d viewport to store thumbnails in saved files
+pub mod thumbnail;
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 enum OffscreenViewport {
     GraphEditor,
@@ -112,10 +167,32 @@ impl RootViewport {
             offscreen_viewports,
             inspector_tabs: InspectorTabs::new(),
             diagnostics_open: false,
+            target_fps: Some(DEFAULT_TARGET_FPS),
+            frame_times: VecDeque::with_capacity(FRAME_TIME_SAMPLES),
+            current_path: None,
+            pending_save: None,
+            save_error: None,
+            autosave_interval: Some(DEFAULT_AUTOSAVE_INTERVAL),
+            last_autosave: Instant::now(),
+            recovery_prompt: None,
+            _session_lock: serialization::SessionLock::acquire()
+                .map_err(|err| eprintln!("There was an error locking the autosave: {}", err))
+                .ok(),
             code_viewer_open: false,
             code_viewer_code: None,
             lua_runtime: LuaRuntime::initialize().expect("Init lua should not fail"),
+            timeline: Timeline::new(),
+            lua_console: LuaConsole::new(),
+        }
+    }
+
+    /// Records the time it took to update and render a frame, to be shown in
+    /// the diagnostics window.
+    pub fn record_frame_time(&mut self, frame_time: Duration) {
+        if self.frame_times.len() == FRAME_TIME_SAMPLES {
+            self.frame_times.pop_front();
         }
+        self.frame_times.push_back(frame_time);
     }
 
     pub fn on_winit_event(&mut self, event: winit::event::Event<()>) {
@@ -170,13 +247,46 @@ impl RootViewport {
             self.handle_root_action(AppRootAction::Load(std::path::PathBuf::from(load_path)))
                 .expect("Error loading scene from cli arg");
         }
+        self.recovery_prompt = serialization::recoverable_autosave(self.current_path.as_deref());
+    }
+
+    /// Saves the editor state to the autosave file when the autosave interval
+    /// has elapsed since the last autosave.
+    fn autosave(&mut self) {
+        let interval = match self.autosave_interval {
+            Some(interval) => interval,
+            None => return,
+        };
+        // Don't overwrite an autosave the user may still want to recover
+        if self.recovery_prompt.is_some() || self.last_autosave.elapsed() < interval {
+            return;
+        }
+        self.last_autosave = Instant::now();
+        let path = serialization::autosave_path(self.current_path.as_deref());
+        if let Err(err) = serialization::save(&self.graph_editor.state, path, None) {
+            eprintln!("There was an error autosaving: {}", err);
+        }
     }
 
     pub fn update(&mut self, render_ctx: &mut RenderContext) {
         let mut actions = vec![];
 
-        if let Err(err) = self.lua_runtime.watch_for_changes() {
-            println!("TODO: {}", err);
+        match self.lua_runtime.watch_for_changes() {
+            Ok(true) => {
+                self.lua_runtime
+                    .node_definitions
+                    .update_graph(&mut self.graph_editor.state.graph);
+                self.app_context.evaluator.reload_node_libraries();
+            }
+            Ok(false) => {}
+            Err(err) => eprintln!("There was an error reloading the node libraries: {}", err),
+        }
+
+        self.autosave();
+
+        self.timeline.advance();
+        if let Err(err) = self.lua_runtime.set_time(self.timeline.time) {
+            eprintln!("Error setting animation time: {}", err);
         }
 
         self.graph_editor.update(
@@ -189,6 +299,8 @@ impl RootViewport {
             self.offscreen_viewports[&OffscreenViewport::Viewport3d].rect,
             render_ctx,
         );
+        render_ctx.set_sample_count(self.viewport_3d.settings.sample_count);
+        render_ctx.set_cull_backfaces(self.viewport_3d.settings.cull_backfaces);
 
         self.platform.begin_frame();
 
@@ -198,6 +310,10 @@ impl RootViewport {
             }
         });
 
+        egui::TopBottomPanel::bottom("timeline").show(&self.platform.context(), |ui| {
+            self.timeline.ui(ui);
+        });
+
         egui::CentralPanel::default().show(&self.platform.context(), |ui| {
             let mut split_tree = self.app_context.split_tree.clone();
             split_tree.show(ui, self, Self::show_leaf);
@@ -205,7 +321,15 @@ impl RootViewport {
         });
 
         self.diagnostics_ui(&self.platform.context());
+        self.recovery_ui(&self.platform.context());
+        self.save_error_ui(&self.platform.context());
         self.code_viewer_ui(&self.platform.context());
+        self.evaluation_progress_ui(&self.platform.context());
+        self.lua_console.ui(
+            &self.platform.context(),
+            &self.lua_runtime,
+            self.app_context.mesh.as_ref(),
+        );
 
         actions.extend(self.app_context.update(
             &self.platform.context(),
@@ -213,6 +337,7 @@ impl RootViewport {
             render_ctx,
             &self.viewport_3d.settings,
             &self.lua_runtime,
+            self.timeline.time,
         ));
 
         for action in actions {
@@ -225,20 +350,79 @@ impl RootViewport {
     pub fn handle_root_action(&mut self, action: AppRootAction) -> Result<()> {
         match action {
             AppRootAction::Save(path) => {
-                serialization::save(&self.graph_editor.state, path)?;
+                // The current path is only updated once the file is written
+                self.pending_save = Some(path);
                 Ok(())
             }
             AppRootAction::Load(path) => {
-                self.graph_editor.state = serialization::load(path)?;
+                self.graph_editor.state = serialization::load(path.clone())?;
+                self.current_path = Some(path);
                 Ok(())
             }
             AppRootAction::SetCodeViewerCode(code) => {
                 self.code_viewer_code = Some(code);
                 Ok(())
             }
+            AppRootAction::ImportObj(path) => {
+                self.app_context
+                    .import_obj(&mut self.graph_editor.state, &self.lua_runtime, path)
+            }
+            AppRootAction::ExportObj(path) => self
+                .app_context
+                .mesh
+                .as_ref()
+                .ok_or_else(|| anyhow!("There is no active node to export"))?
+                .to_wavefront_obj(path),
+            AppRootAction::ExportStl(path, binary) => self
+                .app_context
+                .mesh
+                .as_ref()
+                .ok_or_else(|| anyhow!("There is no active node to export"))?
+                .to_stl(path, binary),
+            AppRootAction::ExportObjSequence(folder) => self.export_obj_sequence(folder),
+            AppRootAction::CopyMeshAsObj => {
+                if let Some(mesh) = self.app_context.mesh.as_ref() {
+                    self.platform.context().output().copied_text =
+                        mesh.to_wavefront_obj_string()?;
+                }
+                Ok(())
+            }
+            AppRootAction::ExportLua(path) => {
+                let active = self
+                    .graph_editor
+                    .state
+                    .user_state
+                    .active_node
+                    .ok_or_else(|| anyhow!("There is no active node to export"))?;
+                let script = crate::graph::graph_compiler::bake_graph(
+                    &self.graph_editor.state.graph,
+                    active,
+                )?;
+                Ok(std::fs::write(path, script)?)
+            }
         }
     }
 
+    /// Re-evaluates the active node once for every frame in the timeline, and
+    /// exports each of the resulting meshes as `mesh_NNNN.obj` inside `folder`.
+    pub fn export_obj_sequence(&mut self, folder: std::path::PathBuf) -> Result<()> {
+        let current_time = self.timeline.time;
+        let result = self.export_obj_sequence_frames(&folder);
+        self.lua_runtime.set_time(current_time)?;
+        result
+    }
+
+    fn export_obj_sequence_frames(&mut self, folder: &std::path::Path) -> Result<()> {
+        for frame in 0..=self.timeline.last_frame() {
+            self.lua_runtime.set_time(self.timeline.frame_time(frame))?;
+            let mesh = self
+                .app_context
+                .run_active_node(&self.graph_editor.state, &self.lua_runtime)?;
+            mesh.to_wavefront_obj(folder.join(format!("mesh_{:04}.obj", frame)))?;
+        }
+        Ok(())
+    }
+
     pub fn render(&mut self, render_ctx: &mut RenderContext) {
         let RenderContext {
             ref base_graph,
@@ -254,11 +438,19 @@ impl RootViewport {
         let frame = rend3::util::output::OutputFrame::Surface {
             surface: Arc::clone(&render_ctx.surface),
         };
+        // There is no thumbnail when the viewport has no area
+        let readback = self.pending_save.as_ref().and_then(|_| {
+            ThumbnailReadback::new(
+                &render_ctx.renderer.device,
+                self.viewport_3d.get_resolution(),
+            )
+        });
         let (cmd_bufs, ready) = render_ctx.renderer.ready();
         let mut graph = rend3::graph::RenderGraph::new();
         self.add_root_to_graph(
             &mut graph,
             &ready,
+            readback.as_ref(),
             ViewportRoutines {
                 base_graph,
                 pbr: pbr_routine,
@@ -270,6 +462,32 @@ impl RootViewport {
             },
         );
         graph.execute(&render_ctx.renderer, frame, cmd_bufs, &ready);
+
+        if let Some(path) = self.pending_save.take() {
+            // A missing thumbnail is not worth failing the save over
+            let thumbnail = readback.and_then(|readback| {
+                readback
+                    .read(&render_ctx.renderer.device)
+                    .map_err(|err| eprintln!("There was an error creating the thumbnail: {}", err))
+                    .ok()
+            });
+            match serialization::save(&self.graph_editor.state, path.clone(), thumbnail.as_deref())
+            {
+                Ok(()) => {
+                    // The unsaved changes are now in the saved file, so the
+                    // autosaves of the previous and the new path are stale.
+                    for saved_path in [self.current_path.as_deref(), Some(path.as_path())] {
+                        if let Err(err) = serialization::remove_autosave(saved_path) {
+                            eprintln!("There was an error removing the autosave: {}", err);
+                        }
+                    }
+                    self.current_path = Some(path)
+                }
+                Err(err) => {
+                    self.save_error = Some(format!("Could not save to {}: {}", path.display(), err))
+                }
+            }
+        }
     }
 }
 
@@ -1,29 +1,73 @@
-use crate::{graph::graph_compiler::CompiledProgram, lua_engine::LuaRuntime, prelude::*};
+use crate::{
+    graph::graph_compiler::CompiledProgram,
+    lua_engine::{
+        evaluator::{EvaluationRequest, Evaluator},
+        LuaRuntime, ProgramResult,
+    },
+    prelude::*,
+    rendergraph::{
+        face_routine::XRaySettings, point_cloud_routine::PointCloudSettings,
+        wireframe_routine::WireframeSettings,
+    },
+};
 use anyhow::Error;
-use egui_node_graph::NodeId;
+use egui_node_graph::{NodeId, NodeTemplateTrait};
+use halfedge::selection::{SelectionExpression, SelectionKind};
 
 use super::{
+    inspector::ChannelEdit,
     root_ui::AppRootAction,
     viewport_3d::{EdgeDrawMode, FaceDrawMode, Viewport3dSettings},
     viewport_split::SplitTree,
 };
 
+/// The color used to draw the mesh vertices in the viewport
+const VERTEX_COLOR: Vec3 = glam::const_vec3!([0.2, 0.8, 0.2]);
+/// The length of the face normal debug lines, in world units
+const FACE_NORMAL_LENGTH: f32 = 0.25;
+
 pub struct ApplicationContext {
     /// The mesh is at the center of the application
     /// - The graph generates a program that produces this mesh.
     /// - The 3d viewport renders this mesh.
     pub mesh: Option<HalfEdgeMesh>,
+    /// The mesh as produced by the active node, before the `channel_edits`
+    /// are applied to get `mesh`.
+    evaluated_mesh: Option<HalfEdgeMesh>,
+    /// Whether `mesh` has any channel edits applied
+    mesh_has_edits: bool,
+    /// Evaluates the active node in the background
+    pub evaluator: Evaluator,
+    /// The error produced by the last evaluation of the active node, if any
+    evaluation_error: Option<Error>,
     /// The tree of splits at the center of application. Splits recursively
     /// partition the state either horizontally or vertically. This separation
     /// is dynamic, very similar to Blender's UI model
     pub split_tree: SplitTree,
+    /// The selection output of the active node, or its selection parameter
+    /// when it has no selection output. Its elements are highlighted in the
+    /// viewport.
+    pub active_selection: Option<SelectionExpression>,
+    /// Channel values edited by hand in the spreadsheet. They are applied on
+    /// top of the mesh produced by the active node, and discarded when a
+    /// different node becomes active.
+    pub channel_edits: Vec<ChannelEdit>,
+    /// The node the `channel_edits` were made for
+    channel_edits_node: Option<NodeId>,
 }
 
 impl ApplicationContext {
     pub fn new() -> ApplicationContext {
         ApplicationContext {
             mesh: None,
+            evaluated_mesh: None,
+            mesh_has_edits: false,
+            evaluator: Evaluator::spawn(crate::lua_engine::node_library_paths()),
+            evaluation_error: None,
             split_tree: SplitTree::default_tree(),
+            active_selection: None,
+            channel_edits: vec![],
+            channel_edits_node: None,
         }
     }
 
@@ -44,6 +88,7 @@ impl ApplicationContext {
         render_ctx: &mut RenderContext,
         viewport_settings: &Viewport3dSettings,
         lua_runtime: &LuaRuntime,
+        time: f32,
     ) -> Vec<AppRootAction> {
         // TODO: Instead of clearing all objects, make the app context own the
         // objects it's drawing and clear those instead.
@@ -51,19 +96,26 @@ impl ApplicationContext {
 
         let mut actions = vec![];
 
-        match self.run_active_node(editor_state, lua_runtime) {
+        match self.evaluate_active_node(editor_state, time) {
             Ok(code) => {
                 actions.push(AppRootAction::SetCodeViewerCode(code));
             }
             Err(err) => {
-                self.paint_errors(egui_ctx, err);
+                self.paint_errors(egui_ctx, &err);
             }
         };
+        self.receive_evaluation_result(editor_state);
+        if let Some(err) = &self.evaluation_error {
+            self.paint_errors(egui_ctx, err);
+        }
         if let Err(err) = self.run_side_effects(editor_state, lua_runtime) {
             eprintln!("There was an errror executing side effect: {}", err);
         }
         if let Err(err) = self.build_and_render_mesh(render_ctx, viewport_settings) {
-            self.paint_errors(egui_ctx, err);
+            self.paint_errors(egui_ctx, &err);
+        }
+        if let Err(err) = self.freeze_node(editor_state, lua_runtime) {
+            eprintln!("There was an error freezing node: {}", err);
         }
 
         actions
@@ -75,6 +127,13 @@ impl ApplicationContext {
         viewport_settings: &Viewport3dSettings,
     ) -> Result<()> {
         if let Some(mesh) = self.mesh.as_ref() {
+            // The previewed channel is only shown in the viewport. The colors
+            // are not written into the mesh, which may get exported.
+            let preview_colors = match &viewport_settings.preview_channel {
+                Some(channel) => Some(mesh.channel_preview_colors(channel)?),
+                None => None,
+            };
+
             // Base mesh
             {
                 if let Some(VertexIndexBuffers {
@@ -84,6 +143,41 @@ impl ApplicationContext {
                 }) = match viewport_settings.face_mode {
                     FaceDrawMode::Flat => Some(mesh.generate_triangle_buffers_flat()),
                     FaceDrawMode::Smooth => Some(mesh.generate_triangle_buffers_smooth()?),
+                    FaceDrawMode::VertexColor => {
+                        let VertexColorBuffers {
+                            positions,
+                            normals,
+                            colors,
+                        } = mesh.generate_vertex_color_buffers(preview_colors.as_ref());
+                        if !positions.is_empty() {
+                            render_ctx.face_routine.add_vertex_color_mesh(
+                                &render_ctx.renderer,
+                                &positions,
+                                &normals,
+                                &colors,
+                            );
+                        }
+                        None
+                    }
+                    FaceDrawMode::XRay => {
+                        let VertexIndexBuffers {
+                            positions,
+                            normals,
+                            indices,
+                        } = mesh.generate_triangle_buffers_flat();
+                        if !positions.is_empty() {
+                            render_ctx.face_routine.add_xray_mesh(
+                                &render_ctx.renderer,
+                                &positions,
+                                &normals,
+                                &indices,
+                                XRaySettings {
+                                    alpha: viewport_settings.xray_alpha,
+                                },
+                            );
+                        }
+                        None
+                    }
                     FaceDrawMode::None => None,
                 } {
                     if !positions.is_empty() {
@@ -97,9 +191,24 @@ impl ApplicationContext {
                 }
             }
 
+            let highlight = match (
+                viewport_settings.highlight_selection,
+                &self.active_selection,
+            ) {
+                (Some(kind), Some(selection)) => Some((kind, selection.clone())),
+                _ => None,
+            };
+
             // Face overlays
             {
-                let FaceOverlayBuffers { positions, colors } = mesh.generate_face_overlay_buffers();
+                let highlighted_faces = match &highlight {
+                    Some((SelectionKind::Faces, selection)) => mesh
+                        .read_connectivity()
+                        .resolve_face_selection_full(selection.clone()),
+                    _ => vec![],
+                };
+                let FaceOverlayBuffers { positions, colors } =
+                    mesh.generate_face_overlay_buffers(&highlighted_faces);
                 if !positions.is_empty() {
                     render_ctx.face_routine.add_overlay_mesh(
                         &render_ctx.renderer,
@@ -121,25 +230,111 @@ impl ApplicationContext {
                             &render_ctx.renderer.device,
                             &positions,
                             &colors,
+                            WireframeSettings {
+                                line_width: viewport_settings.line_width,
+                            },
                         )
                     }
                 }
             }
 
+            // Highlighted edges
+            if let Some((SelectionKind::Edges | SelectionKind::HalfEdges, selection)) = &highlight {
+                let highlighted = mesh
+                    .read_connectivity()
+                    .resolve_halfedge_selection_full(selection.clone());
+                let LineBuffers { positions, colors } =
+                    mesh.generate_highlighted_line_buffers(&highlighted)?;
+                if !positions.is_empty() {
+                    render_ctx.wireframe_routine.add_wireframe(
+                        &render_ctx.renderer.device,
+                        &positions,
+                        &colors,
+                        WireframeSettings {
+                            line_width: viewport_settings.highlight_line_width,
+                        },
+                    )
+                }
+            }
+
+            // Face normals
+            if viewport_settings.show_face_normals {
+                let LineBuffers { positions, colors } =
+                    mesh.generate_face_normal_buffers(FACE_NORMAL_LENGTH);
+                if !positions.is_empty() {
+                    render_ctx.wireframe_routine.add_wireframe(
+                        &render_ctx.renderer.device,
+                        &positions,
+                        &colors,
+                        WireframeSettings {
+                            line_width: viewport_settings.line_width,
+                        },
+                    )
+                }
+            }
+
+            // Bounding box
+            if viewport_settings.show_bounds {
+                let LineBuffers { positions, colors } = mesh.generate_bounding_box_buffers();
+                if !positions.is_empty() {
+                    render_ctx.wireframe_routine.add_wireframe(
+                        &render_ctx.renderer.device,
+                        &positions,
+                        &colors,
+                        WireframeSettings {
+                            line_width: viewport_settings.line_width,
+                        },
+                    )
+                }
+            }
+
             // Vertices
             {
                 let PointBuffers { positions } = mesh.generate_point_buffers();
+                // Vertices take their color from the vertex color channel
+                // when faces are drawn with vertex colors
+                let colors = match viewport_settings.face_mode {
+                    FaceDrawMode::VertexColor => {
+                        mesh.generate_point_colors(preview_colors.as_ref())
+                    }
+                    _ => None,
+                }
+                .unwrap_or_else(|| vec![VERTEX_COLOR]);
                 if !positions.is_empty() {
-                    render_ctx
-                        .point_cloud_routine
-                        .add_point_cloud(&render_ctx.renderer.device, &positions);
+                    render_ctx.point_cloud_routine.add_point_cloud_with_colors(
+                        &render_ctx.renderer.device,
+                        &positions,
+                        &colors,
+                        PointCloudSettings {
+                            point_size: viewport_settings.point_size,
+                        },
+                    );
+                }
+            }
+
+            // Highlighted vertices
+            if let Some((SelectionKind::Vertices, selection)) = &highlight {
+                let highlighted = mesh
+                    .read_connectivity()
+                    .resolve_vertex_selection_full(selection.clone());
+                let PointBuffers { positions } =
+                    mesh.generate_highlighted_point_buffers(&highlighted);
+                if !positions.is_empty() {
+                    render_ctx.point_cloud_routine.add_point_cloud(
+                        &render_ctx.renderer.device,
+                        &positions,
+                        HIGHLIGHT_COLOR,
+                        PointCloudSettings {
+                            point_size: viewport_settings.point_size,
+                        },
+                    );
                 }
             }
         }
         Ok(())
     }
 
-    pub fn paint_errors(&mut self, egui_ctx: &egui::CtxRef, err: Error) {
+    pub fn paint_errors(&self, egui_ctx: &egui::CtxRef, err: &Error) {
         let painter = egui_ctx.debug_painter();
         let width = egui_ctx.available_rect().width();
         painter.text(
@@ -167,24 +362,133 @@ impl ApplicationContext {
         Ok((program, params))
     }
 
-    // Returns the compiled lua code
-    pub fn run_active_node(
+    /// Compiles the active node and requests evaluating it in the background.
+    /// Returns the compiled lua code.
+    pub fn evaluate_active_node(
         &mut self,
         editor_state: &graph::GraphEditorState,
-        lua_runtime: &LuaRuntime,
+        time: f32,
     ) -> Result<String> {
         if let Some(active) = editor_state.user_state.active_node {
-            let (program, params) = self.compile_program(editor_state, lua_runtime, active)?;
-            let mesh =
-                crate::lua_engine::run_program(&lua_runtime.lua, &program.lua_program, params)?;
-            self.mesh = Some(mesh);
+            let program = crate::graph::graph_compiler::compile_graph(&editor_state.graph, active)?;
+            let params =
+                crate::graph::graph_compiler::extract_param_values(&editor_state.graph, &program)?;
+            if self.channel_edits_node != Some(active) {
+                self.channel_edits.clear();
+                self.channel_edits_node = Some(active);
+            }
+            self.evaluator.request(EvaluationRequest {
+                lua_program: program.lua_program.clone(),
+                params,
+                selection_outputs: Self::selection_outputs(editor_state, active),
+                time,
+            });
             Ok(program.lua_program)
         } else {
+            self.evaluator.invalidate();
             self.mesh = None;
+            self.evaluated_mesh = None;
+            self.evaluation_error = None;
+            self.active_selection = None;
             Ok("".into())
         }
     }
 
+    /// Picks up the result of the background evaluation, when it finishes,
+    /// and applies the channel edits to get the mesh shown to the user. While
+    /// a new result is not ready, the previous mesh stays in place.
+    fn receive_evaluation_result(&mut self, editor_state: &graph::GraphEditorState) {
+        let mut changed = false;
+        match self.evaluator.poll() {
+            Some(Ok(ProgramResult { mesh, selection })) => {
+                // The result of a node that is no longer active is dropped
+                if let Some(active) = editor_state.user_state.active_node {
+                    // A selection produced by the node is shown over the
+                    // selection it takes as a parameter.
+                    self.active_selection =
+                        selection.or_else(|| Self::find_selection_param(editor_state, active));
+                    self.evaluated_mesh = Some(mesh);
+                    self.evaluation_error = None;
+                    changed = true;
+                }
+            }
+            Some(Err(err)) => self.evaluation_error = Some(err),
+            None => {}
+        }
+
+        // Edits are applied to a fresh copy of the mesh, so that discarded
+        // edits don't linger.
+        if changed || self.mesh_has_edits || !self.channel_edits.is_empty() {
+            if let Some(evaluated) = &self.evaluated_mesh {
+                let mesh = evaluated.clone();
+                self.channel_edits.retain(|edit| match edit.apply(&mesh) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        eprintln!("There was an error applying a channel edit: {}", err);
+                        false
+                    }
+                });
+                self.mesh_has_edits = !self.channel_edits.is_empty();
+                self.mesh = Some(mesh);
+            }
+        }
+    }
+
+    /// Runs the active node on the main thread, blocking until it finishes,
+    /// and returns its mesh with the channel edits applied. Meant for
+    /// operations that need the result right away, like exporting an OBJ
+    /// sequence.
+    pub fn run_active_node(
+        &self,
+        editor_state: &graph::GraphEditorState,
+        lua_runtime: &LuaRuntime,
+    ) -> Result<HalfEdgeMesh> {
+        let active = editor_state
+            .user_state
+            .active_node
+            .ok_or_else(|| anyhow!("There is no active node"))?;
+        let (program, params) = self.compile_program(editor_state, lua_runtime, active)?;
+        lua_runtime.progress.reset();
+        let ProgramResult { mesh, .. } =
+            crate::lua_engine::run_program(&lua_runtime.lua, &program.lua_program, params, &[])?;
+        for edit in &self.channel_edits {
+            if let Err(err) = edit.apply(&mesh) {
+                eprintln!("There was an error applying a channel edit: {}", err);
+            }
+        }
+        Ok(mesh)
+    }
+
+    /// Returns the names of the selection outputs of the given node
+    fn selection_outputs(editor_state: &graph::GraphEditorState, node_id: NodeId) -> Vec<String> {
+        editor_state.graph[node_id]
+            .outputs
+            .iter()
+            .filter(|(_, output)| {
+                editor_state.graph.get_output(*output).typ == graph::DataType::Selection
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Returns the value of the first selection parameter of the given node,
+    /// when that parameter holds a valid selection.
+    fn find_selection_param(
+        editor_state: &graph::GraphEditorState,
+        node_id: NodeId,
+    ) -> Option<SelectionExpression> {
+        editor_state.graph[node_id]
+            .inputs
+            .iter()
+            .find_map(|(_, input)| match &editor_state.graph[*input].value {
+                graph::ValueType::Selection {
+                    selection: Some(selection),
+                    ..
+                } => Some(selection.clone()),
+                _ => None,
+            })
+    }
+
     pub fn run_side_effects(
         &mut self,
         editor_state: &mut graph::GraphEditorState,
@@ -194,10 +498,98 @@ impl ApplicationContext {
             let (program, params) = self.compile_program(editor_state, lua_runtime, side_effect)?;
             // We ignore the result. The program is only executed to produce a
             // side effect (e.g. exporting a mesh as OBJ)
-            let _ = crate::lua_engine::run_program(&lua_runtime.lua, &program.lua_program, params)?;
+            let _ = crate::lua_engine::run_program(
+                &lua_runtime.lua,
+                &program.lua_program,
+                params,
+                &[],
+            )?;
         }
         Ok(())
     }
+
+    /// Bakes the mesh produced by the node requested by the UI into a new
+    /// static mesh node, which becomes the active node. The upstream nodes are
+    /// left untouched, so they can be deleted manually.
+    pub fn freeze_node(
+        &mut self,
+        editor_state: &mut graph::GraphEditorState,
+        lua_runtime: &LuaRuntime,
+    ) -> Result<()> {
+        if let Some(node_id) = editor_state.user_state.freeze_node.take() {
+            if editor_state.user_state.active_node != Some(node_id) {
+                bail!("Only the active node can be frozen");
+            }
+            let obj = self
+                .mesh
+                .as_ref()
+                .ok_or_else(|| anyhow!("The active node did not produce a mesh"))?
+                .to_wavefront_obj_string()?;
+            let label = format!("{} (frozen)", editor_state.graph[node_id].label);
+            let position = editor_state
+                .node_positions
+                .get(node_id)
+                .copied()
+                .unwrap_or_default();
+            Self::add_static_mesh_node(
+                editor_state,
+                lua_runtime,
+                label,
+                obj,
+                position + egui::vec2(0.0, 200.0),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Reads the OBJ file at `path` into a new static mesh node, which becomes
+    /// the active node.
+    pub fn import_obj(
+        &mut self,
+        editor_state: &mut graph::GraphEditorState,
+        lua_runtime: &LuaRuntime,
+        path: std::path::PathBuf,
+    ) -> Result<()> {
+        let obj = std::fs::read_to_string(&path)?;
+        // Parse the file upfront, so invalid files are reported here instead
+        // of when the node runs.
+        HalfEdgeMesh::from_wavefront_obj_str(&obj)?;
+        let label = path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "Imported mesh".into());
+        Self::add_static_mesh_node(editor_state, lua_runtime, label, obj, egui::Pos2::ZERO)
+    }
+
+    /// Adds a new static mesh node storing the given OBJ data, and makes it
+    /// the active node.
+    fn add_static_mesh_node(
+        editor_state: &mut graph::GraphEditorState,
+        lua_runtime: &LuaRuntime,
+        label: String,
+        obj: String,
+        position: egui::Pos2,
+    ) -> Result<()> {
+        let definition = lua_runtime
+            .node_definitions
+            .0
+            .get("StaticMesh")
+            .ok_or_else(|| anyhow!("The StaticMesh node is not defined"))?;
+
+        let new_node =
+            editor_state
+                .graph
+                .add_node(label, definition.user_data(), |graph, new_node| {
+                    definition.build_node(graph, new_node)
+                });
+        let mesh_data = editor_state.graph[new_node].get_input("mesh_data")?;
+        editor_state.graph[mesh_data].value = graph::ValueType::StaticMesh { obj: Some(obj) };
+
+        editor_state.node_positions.insert(new_node, position);
+        editor_state.node_order.push(new_node);
+        editor_state.user_state.active_node = Some(new_node);
+        Ok(())
+    }
 }
 
 impl Default for ApplicationContext {
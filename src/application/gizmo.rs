@@ -0,0 +1,202 @@
+use crate::prelude::*;
+use egui_node_graph::InputId;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GizmoMode {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+/// The length of the gizmo axes, in world units
+const AXIS_LENGTH: f32 = 1.0;
+/// The distance, in points, at which the mouse cursor starts interacting with
+/// an axis of the gizmo.
+const HIT_DISTANCE: f32 = 8.0;
+/// Degrees of rotation per point of mouse movement, in rotate mode
+const ROTATION_SPEED: f32 = 0.5;
+
+/// A gizmo, drawn over the 3d viewport, to interactively edit the parameters
+/// of the active node. The gizmo is only shown when the active node has
+/// `translate`, `rotate` and `scale` vector inputs, like the Transform node.
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    /// The axis (0, 1 or 2 for X, Y, Z) being dragged, if any
+    dragging: Option<usize>,
+    /// True when the mouse is over the gizmo or dragging it. The viewport
+    /// camera should ignore the mouse while this is set.
+    pub captures_mouse: bool,
+}
+
+/// The inputs of a node that can be edited with the gizmo
+struct TransformInputs {
+    translate: InputId,
+    rotate: InputId,
+    scale: InputId,
+}
+
+impl TransformInputs {
+    fn find(editor_state: &graph::GraphEditorState) -> Option<Self> {
+        let node = &editor_state.graph[editor_state.user_state.active_node?];
+        Some(Self {
+            translate: node.get_input("translate").ok()?,
+            rotate: node.get_input("rotate").ok()?,
+            scale: node.get_input("scale").ok()?,
+        })
+    }
+
+    fn for_mode(&self, mode: GizmoMode) -> InputId {
+        match mode {
+            GizmoMode::Translate => self.translate,
+            GizmoMode::Rotate => self.rotate,
+            GizmoMode::Scale => self.scale,
+        }
+    }
+}
+
+fn vector_value(editor_state: &graph::GraphEditorState, input: InputId) -> Option<Vec3> {
+    match editor_state.graph.get_input(input).value() {
+        graph::ValueType::Vector(v) => Some(*v),
+        _ => None,
+    }
+}
+
+/// Projects a point in world space to a screen position inside `rect`.
+/// Returns `None` for points behind the camera.
+fn project(view_proj: Mat4, rect: egui::Rect, point: Vec3) -> Option<egui::Pos2> {
+    let clip = view_proj * point.extend(1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+    let ndc = clip.truncate() / clip.w;
+    Some(egui::pos2(
+        rect.min.x + (ndc.x + 1.0) * 0.5 * rect.width(),
+        rect.min.y + (1.0 - ndc.y) * 0.5 * rect.height(),
+    ))
+}
+
+/// Returns the distance from `p` to the segment going from `a` to `b`
+fn distance_to_segment(p: egui::Pos2, a: egui::Pos2, b: egui::Pos2) -> f32 {
+    let ab = b - a;
+    let t = ((p - a).dot(ab) / ab.length_sq().max(f32::EPSILON)).clamp(0.0, 1.0);
+    (a + ab * t).distance(p)
+}
+
+impl Gizmo {
+    pub fn new() -> Self {
+        Self {
+            mode: GizmoMode::Translate,
+            dragging: None,
+            captures_mouse: false,
+        }
+    }
+
+    pub fn mode_ui(&mut self, ui: &mut egui::Ui) {
+        ui.selectable_value(&mut self.mode, GizmoMode::Translate, "Move");
+        ui.selectable_value(&mut self.mode, GizmoMode::Rotate, "Rotate");
+        ui.selectable_value(&mut self.mode, GizmoMode::Scale, "Scale");
+    }
+
+    /// Draws the gizmo over the viewport `rect` and handles its interaction.
+    /// Dragging an axis writes the new value back into the active node.
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        rect: egui::Rect,
+        view_proj: Mat4,
+        editor_state: &mut graph::GraphEditorState,
+    ) {
+        self.captures_mouse = false;
+        let inputs = match TransformInputs::find(editor_state) {
+            Some(inputs) => inputs,
+            None => {
+                self.dragging = None;
+                return;
+            }
+        };
+        let origin = vector_value(editor_state, inputs.translate).unwrap_or(Vec3::ZERO);
+        let origin_screen = match project(view_proj, rect, origin) {
+            Some(pos) => pos,
+            None => return,
+        };
+        let axes = [Vec3::X, Vec3::Y, Vec3::Z].map(|axis| {
+            project(view_proj, rect, origin + axis * AXIS_LENGTH).unwrap_or(origin_screen)
+        });
+        let colors = [
+            egui::Color32::from_rgb(230, 60, 60),
+            egui::Color32::from_rgb(60, 200, 60),
+            egui::Color32::from_rgb(60, 100, 230),
+        ];
+
+        let pointer = ui.input().pointer.clone();
+        let hovered = pointer
+            .hover_pos()
+            .filter(|p| rect.contains(*p))
+            .and_then(|p| {
+                (0..3)
+                    .map(|i| (i, distance_to_segment(p, origin_screen, axes[i])))
+                    .filter(|(_, d)| *d < HIT_DISTANCE)
+                    .min_by(|(_, d1), (_, d2)| {
+                        d1.partial_cmp(d2).unwrap_or(std::cmp::Ordering::Equal)
+                    })
+                    .map(|(i, _)| i)
+            });
+
+        if pointer.primary_pressed() {
+            self.dragging = hovered;
+        }
+        if !pointer.primary_down() {
+            self.dragging = None;
+        }
+
+        if let Some(axis) = self.dragging {
+            let axis_screen = axes[axis] - origin_screen;
+            let axis_len = axis_screen.length();
+            if axis_len > f32::EPSILON {
+                // Mouse movement along the projected axis, in points
+                let amount = pointer.delta().dot(axis_screen / axis_len);
+                let delta = match self.mode {
+                    GizmoMode::Translate | GizmoMode::Scale => amount * AXIS_LENGTH / axis_len,
+                    GizmoMode::Rotate => amount * ROTATION_SPEED,
+                };
+                let input = inputs.for_mode(self.mode);
+                if let Some(mut value) = vector_value(editor_state, input) {
+                    value[axis] += delta;
+                    editor_state.graph[input].value = graph::ValueType::Vector(value);
+                }
+            }
+        }
+
+        let painter = ui.painter_at(rect);
+        for i in 0..3 {
+            let width = if self.dragging == Some(i) || hovered == Some(i) {
+                4.0
+            } else {
+                2.0
+            };
+            painter.line_segment(
+                [origin_screen, axes[i]],
+                egui::Stroke::new(width, colors[i]),
+            );
+            match self.mode {
+                GizmoMode::Translate => painter.circle_filled(axes[i], width * 2.0, colors[i]),
+                GizmoMode::Rotate => {
+                    painter.circle_stroke(axes[i], width * 2.5, egui::Stroke::new(2.0, colors[i]))
+                }
+                GizmoMode::Scale => painter.rect_filled(
+                    egui::Rect::from_center_size(axes[i], egui::Vec2::splat(width * 3.0)),
+                    0.0,
+                    colors[i],
+                ),
+            }
+        }
+
+        self.captures_mouse = hovered.is_some() || self.dragging.is_some();
+    }
+}
+
+impl Default for Gizmo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
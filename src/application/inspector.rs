@@ -1,6 +1,6 @@
 use crate::prelude::*;
 use egui::*;
-use egui_node_graph::WidgetValueTrait;
+use egui_node_graph::{InputParamKind, WidgetValueTrait};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum InspectorTab {
@@ -21,6 +21,8 @@ impl InspectorTabs {
             properties: PropertiesTab {},
             spreadsheet: SpreadsheetTab {
                 current_view: SpreadsheetViews::Vertices,
+                first_row: 0,
+                edit_mode: false,
             },
         }
     }
@@ -41,8 +43,183 @@ pub enum SpreadsheetViews {
     Faces,
 }
 
+/// A channel value, as edited from the spreadsheet
+#[derive(Clone, Debug)]
+pub enum ChannelEditValue {
+    Vec3(Vec3),
+    F32(f32),
+    I32(i32),
+    String(String),
+}
+
+/// A change made by hand to a channel value from the spreadsheet. The mesh
+/// shown in the inspector is generated again every time the graph runs, so
+/// edits are stored and applied on top of it after every evaluation.
+#[derive(Clone, Debug)]
+pub struct ChannelEdit {
+    pub kty: ChannelKeyType,
+    pub name: String,
+    pub key: slotmap::KeyData,
+    pub value: ChannelEditValue,
+}
+
+fn read_typed<K: ChannelKey, V: ChannelValue>(
+    mesh: &HalfEdgeMesh,
+    name: &str,
+    key: slotmap::KeyData,
+) -> Option<V> {
+    let ch = mesh
+        .channels
+        .dyn_read_channel_by_name(K::key_type(), V::value_type(), name)
+        .ok()?;
+    let ch = ch.as_any().downcast_ref::<Channel<K, V>>()?;
+    Some(ch[K::from(key)].clone())
+}
+
+fn write_typed<K: ChannelKey, V: ChannelValue>(
+    mesh: &HalfEdgeMesh,
+    name: &str,
+    key: slotmap::KeyData,
+    value: V,
+) -> Result<()> {
+    let mut ch = mesh
+        .channels
+        .dyn_write_channel_by_name(K::key_type(), V::value_type(), name)?;
+    let ch = ch
+        .as_any_mut()
+        .downcast_mut::<Channel<K, V>>()
+        .ok_or_else(|| anyhow!("Channel {name} does not have the expected type"))?;
+    ch[K::from(key)] = value;
+    Ok(())
+}
+
+impl ChannelEditValue {
+    fn read_for_key<K: ChannelKey>(
+        mesh: &HalfEdgeMesh,
+        vty: ChannelValueType,
+        name: &str,
+        key: slotmap::KeyData,
+    ) -> Option<Self> {
+        Some(match vty {
+            ChannelValueType::Vec3 => Self::Vec3(read_typed::<K, Vec3>(mesh, name, key)?),
+            ChannelValueType::f32 => Self::F32(read_typed::<K, f32>(mesh, name, key)?),
+            ChannelValueType::i32 => Self::I32(read_typed::<K, i32>(mesh, name, key)?),
+            ChannelValueType::String => Self::String(read_typed::<K, String>(mesh, name, key)?),
+        })
+    }
+
+    pub fn value_type(&self) -> ChannelValueType {
+        match self {
+            Self::Vec3(_) => ChannelValueType::Vec3,
+            Self::F32(_) => ChannelValueType::f32,
+            Self::I32(_) => ChannelValueType::i32,
+            Self::String(_) => ChannelValueType::String,
+        }
+    }
+
+    /// Reads the value of channel `name` for `key`
+    pub fn read(
+        mesh: &HalfEdgeMesh,
+        kty: ChannelKeyType,
+        vty: ChannelValueType,
+        name: &str,
+        key: slotmap::KeyData,
+    ) -> Option<Self> {
+        match kty {
+            ChannelKeyType::VertexId => Self::read_for_key::<VertexId>(mesh, vty, name, key),
+            ChannelKeyType::FaceId => Self::read_for_key::<FaceId>(mesh, vty, name, key),
+            ChannelKeyType::HalfEdgeId => Self::read_for_key::<HalfEdgeId>(mesh, vty, name, key),
+        }
+    }
+
+    fn write_for_key<K: ChannelKey>(
+        &self,
+        mesh: &HalfEdgeMesh,
+        name: &str,
+        key: slotmap::KeyData,
+    ) -> Result<()> {
+        match self {
+            Self::Vec3(v) => write_typed::<K, Vec3>(mesh, name, key, *v),
+            Self::F32(x) => write_typed::<K, f32>(mesh, name, key, *x),
+            Self::I32(x) => write_typed::<K, i32>(mesh, name, key, *x),
+            Self::String(x) => write_typed::<K, String>(mesh, name, key, x.clone()),
+        }
+    }
+
+    /// Draws a widget to edit this value. Returns true when it changed.
+    fn ui(&mut self, ui: &mut Ui) -> bool {
+        match self {
+            Self::Vec3(v) => {
+                ui.horizontal(|ui| {
+                    let x = ui.add(DragValue::new(&mut v.x).speed(0.01)).changed();
+                    let y = ui.add(DragValue::new(&mut v.y).speed(0.01)).changed();
+                    let z = ui.add(DragValue::new(&mut v.z).speed(0.01)).changed();
+                    x || y || z
+                })
+                .inner
+            }
+            Self::F32(x) => ui.add(DragValue::new(x).speed(0.01)).changed(),
+            Self::I32(x) => ui.add(DragValue::new(x)).changed(),
+            Self::String(x) => ui.text_edit_singleline(x).changed(),
+        }
+    }
+}
+
+impl ChannelEdit {
+    /// Channels of different value types can share a name, so the value type
+    /// is needed to tell them apart.
+    fn is_for(
+        &self,
+        kty: ChannelKeyType,
+        vty: ChannelValueType,
+        name: &str,
+        key: slotmap::KeyData,
+    ) -> bool {
+        self.kty == kty && self.value.value_type() == vty && self.name == name && self.key == key
+    }
+
+    /// Writes the edited value into `mesh`. Fails when the edited element is
+    /// not part of the mesh.
+    pub fn apply(&self, mesh: &HalfEdgeMesh) -> Result<()> {
+        let exists = {
+            let conn = mesh.read_connectivity();
+            match self.kty {
+                ChannelKeyType::VertexId => conn.vertex_exists(VertexId::from(self.key)),
+                ChannelKeyType::FaceId => conn.face(FaceId::from(self.key)).is_some(),
+                ChannelKeyType::HalfEdgeId => conn.halfedge(HalfEdgeId::from(self.key)).is_some(),
+            }
+        };
+        if !exists {
+            bail!(
+                "Cannot edit channel {}: element {:?} is not in the mesh",
+                self.name,
+                self.key
+            );
+        }
+        match self.kty {
+            ChannelKeyType::VertexId => self
+                .value
+                .write_for_key::<VertexId>(mesh, &self.name, self.key),
+            ChannelKeyType::FaceId => self
+                .value
+                .write_for_key::<FaceId>(mesh, &self.name, self.key),
+            ChannelKeyType::HalfEdgeId => self
+                .value
+                .write_for_key::<HalfEdgeId>(mesh, &self.name, self.key),
+        }
+    }
+}
+
+/// The number of rows shown at once in the spreadsheet
+const SPREADSHEET_PAGE_SIZE: usize = 100;
+
 pub struct SpreadsheetTab {
     pub current_view: SpreadsheetViews,
+    /// The first row of the page being shown. Only the values in the current
+    /// page are introspected.
+    pub first_row: usize,
+    /// When set, the values are shown as editable widgets
+    pub edit_mode: bool,
 }
 
 impl InspectorTabs {
@@ -50,6 +227,7 @@ impl InspectorTabs {
         &mut self,
         ui: &mut Ui,
         mesh: Option<&HalfEdgeMesh>,
+        channel_edits: &mut Vec<ChannelEdit>,
         editor_state: &mut graph::GraphEditorState,
     ) {
         ui.horizontal(|ui| {
@@ -67,7 +245,7 @@ impl InspectorTabs {
         ui.separator();
         match self.current_view {
             InspectorTab::Properties => self.properties.ui(ui, editor_state),
-            InspectorTab::Spreadsheet => self.spreadsheet.ui(ui, mesh),
+            InspectorTab::Spreadsheet => self.spreadsheet.ui(ui, mesh, channel_edits),
         }
     }
 }
@@ -91,10 +269,22 @@ impl PropertiesTab {
                     if graph.connection(param).is_some() {
                         ui.label(param_name);
                     } else {
-                        ui.horizontal(|ui| {
-                            tiny_checkbox(ui, &mut graph[param].shown_inline);
-                            graph[param].value.value_widget(&param_name, ui);
-                        });
+                        let response = ui
+                            .horizontal(|ui| {
+                                tiny_checkbox(ui, &mut graph[param].shown_inline);
+                                graph[param].value.value_widget(&param_name, ui);
+                            })
+                            .response;
+                        // Constant-only parameters have no port. Let the user
+                        // add one, so the value can come from a connection.
+                        if matches!(graph[param].kind, InputParamKind::ConstantOnly) {
+                            response.context_menu(|ui| {
+                                if ui.button("Convert to input").clicked() {
+                                    graph[param].kind = InputParamKind::ConnectionOrConstant;
+                                    ui.close_menu();
+                                }
+                            });
+                        }
                     }
                 }
             });
@@ -104,7 +294,13 @@ impl PropertiesTab {
     }
 }
 impl SpreadsheetTab {
-    fn ui(&mut self, ui: &mut Ui, mesh: Option<&HalfEdgeMesh>) {
+    fn ui(
+        &mut self,
+        ui: &mut Ui,
+        mesh: Option<&HalfEdgeMesh>,
+        channel_edits: &mut Vec<ChannelEdit>,
+    ) {
+        let previous_view = self.current_view;
         ui.horizontal(|ui| {
             ui.selectable_value(
                 &mut self.current_view,
@@ -118,58 +314,113 @@ impl SpreadsheetTab {
                 "Half edges",
             );
         });
+        if self.current_view != previous_view {
+            self.first_row = 0;
+        }
 
         if let Some(mesh) = mesh {
-            let channel_introspect = mesh.channels.introspect(mesh.gen_introspect_fn());
+            let kt = match self.current_view {
+                SpreadsheetViews::Vertices => ChannelKeyType::VertexId,
+                SpreadsheetViews::Halfedges => ChannelKeyType::HalfEdgeId,
+                SpreadsheetViews::Faces => ChannelKeyType::FaceId,
+            };
+            let keys = mesh.gen_introspect_fn()(kt);
+            let num_rows = keys.len();
 
-            let scroll_area = ScrollArea::both().auto_shrink([false, false]);
-            scroll_area.show(ui, |ui| {
-                let mut columns = vec![];
-                let kt = match self.current_view {
-                    SpreadsheetViews::Vertices => ChannelKeyType::VertexId,
-                    SpreadsheetViews::Halfedges => ChannelKeyType::HalfEdgeId,
-                    SpreadsheetViews::Faces => ChannelKeyType::FaceId,
-                };
-                for vt in [ChannelValueType::Vec3, ChannelValueType::f32] {
-                    if let Some(ch) = channel_introspect.get(&(kt, vt)) {
-                        for (ch_name, ch_contents) in ch.iter() {
-                            columns.push((ch_name, ch_contents));
-                        }
+            // Keep the page in range when the mesh shrinks
+            let last_page = num_rows.saturating_sub(1) / SPREADSHEET_PAGE_SIZE;
+            self.first_row = self.first_row.min(last_page * SPREADSHEET_PAGE_SIZE);
+            let rows = self.first_row..(self.first_row + SPREADSHEET_PAGE_SIZE).min(num_rows);
+            ui.horizontal(|ui| {
+                if ui.button("◀").clicked() {
+                    self.first_row = self.first_row.saturating_sub(SPREADSHEET_PAGE_SIZE);
+                }
+                ui.label(format!(
+                    "Rows {}-{} of {}",
+                    rows.start,
+                    rows.end.saturating_sub(1),
+                    num_rows
+                ));
+                if ui.button("▶").clicked() && rows.end < num_rows {
+                    self.first_row += SPREADSHEET_PAGE_SIZE;
+                }
+                ui.separator();
+                ui.checkbox(&mut self.edit_mode, "Edit values");
+                if !channel_edits.is_empty()
+                    && ui
+                        .button(format!("Discard {} edits", channel_edits.len()))
+                        .clicked()
+                {
+                    channel_edits.clear();
+                }
+            });
+
+            let mut columns = vec![];
+            for vt in [
+                ChannelValueType::Vec3,
+                ChannelValueType::f32,
+                ChannelValueType::i32,
+                ChannelValueType::String,
+            ] {
+                for ch_name in mesh.channels.channel_names_dyn(kt, vt) {
+                    if let Ok(ch_contents) = mesh.channels.introspect_range(
+                        kt,
+                        vt,
+                        &ch_name,
+                        &keys,
+                        rows.start,
+                        rows.len(),
+                    ) {
+                        columns.push((vt, ch_name, ch_contents));
                     }
                 }
+            }
 
+            let scroll_area = ScrollArea::both().auto_shrink([false, false]);
+            scroll_area.show(ui, |ui| {
                 Grid::new("vertex-spreadsheet")
                     .striped(true)
                     .num_columns(columns.len())
                     .show(ui, |ui| {
                         ui.label(" ");
-                        for c in &columns {
-                            ui.label(c.0);
+                        for (_, ch_name, _) in &columns {
+                            ui.label(ch_name);
                         }
                         ui.end_row();
 
-                        if !columns.is_empty() {
-                            for i in 0..columns[0].1.len() {
-                                ui.label(i.to_string());
-                                for c in &columns {
-                                    ui.monospace(c.1[i].clone() + " |");
-                                }
-                                ui.end_row();
-                            }
-                        } else {
-                            let count = match self.current_view {
-                                SpreadsheetViews::Vertices => {
-                                    mesh.read_connectivity().num_vertices()
+                        for (i, row) in rows.enumerate() {
+                            ui.label(row.to_string());
+                            for (vt, ch_name, ch_contents) in &columns {
+                                if !self.edit_mode {
+                                    ui.monospace(ch_contents[i].clone() + " |");
+                                    continue;
                                 }
-                                SpreadsheetViews::Halfedges => {
-                                    mesh.read_connectivity().num_halfedges()
+                                let key = keys[row];
+                                // Pending edits are only written to the mesh
+                                // the next time the graph runs.
+                                let value = channel_edits
+                                    .iter()
+                                    .find(|edit| edit.is_for(kt, *vt, ch_name, key))
+                                    .map(|edit| edit.value.clone())
+                                    .or_else(|| {
+                                        ChannelEditValue::read(mesh, kt, *vt, ch_name, key)
+                                    });
+                                if let Some(mut value) = value {
+                                    if value.ui(ui) {
+                                        channel_edits
+                                            .retain(|edit| !edit.is_for(kt, *vt, ch_name, key));
+                                        channel_edits.push(ChannelEdit {
+                                            kty: kt,
+                                            name: ch_name.clone(),
+                                            key,
+                                            value,
+                                        });
+                                    }
+                                } else {
+                                    ui.label("-");
                                 }
-                                SpreadsheetViews::Faces => mesh.read_connectivity().num_faces(),
-                            };
-                            for i in 0..count {
-                                ui.label(i.to_string());
-                                ui.end_row();
                             }
+                            ui.end_row();
                         }
                     })
             });
@@ -0,0 +1,128 @@
+use crate::{lua_engine::LuaRuntime, prelude::*};
+
+/// A line of text in the console's output log
+enum ConsoleLine {
+    Input(String),
+    Output(String),
+    Error(String),
+}
+
+/// An interactive Lua console. Code typed in the console runs in the same Lua
+/// engine used by the node libraries, with a copy of the active mesh
+/// available as the `mesh` global.
+#[derive(Default)]
+pub struct LuaConsole {
+    pub open: bool,
+    input: String,
+    log: Vec<ConsoleLine>,
+}
+
+impl LuaConsole {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the given code. Values returned by the code, as well as any calls
+    /// to `print`, are written to the console's output log.
+    fn run(&mut self, code: &str, lua_runtime: &LuaRuntime, mesh: Option<&HalfEdgeMesh>) {
+        self.log.push(ConsoleLine::Input(code.to_string()));
+
+        let mut printed = vec![];
+        let result = eval_code(&lua_runtime.lua, code, mesh, &mut printed);
+
+        self.log
+            .extend(printed.into_iter().map(ConsoleLine::Output));
+        match result {
+            Ok(values) if !values.is_empty() => {
+                self.log.push(ConsoleLine::Output(values.join("\t")));
+            }
+            Ok(_) => {}
+            Err(err) => self.log.push(ConsoleLine::Error(err.to_string())),
+        }
+    }
+
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        lua_runtime: &LuaRuntime,
+        mesh: Option<&HalfEdgeMesh>,
+    ) {
+        let mut open = self.open;
+        egui::Window::new("Lua console")
+            .open(&mut open)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for line in &self.log {
+                            match line {
+                                ConsoleLine::Input(code) => {
+                                    ui.monospace(format!("> {}", code));
+                                }
+                                ConsoleLine::Output(text) => {
+                                    ui.monospace(text);
+                                }
+                                ConsoleLine::Error(err) => {
+                                    ui.colored_label(egui::Color32::RED, err);
+                                }
+                            }
+                        }
+                    });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.input)
+                            .code_editor()
+                            .desired_width(ui.available_width() - 60.0),
+                    );
+                    let submitted =
+                        response.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+                    if (ui.button("Run").clicked() || submitted) && !self.input.trim().is_empty() {
+                        let code = std::mem::take(&mut self.input);
+                        self.run(&code, lua_runtime, mesh);
+                        response.request_focus();
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.log.clear();
+                    }
+                });
+            });
+        self.open = open;
+    }
+}
+
+/// Evaluates `code` with the `mesh` global set to a copy of the given mesh.
+/// Any lines printed by the code get appended to `printed`. Returns the values
+/// returned by the code, converted to strings.
+fn eval_code(
+    lua: &mlua::Lua,
+    code: &str,
+    mesh: Option<&HalfEdgeMesh>,
+    printed: &mut Vec<String>,
+) -> mlua::Result<Vec<String>> {
+    lua.globals().set("mesh", mesh.cloned())?;
+    let old_print: mlua::Value = lua.globals().get("print")?;
+    let result = lua.scope(|scope| {
+        lua.globals().set(
+            "print",
+            scope.create_function_mut(|lua, args: mlua::MultiValue| {
+                printed.push(values_to_strings(lua, args)?.join("\t"));
+                Ok(())
+            })?,
+        )?;
+        let values = lua.load(code).set_name("console")?.eval()?;
+        values_to_strings(lua, values)
+    });
+    lua.globals().set("print", old_print)?;
+    result
+}
+
+/// Converts a list of lua values to strings, using lua's `tostring`
+fn values_to_strings(lua: &mlua::Lua, values: mlua::MultiValue) -> mlua::Result<Vec<String>> {
+    let tostring: mlua::Function = lua.globals().get("tostring")?;
+    values
+        .into_iter()
+        .map(|v| tostring.call::<_, String>(v))
+        .collect()
+}
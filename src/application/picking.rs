@@ -0,0 +1,171 @@
+use crate::prelude::*;
+use halfedge::{
+    accel::BvhAccel,
+    selection::{SelectionExpression, SelectionKind},
+};
+
+/// Returns the ray that goes from the camera through `pos`, a screen position
+/// inside the viewport `rect`, as an `(origin, direction)` pair.
+pub fn screen_ray(view: Mat4, view_proj: Mat4, rect: egui::Rect, pos: egui::Pos2) -> (Vec3, Vec3) {
+    let ndc = Vec3::new(
+        (pos.x - rect.min.x) / rect.width() * 2.0 - 1.0,
+        1.0 - (pos.y - rect.min.y) / rect.height() * 2.0,
+        // Any depth in front of the camera works, only the direction matters
+        0.5,
+    );
+    let eye = view.inverse().transform_point3(Vec3::ZERO);
+    let target = view_proj.inverse().project_point3(ndc);
+    (eye, (target - eye).normalize_or_zero())
+}
+
+/// Keeps the acceleration structure used for picking between clicks. Nodes
+/// are evaluated every frame and produce a new mesh each time, so the cache
+/// is keyed by a fingerprint of the mesh's elements, and the structure only
+/// gets rebuilt when they change.
+#[derive(Default)]
+pub struct PickingCache {
+    fingerprint: u64,
+    accel: Option<BvhAccel>,
+}
+
+impl PickingCache {
+    /// Returns the acceleration structure for `mesh`, building it if the mesh
+    /// changed since the last call.
+    pub fn accel(&mut self, mesh: &HalfEdgeMesh) -> &BvhAccel {
+        let fingerprint = mesh_fingerprint(mesh);
+        if self.accel.is_none() || self.fingerprint != fingerprint {
+            self.fingerprint = fingerprint;
+            self.accel = Some(BvhAccel::build(mesh));
+        }
+        self.accel.as_ref().unwrap()
+    }
+}
+
+/// Hashes the vertex positions and the faces of `mesh`, which is all the data
+/// a [`BvhAccel`] is built from.
+fn mesh_fingerprint(mesh: &HalfEdgeMesh) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let conn = mesh.read_connectivity();
+    let positions = mesh.read_positions();
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for (v, _, pos) in conn.iter_vertices_with_channel(&positions) {
+        v.hash(&mut hasher);
+        pos.to_array().map(f32::to_bits).hash(&mut hasher);
+    }
+    for (face, _) in conn.iter_faces() {
+        face.hash(&mut hasher);
+        conn.face_vertices(face).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Finds the element of the given `kind` under the ray. Returns the index of
+/// the element, as used in selection expressions.
+///
+/// The ray is cast against the faces of the mesh. For vertices and edges, the
+/// element of the hit face closest to the hit point is picked.
+pub fn pick_element(
+    mesh: &HalfEdgeMesh,
+    accel: &BvhAccel,
+    origin: Vec3,
+    dir: Vec3,
+    kind: SelectionKind,
+) -> Option<u32> {
+    let (face, hit) = accel.raycast(origin, dir)?;
+    let conn = mesh.read_connectivity();
+    let positions = mesh.read_positions();
+    let closest = |a: &f32, b: &f32| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal);
+    let index = match kind {
+        SelectionKind::Faces => conn.iter_faces().position(|(f, _)| f == face)?,
+        SelectionKind::Vertices => {
+            let vertex = conn.face_vertices(face).into_iter().min_by(|a, b| {
+                closest(&positions[*a].distance(hit), &positions[*b].distance(hit))
+            })?;
+            conn.iter_vertices().position(|(v, _)| v == vertex)?
+        }
+        SelectionKind::Edges | SelectionKind::HalfEdges => {
+            let distance = |h: HalfEdgeId| {
+                let (src, dst) = conn.at_halfedge(h).src_dst_pair().ok()?;
+                let (a, b) = (positions[src], positions[dst]);
+                let t = ((hit - a).dot(b - a) / (b - a).length_squared().max(f32::EPSILON))
+                    .clamp(0.0, 1.0);
+                Some(a.lerp(b, t).distance(hit))
+            };
+            let halfedge = conn
+                .face_edges(face)
+                .into_iter()
+                .filter_map(|h| Some((h, distance(h)?)))
+                .min_by(|(_, a), (_, b)| closest(a, b))
+                .map(|(h, _)| h)?;
+            conn.iter_halfedges().position(|(h, _)| h == halfedge)?
+        }
+    };
+    Some(index as u32)
+}
+
+/// Writes the picked element `index` into the first selection parameter of
+/// the active node. When `extend` is set, the element is added to the current
+/// selection instead of replacing it.
+pub fn select_in_active_node(editor_state: &mut graph::GraphEditorState, index: u32, extend: bool) {
+    let active = match editor_state.user_state.active_node {
+        Some(active) => active,
+        None => return,
+    };
+    let param = editor_state.graph[active]
+        .inputs
+        .iter()
+        .map(|(_, input)| *input)
+        .find(|input| {
+            matches!(
+                editor_state.graph[*input].value,
+                graph::ValueType::Selection { .. }
+            )
+        });
+    if let Some(param) = param {
+        if let graph::ValueType::Selection { text, selection } =
+            &mut editor_state.graph[param].value
+        {
+            let already_selected = text
+                .split(',')
+                .any(|fragment| fragment.trim() == index.to_string());
+            if !extend || text.trim().is_empty() {
+                *text = index.to_string();
+            } else if !already_selected && text.trim() != "*" {
+                *text = format!("{}, {}", text.trim(), index);
+            }
+            *selection = SelectionExpression::parse(text).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_picking_cache() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let mut cache = PickingCache::default();
+
+        // A ray going down through the top face
+        let pick = |cache: &mut PickingCache, mesh: &HalfEdgeMesh| {
+            let accel = cache.accel(mesh);
+            pick_element(mesh, accel, Vec3::Y * 2.0, -Vec3::Y, SelectionKind::Faces)
+        };
+        let top = pick(&mut cache, &mesh);
+        assert!(top.is_some());
+        let fingerprint = cache.fingerprint;
+
+        // Evaluating the same node again gives an equal mesh, which reuses
+        // the cached structure
+        let same = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        assert_eq!(pick(&mut cache, &same), top);
+        assert_eq!(cache.fingerprint, fingerprint);
+
+        // Moving the mesh out of the ray's way rebuilds it
+        let moved = primitives::Box::build(Vec3::X * 5.0, Vec3::ONE);
+        assert_eq!(pick(&mut cache, &moved), None);
+        assert_ne!(cache.fingerprint, fingerprint);
+    }
+}
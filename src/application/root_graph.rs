@@ -5,6 +5,7 @@ impl RootViewport {
         &'node mut self,
         graph: &mut r3::RenderGraph<'node>,
         ready: &r3::ReadyData,
+        thumbnail: Option<&'node ThumbnailReadback>,
         viewport_routines: ViewportRoutines<'node>,
         output: r3::RenderTargetHandle,
     ) {
@@ -28,6 +29,9 @@ impl RootViewport {
             parent_scale,
         );
         let viewport_3d_texture = viewport_3d.add_to_graph(graph, ready, viewport_routines);
+        if let Some(thumbnail) = thumbnail {
+            thumbnail.add_to_graph(graph, viewport_3d_texture);
+        }
 
         // --- Draw parent UI ---
         let (_output, paint_commands) = platform.end_frame(None);
@@ -114,9 +118,10 @@ impl RootViewport {
         &'node mut self,
         graph: &mut r3::RenderGraph<'node>,
         ready: &r3::ReadyData,
+        thumbnail: Option<&'node ThumbnailReadback>,
         viewport_routines: ViewportRoutines<'node>,
     ) {
         let output = graph.add_surface_texture();
-        self.add_draw_to_graph(graph, ready, viewport_routines, output);
+        self.add_draw_to_graph(graph, ready, thumbnail, viewport_routines, output);
     }
 }
@@ -5,6 +5,21 @@ pub enum AppRootAction {
     Save(PathBuf),
     Load(PathBuf),
     SetCodeViewerCode(String),
+    /// Imports the OBJ file at the given path as a new static mesh node
+    ImportObj(PathBuf),
+    /// Exports the current mesh as an OBJ file at the given path
+    ExportObj(PathBuf),
+    /// Exports the current mesh as an STL file at the given path. The
+    /// boolean selects between binary and ASCII STL.
+    ExportStl(PathBuf, bool),
+    /// Exports every frame of the animation as a numbered OBJ file inside the
+    /// given folder.
+    ExportObjSequence(PathBuf),
+    /// Copies the OBJ representation of the current mesh to the clipboard
+    CopyMeshAsObj,
+    /// Writes a standalone Lua script that evaluates the graph up to the
+    /// active node to the given path
+    ExportLua(PathBuf),
 }
 
 impl RootViewport {
@@ -31,12 +46,100 @@ impl RootViewport {
                         action = Some(AppRootAction::Load(path))
                     }
                 }
+                if ui.button("Import OBJ...").clicked() {
+                    let file_location = rfd::FileDialog::new()
+                        .add_filter("Wavefront OBJ", &["obj"])
+                        .pick_file();
+                    if let Some(path) = file_location {
+                        action = Some(AppRootAction::ImportObj(path))
+                    }
+                }
+                if ui.button("Export OBJ...").clicked() {
+                    let file_location = rfd::FileDialog::new()
+                        .set_file_name("Untitled.obj")
+                        .add_filter("Wavefront OBJ", &["obj"])
+                        .save_file();
+                    if let Some(path) = file_location {
+                        action = Some(AppRootAction::ExportObj(path))
+                    }
+                }
+                ui.menu_button("Export STL", |ui| {
+                    for (label, binary) in [("Binary...", true), ("ASCII...", false)] {
+                        if ui.button(label).clicked() {
+                            let file_location = rfd::FileDialog::new()
+                                .set_file_name("Untitled.stl")
+                                .add_filter("STL", &["stl"])
+                                .save_file();
+                            if let Some(path) = file_location {
+                                action = Some(AppRootAction::ExportStl(path, binary))
+                            }
+                        }
+                    }
+                });
+                if ui.button("Export animation as OBJ sequence...").clicked() {
+                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                        action = Some(AppRootAction::ExportObjSequence(path))
+                    }
+                }
+                if ui.button("Copy mesh as OBJ").clicked() {
+                    action = Some(AppRootAction::CopyMeshAsObj)
+                }
+                if ui.button("Bake to Lua...").clicked() {
+                    let file_location = rfd::FileDialog::new()
+                        .set_file_name("Untitled.lua")
+                        .add_filter("Lua script", &["lua"])
+                        .save_file();
+                    if let Some(path) = file_location {
+                        action = Some(AppRootAction::ExportLua(path))
+                    }
+                }
+            });
+            ui.menu_button("Settings", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Max elements per operation:");
+                    let mut limit = crate::utils::element_limit();
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut limit)
+                                .speed(1000)
+                                .clamp_range(1000..=crate::utils::MAX_ELEMENT_LIMIT),
+                        )
+                        .changed()
+                    {
+                        crate::utils::set_element_limit(limit);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Autosave:");
+                    let mut enabled = self.autosave_interval.is_some();
+                    ui.checkbox(&mut enabled, "");
+                    if enabled {
+                        let mut secs = self
+                            .autosave_interval
+                            .unwrap_or(DEFAULT_AUTOSAVE_INTERVAL)
+                            .as_secs();
+                        ui.add(
+                            egui::DragValue::new(&mut secs)
+                                .clamp_range(5..=3600)
+                                .prefix("every ")
+                                .suffix(" s"),
+                        );
+                        self.autosave_interval = Some(std::time::Duration::from_secs(secs));
+                    } else {
+                        self.autosave_interval = None;
+                    }
+                });
+                ui.menu_button("Port colors", |ui| {
+                    self.graph_editor.state.user_state.data_type_colors.ui(ui);
+                });
             });
             ui.menu_button("Help", |ui| {
                 if ui.button("Diagnosics").clicked() {
                     self.diagnostics_open = true;
                 } else if ui.button("View graph source").clicked() {
                     self.code_viewer_open = true;
+                } else if ui.button("Lua console").clicked() {
+                    self.lua_console.open = true;
                 }
             });
         });
@@ -49,6 +152,122 @@ impl RootViewport {
             .open(&mut self.diagnostics_open)
             .show(ctx, |ui| {
                 ui.label(format!("HiDPI scale: {}", ui.ctx().pixels_per_point()));
+                if let (Some(min), Some(max)) =
+                    (self.frame_times.iter().min(), self.frame_times.iter().max())
+                {
+                    let avg = self.frame_times.iter().sum::<std::time::Duration>()
+                        / self.frame_times.len() as u32;
+                    ui.label(format!(
+                        "Frame time: {:.2}ms avg, {:.2}ms min, {:.2}ms max",
+                        avg.as_secs_f32() * 1000.0,
+                        min.as_secs_f32() * 1000.0,
+                        max.as_secs_f32() * 1000.0,
+                    ));
+                    ui.label(format!(
+                        "FPS (without cap): {:.0}",
+                        1.0 / avg.as_secs_f32().max(f32::EPSILON)
+                    ));
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Frame rate cap:");
+                    let mut capped = self.target_fps.is_some();
+                    ui.checkbox(&mut capped, "");
+                    if capped {
+                        let mut fps = self.target_fps.unwrap_or(DEFAULT_TARGET_FPS);
+                        ui.add(
+                            egui::DragValue::new(&mut fps)
+                                .clamp_range(1..=500)
+                                .suffix(" fps"),
+                        );
+                        self.target_fps = Some(fps);
+                    } else {
+                        self.target_fps = None;
+                        ui.label("Uncapped");
+                    }
+                });
+            });
+    }
+
+    /// Asks the user whether to recover an autosave found on startup
+    pub fn recovery_ui(&mut self, ctx: &egui::CtxRef) {
+        let path = match &self.recovery_prompt {
+            Some(path) => path.clone(),
+            None => return,
+        };
+        egui::Window::new("Recover autosave")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("An autosave with unsaved changes was found:");
+                ui.monospace(path.display().to_string());
+                ui.horizontal(|ui| {
+                    if ui.button("Recover").clicked() {
+                        match serialization::load(path.clone()) {
+                            Ok(state) => {
+                                self.graph_editor.state = state;
+                                // The recovered changes are autosaved again
+                                // by this instance from now on.
+                                if let Err(err) = std::fs::remove_file(&path) {
+                                    eprintln!("There was an error removing the autosave: {}", err)
+                                }
+                            }
+                            Err(err) => {
+                                eprintln!("There was an error recovering the autosave: {}", err)
+                            }
+                        }
+                        self.recovery_prompt = None;
+                    }
+                    if ui.button("Discard").clicked() {
+                        if let Err(err) = std::fs::remove_file(&path) {
+                            eprintln!("There was an error removing the autosave: {}", err)
+                        }
+                        self.recovery_prompt = None;
+                    }
+                });
+            });
+    }
+
+    /// Reports the error of a failed save to the user
+    pub fn save_error_ui(&mut self, ctx: &egui::CtxRef) {
+        let error = match &self.save_error {
+            Some(error) => error.clone(),
+            None => return,
+        };
+        egui::Window::new("Save failed")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(error);
+                if ui.button("Close").clicked() {
+                    self.save_error = None;
+                }
+            });
+    }
+
+    /// Shows the progress of the active node evaluation, with a button to
+    /// cancel it. Quick evaluations finish before the window shows up.
+    pub fn evaluation_progress_ui(&mut self, ctx: &egui::CtxRef) {
+        const SHOW_AFTER: std::time::Duration = std::time::Duration::from_millis(300);
+        let evaluator = &mut self.app_context.evaluator;
+        match evaluator.running_time() {
+            Some(time) if time >= SHOW_AFTER => {}
+            _ => return,
+        }
+        egui::Window::new("Evaluating")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::ProgressBar::new(evaluator.fraction())
+                            .show_percentage()
+                            .desired_width(200.0),
+                    );
+                    if ui.button("Cancel").clicked() {
+                        evaluator.cancel();
+                    }
+                });
             });
     }
 
@@ -73,6 +292,8 @@ impl RootViewport {
                         .offscreen_viewports
                         .get_mut(&OffscreenViewport::Viewport3d)
                         .unwrap(),
+                    &mut payload.graph_editor.state,
+                    payload.app_context.mesh.as_ref(),
                 );
             }
             "graph_editor" => {
@@ -85,6 +306,7 @@ impl RootViewport {
             "inspector" => payload.inspector_tabs.ui(
                 ui,
                 payload.app_context.mesh.as_ref(),
+                &mut payload.app_context.channel_edits,
                 &mut payload.graph_editor.state,
             ),
             _ => panic!("Invalid split name {}", name),
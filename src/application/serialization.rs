@@ -1,36 +1,81 @@
 use crate::{prelude::graph::*, prelude::*};
-use std::path::PathBuf;
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use egui_node_graph::PanZoom;
 use serde::{Deserialize, Serialize};
 use slotmap::SecondaryMap;
 
+/// The version of the file format written by [`save`]. Bump it whenever a
+/// change to the format needs older files to be migrated, and add the
+/// migration to [`SerializedEditorState::migrate`].
+///
+/// Files saved before the format was versioned have no version, and are read
+/// as version 0.
+const FORMAT_VERSION: u32 = 1;
+
+/// The first field of every saved file. It is read on its own before the rest
+/// of the file, so loading can pick how to read a file based on its version.
+#[derive(Deserialize)]
+struct FormatHeader {
+    #[serde(default)]
+    version: u32,
+}
+
 /// We don't serialize the whole editor state. Instead, we serialize just a few
 /// select fields.
+///
+/// Fields added after the first version must have a default, so that files
+/// without them keep loading.
 #[derive(Serialize, Deserialize)]
 struct SerializedEditorState {
+    #[serde(default)]
+    pub version: u32,
     pub graph: graph::Graph,
     pub node_order: Option<Vec<NodeId>>,
     pub active_node: Option<NodeId>,
     pub node_positions: SecondaryMap<NodeId, egui::Pos2>,
     pub pan_zoom: PanZoom,
+    #[serde(default)]
+    pub data_type_colors: DataTypeColors,
 }
 
 impl SerializedEditorState {
     pub fn from_state(editor_state: &GraphEditorState) -> Self {
         SerializedEditorState {
+            version: FORMAT_VERSION,
             graph: editor_state.graph.clone(),
             node_order: Some(editor_state.node_order.clone()),
             active_node: editor_state.user_state.active_node,
             node_positions: editor_state.node_positions.clone(),
             pan_zoom: editor_state.pan_zoom,
+            data_type_colors: editor_state.user_state.data_type_colors,
         }
     }
 
+    /// Upgrades the state read from a file saved with an older version of the
+    /// format to the current one, one version at a time.
+    fn migrate(mut self) -> Result<Self> {
+        while self.version < FORMAT_VERSION {
+            match self.version {
+                // Version 1 only added the version field itself
+                0 => {}
+                version => bail!("No migration for version {} of the file format", version),
+            }
+            self.version += 1;
+        }
+        Ok(self)
+    }
+
     pub fn into_state(self) -> GraphEditorState {
         let user_state = CustomGraphState {
             run_side_effect: None,
             active_node: self.active_node,
+            freeze_node: None,
+            data_type_colors: self.data_type_colors,
+            ..Default::default()
         };
 
         let mut state = GraphEditorState::new(1.0, user_state);
@@ -44,14 +89,291 @@ impl SerializedEditorState {
     }
 }
 
-pub fn save(editor_state: &GraphEditorState, path: PathBuf) -> Result<()> {
-    let writer = std::io::BufWriter::new(std::fs::File::create(path)?);
-    ron::ser::to_writer(writer, &SerializedEditorState::from_state(editor_state))?;
+/// Marks the line storing the file's thumbnail. The thumbnail is appended
+/// after the graph as a RON comment, so loaders that don't know about it skip
+/// it. The version lets the encoding change without breaking older loaders,
+/// which ignore tags they don't recognize.
+const THUMBNAIL_TAG: &str = "// thumbnail v1: ";
+
+/// Saves the editor state to `path`. When given, `thumbnail` is stored in the
+/// file too, as PNG-encoded bytes.
+pub fn save(
+    editor_state: &GraphEditorState,
+    path: PathBuf,
+    thumbnail: Option<&[u8]>,
+) -> Result<()> {
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    ron::ser::to_writer(
+        &mut writer,
+        &SerializedEditorState::from_state(editor_state),
+    )?;
+    if let Some(thumbnail) = thumbnail {
+        write!(writer, "\n{}", THUMBNAIL_TAG)?;
+        for byte in thumbnail {
+            write!(writer, "{:02x}", byte)?;
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()?;
     Ok(())
 }
 
 pub fn load(path: PathBuf) -> Result<GraphEditorState> {
-    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
-    let state: SerializedEditorState = ron::de::from_reader(reader)?;
-    Ok(state.into_state())
+    let contents = std::fs::read_to_string(&path)?;
+    Ok(deserialize(&contents)
+        .with_context(|| format!("Could not load {:?}", path))?
+        .into_state())
+}
+
+fn deserialize(contents: &str) -> Result<SerializedEditorState> {
+    let header: FormatHeader = ron::de::from_str(contents)?;
+    if header.version > FORMAT_VERSION {
+        bail!(
+            "The file was saved with a newer version of blackjack (file format version {}, \
+             but only up to {} is supported)",
+            header.version,
+            FORMAT_VERSION
+        );
+    }
+    let state: SerializedEditorState = ron::de::from_str(contents)?;
+    state.migrate()
+}
+
+/// Returns the path of the autosave file for a file saved at `saved_path`.
+/// This is the same path with an `.autosave` extension appended. Unsaved
+/// files are autosaved in the system's temp directory, in a file named after
+/// the process id so running instances don't overwrite each other's.
+pub fn autosave_path(saved_path: Option<&Path>) -> PathBuf {
+    match saved_path {
+        Some(path) => {
+            let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+            file_name.push(".autosave");
+            path.with_file_name(file_name)
+        }
+        None => untitled_autosave_path(std::process::id()),
+    }
+}
+
+fn untitled_autosave_path(pid: u32) -> PathBuf {
+    std::env::temp_dir().join(format!("Untitled-{}.blj.autosave", pid))
+}
+
+/// Returns the process id in the name of an autosave file for unsaved files.
+fn untitled_autosave_pid(path: &Path) -> Option<u32> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix("Untitled-")?
+        .strip_suffix(".blj.autosave")?
+        .parse()
+        .ok()
+}
+
+fn session_lock_path(pid: u32) -> PathBuf {
+    std::env::temp_dir().join(format!("Untitled-{}.blj.lock", pid))
+}
+
+/// Marks the autosave for unsaved files of a process as in use. The file lock
+/// is held for as long as this value lives, and the OS releases it if the
+/// process crashes, so other instances can tell whether an autosave was
+/// abandoned.
+pub struct SessionLock {
+    _file: std::fs::File,
+    path: PathBuf,
+}
+
+impl SessionLock {
+    /// Locks the session of the current process
+    pub fn acquire() -> Result<Self> {
+        Self::acquire_for(std::process::id())
+    }
+
+    fn acquire_for(pid: u32) -> Result<Self> {
+        let path = session_lock_path(pid);
+        let file = std::fs::File::create(&path)?;
+        file.try_lock()?;
+        Ok(Self { _file: file, path })
+    }
+}
+
+impl Drop for SessionLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Returns whether the process with the given id holds its [`SessionLock`]
+fn is_session_running(pid: u32) -> bool {
+    match std::fs::File::open(session_lock_path(pid)) {
+        Ok(file) => matches!(file.try_lock(), Err(std::fs::TryLockError::WouldBlock)),
+        Err(_) => false,
+    }
+}
+
+/// Returns the path of the autosave file for `saved_path` if it exists and is
+/// newer than the saved file, meaning it has changes that were never saved.
+///
+/// For unsaved files, this is the newest autosave left behind by an instance
+/// that is no longer running.
+pub fn recoverable_autosave(saved_path: Option<&Path>) -> Option<PathBuf> {
+    let modified = |path: &Path| std::fs::metadata(path).ok()?.modified().ok();
+    let saved_path = match saved_path {
+        Some(path) => path,
+        None => {
+            return std::fs::read_dir(std::env::temp_dir())
+                .ok()?
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| match untitled_autosave_pid(path) {
+                    Some(pid) => pid != std::process::id() && !is_session_running(pid),
+                    None => false,
+                })
+                .filter_map(|path| Some((modified(&path)?, path)))
+                .max_by_key(|(time, _)| *time)
+                .map(|(_, path)| path);
+        }
+    };
+    let autosave = autosave_path(Some(saved_path));
+    let autosave_time = modified(&autosave)?;
+    match modified(saved_path) {
+        Some(saved_time) if saved_time >= autosave_time => None,
+        _ => Some(autosave),
+    }
+}
+
+/// Deletes the autosave file for `saved_path`, if there is one. Called once
+/// its contents are no longer needed, so they don't get offered for recovery
+/// again on the next start.
+pub fn remove_autosave(saved_path: Option<&Path>) -> Result<()> {
+    match std::fs::remove_file(autosave_path(saved_path)) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_save_thumbnail() {
+        let dir = std::env::temp_dir();
+        let state = GraphEditorState::new(1.0, CustomGraphState::default());
+
+        let path = dir.join("blackjack_test_thumbnail.blj");
+        let thumbnail = [0x89, b'P', b'N', b'G', 0x00, 0xff];
+        let thumbnail_line = |path: &Path| {
+            std::fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .find_map(|line| line.strip_prefix(THUMBNAIL_TAG).map(str::to_owned))
+        };
+
+        save(&state, path.clone(), Some(&thumbnail)).unwrap();
+        assert_eq!(thumbnail_line(&path).as_deref(), Some("89504e4700ff"));
+        // The thumbnail doesn't get in the way of loading the graph
+        assert!(load(path.clone()).is_ok());
+
+        save(&state, path.clone(), None).unwrap();
+        assert_eq!(thumbnail_line(&path), None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    pub fn test_format_version() {
+        let state = GraphEditorState::new(1.0, CustomGraphState::default());
+        let saved = ron::ser::to_string(&SerializedEditorState::from_state(&state)).unwrap();
+        let current = format!("version:{},", FORMAT_VERSION);
+        assert!(saved.contains(&current));
+        assert_eq!(deserialize(&saved).unwrap().version, FORMAT_VERSION);
+
+        // Files from before the format was versioned get migrated
+        let unversioned = saved.replacen(&current, "", 1);
+        assert_eq!(deserialize(&unversioned).unwrap().version, FORMAT_VERSION);
+
+        let newer = saved.replacen(&current, &format!("version:{},", FORMAT_VERSION + 1), 1);
+        assert!(deserialize(&newer).is_err());
+    }
+
+    #[test]
+    pub fn test_data_type_colors() {
+        let mut state = GraphEditorState::new(1.0, CustomGraphState::default());
+        state.user_state.data_type_colors.mesh = egui::Color32::RED;
+        let saved = ron::ser::to_string(&SerializedEditorState::from_state(&state)).unwrap();
+        let loaded = deserialize(&saved).unwrap().into_state();
+        assert_eq!(loaded.user_state.data_type_colors.mesh, egui::Color32::RED);
+
+        // Files saved before the colors were stored get the default ones
+        let without_colors = saved.replacen("data_type_colors:", "unused_colors:", 1);
+        let loaded = deserialize(&without_colors).unwrap().into_state();
+        assert_eq!(
+            loaded.user_state.data_type_colors.mesh,
+            DataTypeColors::default().mesh
+        );
+    }
+
+    #[test]
+    pub fn test_autosave_path() {
+        let path = Path::new("some/folder/scene.blj");
+        assert_eq!(
+            autosave_path(Some(path)),
+            Path::new("some/folder/scene.blj.autosave")
+        );
+        let untitled = autosave_path(None);
+        assert_eq!(untitled.parent(), Some(std::env::temp_dir().as_path()));
+        assert_eq!(untitled_autosave_pid(&untitled), Some(std::process::id()));
+        assert_eq!(
+            untitled_autosave_pid(&path.with_extension("autosave")),
+            None
+        );
+    }
+
+    #[test]
+    pub fn test_recoverable_untitled_autosave() {
+        let state = GraphEditorState::new(1.0, CustomGraphState::default());
+        // A made up process id, standing in for another instance
+        let pid = u32::MAX - 1;
+        let autosave = untitled_autosave_path(pid);
+        save(&state, autosave.clone(), None).unwrap();
+
+        // The instance is still running, so its autosave is not offered
+        let lock = SessionLock::acquire_for(pid).unwrap();
+        assert!(is_session_running(pid));
+        assert_ne!(recoverable_autosave(None), Some(autosave.clone()));
+
+        // The autosave of an instance that exited can be recovered
+        drop(lock);
+        assert!(!is_session_running(pid));
+        assert_eq!(recoverable_autosave(None), Some(autosave.clone()));
+
+        // The autosave of the current instance is never offered
+        let own = autosave_path(None);
+        save(&state, own.clone(), None).unwrap();
+        assert_ne!(recoverable_autosave(None), Some(own.clone()));
+
+        std::fs::remove_file(autosave).unwrap();
+        std::fs::remove_file(own).unwrap();
+    }
+
+    #[test]
+    pub fn test_recoverable_autosave() {
+        let state = GraphEditorState::new(1.0, CustomGraphState::default());
+        let path = std::env::temp_dir().join("blackjack_test_recovery.blj");
+        let autosave = autosave_path(Some(&path));
+        let _ = std::fs::remove_file(&path);
+        remove_autosave(Some(&path)).unwrap();
+        assert_eq!(recoverable_autosave(Some(&path)), None);
+
+        // An autosave of a file that was never saved can be recovered
+        save(&state, autosave.clone(), None).unwrap();
+        assert_eq!(recoverable_autosave(Some(&path)), Some(autosave.clone()));
+
+        // Saving the file afterwards makes the autosave outdated
+        save(&state, path.clone(), None).unwrap();
+        assert_eq!(recoverable_autosave(Some(&path)), None);
+
+        remove_autosave(Some(&path)).unwrap();
+        assert!(!autosave.exists());
+        // Removing a missing autosave is not an error
+        remove_autosave(Some(&path)).unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
 }
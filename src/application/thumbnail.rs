@@ -0,0 +1,210 @@
+use crate::prelude::*;
+use image::ImageEncoder;
+
+/// The size, in pixels, of the longest side of the thumbnails stored in
+/// saved files.
+pub const THUMBNAIL_SIZE: u32 = 128;
+
+/// A copy of the 3d viewport's contents from the GPU into a buffer the CPU
+/// can read. Used to store a thumbnail of the viewport when saving.
+///
+/// The viewport render target can only be sampled, so it is first drawn into
+/// a texture owned by the readback, which is then copied into the buffer. The
+/// copy is recorded in the render graph for the frame, and can be read once
+/// the graph has been executed.
+pub struct ThumbnailReadback {
+    pipeline: wgpu::RenderPipeline,
+    bgl: wgpu::BindGroupLayout,
+    texture: wgpu::Texture,
+    texture_view: wgpu::TextureView,
+    buffer: wgpu::Buffer,
+    resolution: UVec2,
+    /// Rows in the buffer are padded to the alignment required by wgpu
+    padded_bytes_per_row: u32,
+}
+
+impl ThumbnailReadback {
+    /// Returns `None` when the resolution is zero in any dimension, since
+    /// there is nothing to read back in that case.
+    pub fn new(device: &wgpu::Device, resolution: UVec2) -> Option<Self> {
+        use wgpu::*;
+        if resolution.x == 0 || resolution.y == 0 {
+            return None;
+        }
+
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Viewport thumbnail texture"),
+            size: Extent3d {
+                width: resolution.x,
+                height: resolution.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+        });
+        let texture_view = texture.create_view(&TextureViewDescriptor::default());
+
+        let align = COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = (resolution.x * 4 + align - 1) / align * align;
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Viewport thumbnail readback"),
+            size: (padded_bytes_per_row * resolution.y) as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let shader = device.create_shader_module(&ShaderModuleDescriptor {
+            label: Some("Thumbnail blit shader"),
+            source: ShaderSource::Wgsl(include_str!("../rendergraph/thumbnail_blit.wgsl").into()),
+        });
+        let bgl = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Thumbnail blit BGL"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: false },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Thumbnail blit pipeline layout"),
+            bind_group_layouts: &[&bgl],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Thumbnail blit pipeline"),
+            layout: Some(&layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[ColorTargetState {
+                    format: TextureFormat::Rgba8UnormSrgb,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                }],
+            }),
+            multiview: None,
+        });
+
+        Some(Self {
+            pipeline,
+            bgl,
+            texture,
+            texture_view,
+            buffer,
+            resolution,
+            padded_bytes_per_row,
+        })
+    }
+
+    /// Adds a node to the graph that copies `texture`, which must have the
+    /// resolution given when creating the readback, into the buffer.
+    pub fn add_to_graph<'node>(
+        &'node self,
+        graph: &mut r3::RenderGraph<'node>,
+        texture: r3::RenderTargetHandle,
+    ) {
+        use wgpu::*;
+        let mut builder = graph.add_node("ViewportThumbnail");
+        let texture_handle = builder.add_render_target_input(texture);
+        let pt_handle = builder.passthrough_ref(self);
+        builder.build(
+            move |pt, renderer, encoder_or_pass, _temps, _ready, graph_data| {
+                let this = pt.get(pt_handle);
+                let viewport_view = graph_data.get_render_target(texture_handle);
+                let bind_group = renderer.device.create_bind_group(&BindGroupDescriptor {
+                    label: Some("Thumbnail blit BindGroup"),
+                    layout: &this.bgl,
+                    entries: &[BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(viewport_view),
+                    }],
+                });
+
+                let encoder = encoder_or_pass.get_encoder();
+                {
+                    let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+                        label: Some("Thumbnail blit"),
+                        color_attachments: &[RenderPassColorAttachment {
+                            view: &this.texture_view,
+                            resolve_target: None,
+                            ops: Operations {
+                                load: LoadOp::Clear(Color::BLACK),
+                                store: true,
+                            },
+                        }],
+                        depth_stencil_attachment: None,
+                    });
+                    rpass.set_pipeline(&this.pipeline);
+                    rpass.set_bind_group(0, &bind_group, &[]);
+                    rpass.draw(0..3, 0..1);
+                }
+
+                encoder.copy_texture_to_buffer(
+                    this.texture.as_image_copy(),
+                    ImageCopyBuffer {
+                        buffer: &this.buffer,
+                        layout: ImageDataLayout {
+                            offset: 0,
+                            bytes_per_row: std::num::NonZeroU32::new(this.padded_bytes_per_row),
+                            rows_per_image: None,
+                        },
+                    },
+                    Extent3d {
+                        width: this.resolution.x,
+                        height: this.resolution.y,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            },
+        );
+    }
+
+    /// Waits for the copy to finish, and returns the viewport contents scaled
+    /// down to a thumbnail, encoded as PNG.
+    pub fn read(self, device: &wgpu::Device) -> Result<Vec<u8>> {
+        let slice = self.buffer.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(mapping)?;
+
+        let (width, height) = (self.resolution.x, self.resolution.y);
+        let data = slice.get_mapped_range();
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for row in data.chunks(self.padded_bytes_per_row as usize) {
+            pixels.extend_from_slice(&row[..(width * 4) as usize]);
+        }
+        let image = image::RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| anyhow!("The viewport image has the wrong size"))?;
+
+        let scale = THUMBNAIL_SIZE as f32 / width.max(height) as f32;
+        let thumbnail = image::imageops::thumbnail(
+            &image,
+            ((width as f32 * scale) as u32).max(1),
+            ((height as f32 * scale) as u32).max(1),
+        );
+        let mut png = vec![];
+        image::codecs::png::PngEncoder::new(&mut png).write_image(
+            thumbnail.as_raw(),
+            thumbnail.width(),
+            thumbnail.height(),
+            image::ColorType::Rgba8,
+        )?;
+        Ok(png)
+    }
+}
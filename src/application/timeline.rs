@@ -0,0 +1,93 @@
+use std::time::Instant;
+
+/// Stores the global animation time. The time is exposed to Lua as a global
+/// variable so that node graphs can produce animated results.
+pub struct Timeline {
+    /// The current time, in seconds
+    pub time: f32,
+    /// The upper bound for the scrubbing slider, in seconds. When playing, the
+    /// time wraps around after reaching this value.
+    pub duration: f32,
+    /// When true, time advances every frame
+    pub playing: bool,
+    /// Number of animation frames per second. Used when exporting the
+    /// animation as a sequence of frames.
+    pub fps: f32,
+    /// The instant at which the last frame was processed. Used to compute the
+    /// delta time between frames.
+    last_update: Instant,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self {
+            time: 0.0,
+            duration: 10.0,
+            playing: false,
+            fps: 24.0,
+            last_update: Instant::now(),
+        }
+    }
+
+    /// Advances the time by the amount of real time elapsed since the last
+    /// call, but only when the timeline is playing.
+    pub fn advance(&mut self) {
+        let now = Instant::now();
+        let delta = (now - self.last_update).as_secs_f32();
+        self.last_update = now;
+
+        if self.playing {
+            self.time += delta;
+            if self.time > self.duration {
+                self.time = 0.0;
+            }
+        }
+    }
+
+    /// Returns the number of the last frame in the animation. Frames are
+    /// numbered starting from zero.
+    pub fn last_frame(&self) -> u32 {
+        (self.duration * self.fps).floor() as u32
+    }
+
+    /// Returns the time, in seconds, corresponding to the given frame number.
+    pub fn frame_time(&self, frame: u32) -> f32 {
+        frame as f32 / self.fps
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button(if self.playing { "⏸" } else { "▶" }).clicked() {
+                self.playing = !self.playing;
+            }
+            if ui.button("⏹").clicked() {
+                self.playing = false;
+                self.time = 0.0;
+            }
+            ui.add(
+                egui::Slider::new(&mut self.time, 0.0..=self.duration)
+                    .text("time")
+                    .fixed_decimals(2),
+            );
+            ui.add(
+                egui::DragValue::new(&mut self.duration)
+                    .prefix("duration: ")
+                    .clamp_range(0.1..=3600.0)
+                    .speed(0.1),
+            );
+            ui.add(
+                egui::DragValue::new(&mut self.fps)
+                    .suffix(" fps")
+                    .clamp_range(1.0..=240.0)
+                    .speed(0.1),
+            );
+            ui.label(format!("frame: {}", (self.time * self.fps).floor() as u32));
+        });
+    }
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,8 +2,13 @@ use winit::event::MouseButton;
 
 use crate::app_window::input::InputSystem;
 use crate::{prelude::*, rendergraph};
+use halfedge::selection::SelectionKind;
 
-use super::app_viewport::AppViewport;
+use super::{
+    app_viewport::AppViewport,
+    gizmo::Gizmo,
+    picking::{self, PickingCache},
+};
 
 #[derive(PartialEq, Eq)]
 pub enum EdgeDrawMode {
@@ -16,6 +21,10 @@ pub enum EdgeDrawMode {
 pub enum FaceDrawMode {
     Flat,
     Smooth,
+    /// Faces are drawn using the mesh's vertex color channel
+    VertexColor,
+    /// Faces are drawn semi-transparent, without occluding what's behind them
+    XRay,
     None,
 }
 
@@ -23,7 +32,51 @@ pub struct Viewport3dSettings {
     pub render_vertices: bool,
     pub matcap: usize,
     pub edge_mode: EdgeDrawMode,
+    /// The width of the wireframe edges, in pixels
+    pub line_width: f32,
+    /// The width of the edges highlighted by the active node's selection, in
+    /// pixels. Wider than the wireframe, so they stand out over it.
+    pub highlight_line_width: f32,
+    /// The width of the vertex points, in pixels
+    pub point_size: f32,
+    /// When set, a line is drawn from the center of each face along its
+    /// normal, to debug face winding.
+    pub show_face_normals: bool,
+    /// When set, the axis-aligned bounding box of the mesh is drawn
+    pub show_bounds: bool,
     pub face_mode: FaceDrawMode,
+    /// The opacity of the faces in the x-ray face mode, between 0 and 1
+    pub xray_alpha: f32,
+    /// The color used to fill the viewport where nothing is drawn. When
+    /// `background_gradient` is set, this is the color at the top.
+    pub background_color: egui::Color32,
+    /// The color at the bottom of the viewport, only used when
+    /// `background_gradient` is set.
+    pub background_color_bottom: egui::Color32,
+    pub background_gradient: bool,
+    /// The color of the grid lines drawn on the ground plane
+    pub grid_color: egui::Color32,
+    /// The distance between grid lines, in world units
+    pub grid_spacing: f32,
+    /// The number of MSAA samples used to render the viewport
+    pub sample_count: r3::SampleCount,
+    /// When set, the back side of faces is not drawn. Disabling it helps
+    /// telling apart wrong normals from missing geometry.
+    pub cull_backfaces: bool,
+    /// When set, the elements selected by the active node's selection
+    /// parameter are highlighted, interpreting the selection as this kind.
+    /// When `picking` is enabled, clicking on the mesh picks elements of this
+    /// kind.
+    pub highlight_selection: Option<SelectionKind>,
+    /// Whether clicking on the mesh selects elements in the active node. Off
+    /// by default, so clicks to orbit or focus the viewport don't change the
+    /// graph.
+    pub picking: bool,
+    /// When set, the vertex channel with this name is shown as vertex colors
+    /// in the viewport, to visualize its values.
+    pub preview_channel: Option<String>,
+    /// The channel name being edited in the UI, before it's previewed
+    preview_channel_input: String,
 }
 
 pub struct Viewport3d {
@@ -32,6 +85,12 @@ pub struct Viewport3d {
     viewport_rect: egui::Rect,
     parent_scale: f32,
     pub settings: Viewport3dSettings,
+    gizmo: Gizmo,
+    picking_cache: PickingCache,
+    /// The camera's view-projection matrix, as of the last update
+    view_proj: Mat4,
+    /// The camera's view matrix, as of the last update
+    view: Mat4,
 }
 
 struct OrbitCamera {
@@ -59,11 +118,32 @@ impl Viewport3d {
             // the first update.
             viewport_rect: egui::Rect::from_min_size(egui::Pos2::ZERO, egui::Vec2::new(10.0, 10.0)),
             parent_scale: 1.0,
+            gizmo: Gizmo::new(),
+            picking_cache: PickingCache::default(),
+            view_proj: Mat4::IDENTITY,
+            view: Mat4::IDENTITY,
             settings: Viewport3dSettings {
                 edge_mode: EdgeDrawMode::FullEdge,
+                line_width: 1.0,
+                highlight_line_width: 3.0,
+                point_size: 5.0,
+                show_face_normals: false,
+                show_bounds: false,
                 face_mode: FaceDrawMode::Flat,
+                xray_alpha: 0.3,
                 render_vertices: true,
                 matcap: 0,
+                background_color: egui::Color32::BLACK,
+                background_color_bottom: egui::Color32::from_gray(60),
+                background_gradient: false,
+                grid_color: egui::Color32::from_gray(124),
+                grid_spacing: 0.5,
+                sample_count: r3::SampleCount::One,
+                cull_backfaces: true,
+                highlight_selection: Some(SelectionKind::Faces),
+                picking: false,
+                preview_channel: None,
+                preview_channel_input: String::new(),
             },
         }
     }
@@ -86,7 +166,7 @@ impl Viewport3d {
 
     fn update_camera(&mut self, render_ctx: &mut RenderContext) {
         // Update status
-        if self.input.mouse.buttons().pressed(MouseButton::Left) {
+        if self.input.mouse.buttons().pressed(MouseButton::Left) && !self.gizmo.captures_mouse {
             self.camera.yaw += self.input.mouse.cursor_delta().x * 2.0;
             self.camera.pitch += self.input.mouse.cursor_delta().y * 2.0;
         }
@@ -97,6 +177,7 @@ impl Viewport3d {
             * Mat4::from_rotation_x(-self.camera.pitch.to_radians())
             * Mat4::from_rotation_y(-self.camera.yaw.to_radians());
         render_ctx.set_camera(view);
+        self.view = view;
     }
 
     pub fn update(
@@ -109,6 +190,7 @@ impl Viewport3d {
         self.parent_scale = parent_scale;
 
         self.update_camera(render_ctx);
+        self.view_proj = render_ctx.view_proj();
         self.input.update();
 
         // TODO: What if we ever have multiple 3d viewports? There's no way to
@@ -125,7 +207,7 @@ impl Viewport3d {
         Vec4::splat(0.25)
     }
 
-    fn get_resolution(&self) -> UVec2 {
+    pub fn get_resolution(&self) -> UVec2 {
         UVec2::new(
             (self.viewport_rect.width() * self.parent_scale) as u32,
             (self.viewport_rect.height() * self.parent_scale) as u32,
@@ -143,13 +225,19 @@ impl Viewport3d {
             ready,
             viewport_routines,
             self.get_resolution(),
-            r3::SampleCount::One,
+            self.settings.sample_count,
             Self::ambient_light(),
             &self.settings,
         )
     }
 
-    pub fn show_ui(&mut self, ui: &mut egui::Ui, offscreen_viewport: &mut AppViewport) {
+    pub fn show_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        offscreen_viewport: &mut AppViewport,
+        editor_state: &mut graph::GraphEditorState,
+        mesh: Option<&HalfEdgeMesh>,
+    ) {
         ui.vertical(|ui| {
             ui.horizontal(|ui| {
                 mesh_visuals_popup(ui, |ui| {
@@ -172,11 +260,51 @@ impl Viewport3d {
                         );
                     });
 
+                    ui.horizontal(|ui| {
+                        ui.label("Edge width:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.settings.line_width)
+                                .clamp_range(0.5..=10.0)
+                                .speed(0.05)
+                                .suffix(" px"),
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Highlighted edge width:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.settings.highlight_line_width)
+                                .clamp_range(0.5..=10.0)
+                                .speed(0.05)
+                                .suffix(" px"),
+                        );
+                    });
+
                     ui.horizontal(|ui| {
                         ui.label("Vertices:");
                         ui.checkbox(&mut self.settings.render_vertices, "");
                     });
 
+                    ui.horizontal(|ui| {
+                        ui.label("Vertex size:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.settings.point_size)
+                                .clamp_range(1.0..=20.0)
+                                .speed(0.05)
+                                .suffix(" px"),
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Face normals:");
+                        ui.checkbox(&mut self.settings.show_face_normals, "");
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Bounding box:");
+                        ui.checkbox(&mut self.settings.show_bounds, "");
+                    });
+
                     ui.horizontal(|ui| {
                         ui.label("Faces:");
                         ui.selectable_value(
@@ -189,6 +317,16 @@ impl Viewport3d {
                             FaceDrawMode::Smooth,
                             "Smooth",
                         );
+                        ui.selectable_value(
+                            &mut self.settings.face_mode,
+                            FaceDrawMode::VertexColor,
+                            "Vertex color",
+                        );
+                        ui.selectable_value(
+                            &mut self.settings.face_mode,
+                            FaceDrawMode::XRay,
+                            "X-ray",
+                        );
                         ui.selectable_value(
                             &mut self.settings.face_mode,
                             FaceDrawMode::None,
@@ -196,6 +334,13 @@ impl Viewport3d {
                         );
                     });
 
+                    if self.settings.face_mode == FaceDrawMode::XRay {
+                        ui.horizontal(|ui| {
+                            ui.label("X-ray opacity:");
+                            ui.add(egui::Slider::new(&mut self.settings.xray_alpha, 0.0..=1.0));
+                        });
+                    }
+
                     ui.horizontal(|ui| {
                         ui.label("Matcap:");
                         if ui.button("<").clicked() {
@@ -209,11 +354,124 @@ impl Viewport3d {
                             self.settings.matcap += 1;
                         }
                     });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Background:");
+                        ui.color_edit_button_srgba(&mut self.settings.background_color);
+                        ui.checkbox(&mut self.settings.background_gradient, "Gradient");
+                        if self.settings.background_gradient {
+                            ui.color_edit_button_srgba(&mut self.settings.background_color_bottom);
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Grid:");
+                        ui.color_edit_button_srgba(&mut self.settings.grid_color);
+                        ui.add(
+                            egui::DragValue::new(&mut self.settings.grid_spacing)
+                                .clamp_range(0.01..=100.0)
+                                .speed(0.01)
+                                .prefix("spacing: "),
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Highlight selection:");
+                        ui.selectable_value(
+                            &mut self.settings.highlight_selection,
+                            Some(SelectionKind::Faces),
+                            "Faces",
+                        );
+                        ui.selectable_value(
+                            &mut self.settings.highlight_selection,
+                            Some(SelectionKind::Edges),
+                            "Edges",
+                        );
+                        ui.selectable_value(
+                            &mut self.settings.highlight_selection,
+                            Some(SelectionKind::Vertices),
+                            "Vertices",
+                        );
+                        ui.selectable_value(&mut self.settings.highlight_selection, None, "None");
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Preview channel:");
+                        ui.text_edit_singleline(&mut self.settings.preview_channel_input);
+                        if ui.button("Preview").clicked()
+                            && !self.settings.preview_channel_input.is_empty()
+                        {
+                            self.settings.preview_channel =
+                                Some(self.settings.preview_channel_input.clone());
+                            self.settings.face_mode = FaceDrawMode::VertexColor;
+                        }
+                        if ui.button("Clear").clicked() {
+                            self.settings.preview_channel = None;
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Antialiasing:");
+                        // These are the only two variants of `r3::SampleCount`.
+                        // WebGPU only guarantees 1 and 4 samples on every
+                        // adapter, and wgpu 0.12 rejects any other count
+                        // unless adapter specific format features are enabled.
+                        ui.selectable_value(
+                            &mut self.settings.sample_count,
+                            r3::SampleCount::One,
+                            "Off",
+                        );
+                        ui.selectable_value(
+                            &mut self.settings.sample_count,
+                            r3::SampleCount::Four,
+                            "4x MSAA",
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Backface culling:");
+                        ui.checkbox(&mut self.settings.cull_backfaces, "");
+                    });
                 });
+                ui.separator();
+                self.gizmo.mode_ui(ui);
+                ui.separator();
+                ui.checkbox(&mut self.settings.picking, "Pick")
+                    .on_hover_text("Click on the mesh to select elements in the active node");
             });
             offscreen_viewport.show(ui, ui.available_size());
+            self.gizmo
+                .ui(ui, offscreen_viewport.rect, self.view_proj, editor_state);
+            self.pick_on_click(ui, offscreen_viewport.rect, editor_state, mesh);
         });
     }
+
+    /// When picking is enabled, clicking a mesh element selects it in the
+    /// active node's selection parameter, interpreting the selection as the
+    /// highlighted kind. Holding shift adds the element to the selection.
+    fn pick_on_click(
+        &mut self,
+        ui: &egui::Ui,
+        rect: egui::Rect,
+        editor_state: &mut graph::GraphEditorState,
+        mesh: Option<&HalfEdgeMesh>,
+    ) {
+        let (mesh, kind) = match (mesh, self.settings.highlight_selection) {
+            (Some(mesh), Some(kind)) => (mesh, kind),
+            _ => return,
+        };
+        let input = ui.input();
+        if !self.settings.picking || !input.pointer.any_click() || self.gizmo.captures_mouse {
+            return;
+        }
+        if let Some(pos) = input.pointer.interact_pos().filter(|p| rect.contains(*p)) {
+            let (origin, dir) = picking::screen_ray(self.view, self.view_proj, rect, pos);
+            let accel = self.picking_cache.accel(mesh);
+            if let Some(index) = picking::pick_element(mesh, accel, origin, dir, kind) {
+                picking::select_in_active_node(editor_state, index, input.modifiers.shift);
+            }
+        }
+    }
 }
 
 /// Draws the "Mesh Visuals" popup.
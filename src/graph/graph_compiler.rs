@@ -3,7 +3,11 @@ use mlua::{ExternalResult, Lua, ToLua};
 
 use crate::{lua_engine::lua_stdlib, prelude::graph::*, prelude::*};
 
-use std::fmt::Write;
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt::Write,
+    hash::{Hash, Hasher},
+};
 
 /// Represents the place where the output of a node will be stored. Gets
 /// translated to local identifiers in the Lua code.
@@ -35,6 +39,7 @@ pub struct ConstParamAddr {
 
 /// When passing inputs to nodes, those can be outputs from another node (the
 /// `OtherOut` variant) or constant parameters (the `ConstParam` variant)
+#[derive(Clone, Copy)]
 enum InputArgAddr {
     OtherOut {
         out_addr: NodeOutputAddr,
@@ -61,6 +66,9 @@ struct CodegenContext {
     /// The current indent level. Gets added / subtracted as we nesting levels
     /// increase. Each indent level equals four spaces.
     indent_level: usize,
+    /// When set, node calls go through the node library's output cache, so
+    /// nodes are only evaluated again when something changed.
+    cache_outputs: bool,
 }
 
 /// The resulting compiled program
@@ -184,7 +192,8 @@ fn codegen_input(
             | ValueType::Scalar { .. }
             | ValueType::Selection { .. }
             | ValueType::Enum { .. }
-            | ValueType::NewFile { .. } => {
+            | ValueType::NewFile { .. }
+            | ValueType::StaticMesh { .. } => {
                 let addr = ConstParamAddr { id: param };
                 ctx.const_parameters.push(addr);
                 Ok(InputArgAddr::ConstParam(addr))
@@ -233,34 +242,70 @@ fn codegen_node(
             }
         };
     }
+    let node_name = graph[node_id].user_data.op_name.as_str();
+
+    // The cache key for this node's outputs is computed from everything that
+    // affects them other than the upstream outputs: The node's operation, its
+    // constant parameters and where its connected inputs come from. Changes
+    // to upstream nodes are tracked at runtime by the node library.
+    let mut hasher = DefaultHasher::new();
+    node_name.hash(&mut hasher);
+    let mut deps = vec![];
+
     let args = if graph[node_id].inputs.is_empty() {
         String::from("{}")
     } else {
         let mut args = String::from("{\n");
         for input_name in graph[node_id].inputs.iter().map(|x| &x.0) {
             let input_addr = codegen_input(graph, ctx, node_id, input_name)?;
-            args += &format!(
-                "{indent}{indent}{input_name} = {},\n",
-                input_addr.generate_code(graph, ctx)?
-            );
+            let code = input_addr.generate_code(graph, ctx)?;
+            input_name.hash(&mut hasher);
+            match input_addr {
+                InputArgAddr::OtherOut { out_addr, .. } => {
+                    code.hash(&mut hasher);
+                    let dep = out_addr.variable_name(graph)?;
+                    if !deps.contains(&dep) {
+                        deps.push(dep);
+                    }
+                }
+                InputArgAddr::ConstParam(addr) => {
+                    format!("{:?}", graph[addr.id].value()).hash(&mut hasher);
+                }
+            }
+            args += &format!("{indent}{indent}{input_name} = {code},\n");
         }
         args + indent.as_str() + "}"
     };
     let output_addr = codegen_output(graph, ctx, node_id)?.variable_name(graph)?;
-    let node_name = graph[node_id].user_data.op_name.as_str();
+    let hash = hasher.finish();
+    let deps = deps.iter().map(|dep| format!("'{dep}'")).join(", ");
 
-    emit_line!("local {output_addr} = NodeLibrary:callNode('{node_name}', {args})");
+    if ctx.cache_outputs {
+        emit_line!(
+            "local {output_addr} = NodeLibrary:callNodeCached('{output_addr}', '{hash:016x}', {{{deps}}}, '{node_name}', {args})"
+        );
+    } else {
+        emit_line!("local {output_addr} = NodeLibrary:callNode('{node_name}', {args})");
+    }
 
     // TODO: The return value is not always out_mesh. This should be stored
     // somehow in the node definition.
-    emit_return!(format!("{output_addr}.out_mesh"));
+    //
+    // The table with all the outputs is returned too, so the selection
+    // outputs of the node can be shown in the viewport.
+    emit_return!(format!("{output_addr}.out_mesh, {output_addr}"));
 
     Ok(())
 }
 
 /// Compiles a graph into a Lua program. The program produced computes and
-/// returns the value of the `final_node`.
+/// returns the mesh of the `final_node`, followed by the table with all of
+/// its outputs.
 pub fn compile_graph(graph: &Graph, final_node: NodeId) -> Result<CompiledProgram> {
+    compile(graph, final_node, true)
+}
+
+fn compile(graph: &Graph, final_node: NodeId, cache_outputs: bool) -> Result<CompiledProgram> {
     let input_params_ident = "input_params";
     let mut ctx = CodegenContext {
         indent_level: 1,
@@ -268,6 +313,7 @@ pub fn compile_graph(graph: &Graph, final_node: NodeId) -> Result<CompiledProgra
         lua_program: String::new(),
         outputs_cache: Default::default(),
         const_parameters: Default::default(),
+        cache_outputs,
     };
 
     writeln!(ctx.lua_program, "function main({input_params_ident})")?;
@@ -279,39 +325,289 @@ pub fn compile_graph(graph: &Graph, final_node: NodeId) -> Result<CompiledProgra
     })
 }
 
+/// Compiles a graph into a standalone Lua script, which returns the value of
+/// the `final_node` when run. Unlike [`compile_graph`], the constant
+/// parameters are written into the script and nodes are not cached, so the
+/// script can run on its own in any Lua runtime with the blackjack libraries
+/// loaded.
+///
+/// Nodes are emitted in dependency order, visiting the inputs of each node in
+/// the order they are declared, and constants are sorted by name. Baking the
+/// same graph always produces the same script.
+pub fn bake_graph(graph: &Graph, final_node: NodeId) -> Result<String> {
+    let compiled = compile(graph, final_node, false)?;
+
+    let mut consts = compiled
+        .const_parameters
+        .iter()
+        .map(|addr| {
+            Ok((
+                addr.const_value_ref(graph)?,
+                lua_literal(graph[addr.id].value())?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    consts.sort();
+    consts.dedup();
+
+    let mut script = String::new();
+    writeln!(
+        script,
+        "-- Baked from the blackjack node graph. Returns the output of the {} node.",
+        graph[final_node].label
+    )?;
+    writeln!(script, "local input_params = {{")?;
+    for (ident, literal) in consts {
+        writeln!(script, "    {ident} = {literal},")?;
+    }
+    writeln!(script, "}}\n")?;
+    script += &compiled.lua_program;
+    writeln!(script, "\nreturn main(input_params)")?;
+    Ok(script)
+}
+
+/// Returns the Lua code that builds the given constant value.
+fn lua_literal(value: &ValueType) -> Result<String> {
+    fn number(x: f32) -> String {
+        if x.is_nan() {
+            "(0/0)".into()
+        } else if x.is_infinite() {
+            if x > 0.0 { "math.huge" } else { "-math.huge" }.into()
+        } else {
+            format!("{:?}", x)
+        }
+    }
+    // The escape sequences in Rust's debug representation of strings are
+    // also valid in Luau string literals.
+    fn string(s: &str) -> String {
+        format!("{:?}", s)
+    }
+
+    Ok(match value {
+        ValueType::None => bail!("Cannot use constant value for non-existing type"),
+        ValueType::Vector(v) => format!("Vec3({}, {}, {})", number(v.x), number(v.y), number(v.z)),
+        ValueType::Scalar { value, .. } => number(*value),
+        // An invalid selection evaluates to an empty one, like in the editor
+        ValueType::Selection { text, selection } => format!(
+            "Blackjack.selection({})",
+            string(if selection.is_some() { text } else { "" })
+        ),
+        ValueType::Enum { values, selected } => string(&values[selected.unwrap_or(0) as usize]),
+        ValueType::NewFile { path } => format!(
+            "Blackjack.path({})",
+            string(
+                &path
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("Path not set"))?
+                    .to_string_lossy()
+            )
+        ),
+        ValueType::StaticMesh { obj } => format!(
+            "Blackjack.mesh_from_obj({})",
+            string(
+                obj.as_ref()
+                    .ok_or_else(|| anyhow!("Static mesh has no data"))?
+            )
+        ),
+    })
+}
+
 /// Extracts parameters from a graph into a Lua table
 pub fn extract_params<'lua>(
     lua: &'lua Lua,
     graph: &Graph,
     compiled: &CompiledProgram,
+) -> Result<mlua::Table<'lua>> {
+    params_to_lua(lua, &extract_param_values(graph, compiled)?)
+}
+
+/// Extracts the values of the parameters of a graph, paired with the
+/// identifiers the compiled program uses to refer to them. Unlike the Lua
+/// table built by [`extract_params`], the values can be sent to another
+/// thread.
+pub fn extract_param_values(
+    graph: &Graph,
+    compiled: &CompiledProgram,
+) -> Result<Vec<(String, ValueType)>> {
+    compiled
+        .const_parameters
+        .iter()
+        .map(|const_param| {
+            let ident = const_param.const_value_ref(graph)?;
+            Ok((ident, graph.get_input(const_param.id).value().clone()))
+        })
+        .collect()
+}
+
+/// Builds the Lua table of parameters a compiled program takes as input, from
+/// the values returned by [`extract_param_values`].
+pub fn params_to_lua<'lua>(
+    lua: &'lua Lua,
+    values: &[(String, ValueType)],
 ) -> Result<mlua::Table<'lua>> {
     let table = lua.create_table()?;
-    for const_param in &compiled.const_parameters {
-        let id = const_param.id;
-        let input = graph.get_input(id);
-        let ident = const_param.const_value_ref(graph)?;
-        let value = match input.value() {
-            crate::prelude::graph::ValueType::None => {
+    for (ident, value) in values {
+        let value = match value {
+            ValueType::None => {
                 Err(anyhow!("Cannot use constant value for non-existing type")).to_lua_err()
             }
-            crate::prelude::graph::ValueType::Vector(v) => lua_stdlib::Vec3(*v).to_lua(lua),
-            crate::prelude::graph::ValueType::Scalar { value, .. } => value.to_lua(lua),
-            crate::prelude::graph::ValueType::Selection { selection, .. } => selection
+            ValueType::Vector(v) => lua_stdlib::Vec3(*v).to_lua(lua),
+            ValueType::Scalar { value, .. } => value.to_lua(lua),
+            ValueType::Selection { selection, .. } => selection
                 .clone()
                 .unwrap_or(SelectionExpression::None)
                 .to_lua(lua),
-            crate::prelude::graph::ValueType::Enum {
+            ValueType::Enum {
                 values,
                 selected: selection,
             } => values[selection.unwrap_or(0) as usize].clone().to_lua(lua),
-            crate::prelude::graph::ValueType::NewFile { path } => lua_stdlib::Path(
+            ValueType::NewFile { path } => lua_stdlib::Path(
                 path.as_ref()
                     .ok_or_else(|| anyhow!("Path not set"))?
                     .clone(),
             )
             .to_lua(lua),
+            ValueType::StaticMesh { obj } => HalfEdgeMesh::from_wavefront_obj_str(
+                obj.as_ref()
+                    .ok_or_else(|| anyhow!("Static mesh has no data"))?,
+            )?
+            .to_lua(lua),
         }?;
-        table.set(ident, value)?;
+        table.set(ident.as_str(), value)?;
     }
     Ok(table)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use egui_node_graph::InputParamKind;
+
+    fn node_data(op_name: &str) -> NodeData {
+        NodeData {
+            op_name: op_name.into(),
+            returns: Some("out_mesh".into()),
+            is_executable: false,
+        }
+    }
+
+    /// A box node connected to an extrude node, with one constant of every
+    /// kind that gets baked into the script.
+    fn box_extrude_graph() -> (Graph, NodeId) {
+        let mut graph = Graph::new();
+        let make_box = graph.add_node("Box".into(), node_data("MakeBox"), |graph, node| {
+            graph.add_input_param(
+                node,
+                "size".into(),
+                DataType::Vector,
+                ValueType::Vector(Vec3::new(0.5, 1.0, -2.0)),
+                InputParamKind::ConnectionOrConstant,
+                true,
+            );
+            graph.add_output_param(node, "out_mesh".into(), DataType::Mesh);
+        });
+        let extrude = graph.add_node("Extrude".into(), node_data("Extrude"), |graph, node| {
+            graph.add_input_param(
+                node,
+                "in_mesh".into(),
+                DataType::Mesh,
+                ValueType::None,
+                InputParamKind::ConnectionOnly,
+                true,
+            );
+            graph.add_input_param(
+                node,
+                "faces".into(),
+                DataType::Selection,
+                ValueType::Selection {
+                    text: "0..2".into(),
+                    selection: Some(SelectionExpression::parse("0..2").unwrap()),
+                },
+                InputParamKind::ConnectionOrConstant,
+                true,
+            );
+            graph.add_input_param(
+                node,
+                "amount".into(),
+                DataType::Scalar,
+                ValueType::Scalar {
+                    value: 0.25,
+                    min: 0.0,
+                    max: 1.0,
+                },
+                InputParamKind::ConnectionOrConstant,
+                true,
+            );
+            graph.add_input_param(
+                node,
+                "mode".into(),
+                DataType::Enum,
+                ValueType::Enum {
+                    values: vec!["plain".into(), "with \"quotes\"\n".into()],
+                    selected: Some(1),
+                },
+                InputParamKind::ConstantOnly,
+                true,
+            );
+            graph.add_output_param(node, "out_mesh".into(), DataType::Mesh);
+        });
+        let output = graph[make_box].get_output("out_mesh").unwrap();
+        let input = graph[extrude].get_input("in_mesh").unwrap();
+        graph.add_connection(output, input);
+        (graph, extrude)
+    }
+
+    #[test]
+    pub fn test_bake_graph() {
+        let (graph, extrude) = box_extrude_graph();
+        let script = bake_graph(&graph, extrude).unwrap();
+        assert_eq!(script, bake_graph(&graph, extrude).unwrap());
+
+        assert!(script.contains("Vec3(0.5, 1.0, -2.0)"));
+        assert!(script.contains("= 0.25,"));
+        assert!(script.contains(r#"Blackjack.selection("0..2")"#));
+        assert!(script.contains(r#""with \"quotes\"\n""#));
+        assert!(script.ends_with("return main(input_params)\n"));
+        // The box is emitted before the node using its output
+        assert!(script.find("'MakeBox'").unwrap() < script.find("'Extrude'").unwrap());
+    }
+
+    #[test]
+    pub fn test_lua_literal() {
+        let scalar = |value| ValueType::Scalar {
+            value,
+            min: 0.0,
+            max: 1.0,
+        };
+        assert_eq!(lua_literal(&scalar(1.0)).unwrap(), "1.0");
+        assert_eq!(lua_literal(&scalar(-0.125)).unwrap(), "-0.125");
+        assert_eq!(lua_literal(&scalar(f32::NAN)).unwrap(), "(0/0)");
+        assert_eq!(lua_literal(&scalar(f32::INFINITY)).unwrap(), "math.huge");
+        assert_eq!(
+            lua_literal(&scalar(f32::NEG_INFINITY)).unwrap(),
+            "-math.huge"
+        );
+        assert_eq!(
+            lua_literal(&ValueType::Vector(Vec3::new(1.0, -2.5, 0.0))).unwrap(),
+            "Vec3(1.0, -2.5, 0.0)"
+        );
+        // Invalid selections are baked as empty ones
+        assert_eq!(
+            lua_literal(&ValueType::Selection {
+                text: "not a selection".into(),
+                selection: None,
+            })
+            .unwrap(),
+            r#"Blackjack.selection("")"#
+        );
+        assert_eq!(
+            lua_literal(&ValueType::Enum {
+                values: vec!["a\\b\t\"c\"".into()],
+                selected: None,
+            })
+            .unwrap(),
+            r#""a\\b\t\"c\"""#
+        );
+        assert!(lua_literal(&ValueType::None).is_err());
+    }
+}
@@ -10,6 +10,7 @@ use serde::{Deserialize, Serialize};
 //use self::node_templates::GraphNodeType;
 use self::node_templates::{NodeDefinition, NodeDefinitions};
 
+pub mod clipboard;
 pub mod node_templates;
 pub mod value_widget;
 
@@ -42,10 +43,12 @@ pub enum DataType {
     Enum,
     // The path to a (possibly new) file where export contents will be saved to
     NewFile,
+    // A mesh stored inline in the graph, e.g. the result of freezing a node
+    StaticMesh,
 }
 
 /// Blackjack-specific constant types (inline widget)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ValueType {
     /// Used for parameters that can't have a value because they only accept
     /// connections.
@@ -67,6 +70,10 @@ pub enum ValueType {
     NewFile {
         path: Option<std::path::PathBuf>,
     },
+    StaticMesh {
+        /// The mesh data, stored in Wavefront OBJ format
+        obj: Option<String>,
+    },
 }
 
 /// Blackjack-specific node responses (graph side-effects)
@@ -75,6 +82,8 @@ pub enum CustomNodeResponse {
     SetActiveNode(NodeId),
     ClearActiveNode,
     RunNodeSideEffect(NodeId),
+    FreezeNode(NodeId),
+    DuplicateNode(NodeId),
 }
 
 /// Blackjack-specific global graph state
@@ -86,18 +95,99 @@ pub struct CustomGraphState {
     /// The currently active node. A program will be compiled to compute the
     /// result of this node and constantly updated in real-time.
     pub active_node: Option<NodeId>,
+    /// When this option is set by the UI, the mesh currently produced by this
+    /// node will be baked into a new static mesh node at the end of the frame.
+    pub freeze_node: Option<NodeId>,
+    /// The nodes selected with the rubber band selection. These are the nodes
+    /// that get copied to the clipboard.
+    #[serde(skip)]
+    pub selected_nodes: Vec<NodeId>,
+    /// The screen position where the current rubber band selection started
+    #[serde(skip)]
+    pub rubber_band_start: Option<egui::Pos2>,
+    /// The nodes copied with Ctrl+C, serialized as a string
+    #[serde(skip)]
+    pub clipboard: Option<String>,
+    /// The colors used to draw the ports of each data type
+    #[serde(default)]
+    pub data_type_colors: DataTypeColors,
+}
+
+/// The colors used to draw the ports and connections of each data type in the
+/// graph editor. Colors missing from a saved file get their default value.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DataTypeColors {
+    pub mesh: egui::Color32,
+    pub vector: egui::Color32,
+    pub scalar: egui::Color32,
+    pub selection: egui::Color32,
+    pub enumeration: egui::Color32,
+    pub new_file: egui::Color32,
+    pub static_mesh: egui::Color32,
+}
+
+impl Default for DataTypeColors {
+    fn default() -> Self {
+        // Enum, NewFile and StaticMesh parameters are constant-only, so they
+        // never show up as ports. They get a neutral color anyway.
+        let neutral = color_from_hex("#a0a0a0").unwrap();
+        Self {
+            mesh: color_from_hex("#266dd3").unwrap(),
+            vector: color_from_hex("#eecf6d").unwrap(),
+            scalar: color_from_hex("#eb9fef").unwrap(),
+            selection: color_from_hex("#4b7f52").unwrap(),
+            enumeration: neutral,
+            new_file: neutral,
+            static_mesh: neutral,
+        }
+    }
+}
+
+impl DataTypeColors {
+    pub fn get(&self, data_type: DataType) -> egui::Color32 {
+        match data_type {
+            DataType::Mesh => self.mesh,
+            DataType::Vector => self.vector,
+            DataType::Scalar => self.scalar,
+            DataType::Selection => self.selection,
+            DataType::Enum => self.enumeration,
+            DataType::NewFile => self.new_file,
+            DataType::StaticMesh => self.static_mesh,
+        }
+    }
+
+    /// Shows a color picker for each data type
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("data_type_colors").show(ui, |ui| {
+            for (label, color) in [
+                ("Mesh", &mut self.mesh),
+                ("Vector", &mut self.vector),
+                ("Scalar", &mut self.scalar),
+                ("Selection", &mut self.selection),
+                ("Enum", &mut self.enumeration),
+                ("File", &mut self.new_file),
+                ("Static mesh", &mut self.static_mesh),
+            ] {
+                ui.label(label);
+                ui.color_edit_button_srgba(color);
+                ui.end_row();
+            }
+        });
+    }
+}
+
+thread_local! {
+    /// The colors read by [`DataType::data_type_color`]. egui_node_graph
+    /// doesn't give that function access to the editor state, so the colors
+    /// in [`CustomGraphState`] are copied here every frame.
+    static DATA_TYPE_COLORS: std::cell::Cell<DataTypeColors> =
+        std::cell::Cell::new(DataTypeColors::default());
 }
 
 impl DataTypeTrait for DataType {
     fn data_type_color(&self) -> egui::Color32 {
-        match self {
-            DataType::Mesh => color_from_hex("#266dd3").unwrap(),
-            DataType::Vector => color_from_hex("#eecf6d").unwrap(),
-            DataType::Scalar => color_from_hex("#eb9fef").unwrap(),
-            DataType::Selection => color_from_hex("#4b7f52").unwrap(),
-            DataType::Enum => color_from_hex("#ff0000").unwrap(), // Should never be in a port, so highlight in red
-            DataType::NewFile => color_from_hex("#ff0000").unwrap(), // Should never be in a port, so highlight in red
-        }
+        DATA_TYPE_COLORS.with(|colors| colors.get().get(*self))
     }
 
     fn name(&self) -> &str {
@@ -108,6 +198,7 @@ impl DataTypeTrait for DataType {
             DataType::Mesh => "mesh",
             DataType::Enum => "enum",
             DataType::NewFile => "newfile",
+            DataType::StaticMesh => "static_mesh",
         }
     }
 }
@@ -155,6 +246,10 @@ impl NodeDataTrait for NodeData {
                         if ui.add(button).clicked() {
                             responses.push(NodeResponse::User(CustomNodeResponse::ClearActiveNode));
                         }
+                        if ui.button("❄ Freeze").clicked() {
+                            responses
+                                .push(NodeResponse::User(CustomNodeResponse::FreezeNode(node_id)));
+                        }
                     }
                 });
             }
@@ -181,8 +276,21 @@ impl NodeTemplateIter for &NodeDefinitions {
 /// draw the graph itself, then interprets any responses it got and applies the
 /// required side effects.
 pub fn draw_node_graph(ctx: &egui::CtxRef, state: &mut GraphEditorState, defs: &NodeDefinitions) {
-    let responses = state.draw_graph_editor(ctx, defs);
-    for response in responses.node_responses {
+    DATA_TYPE_COLORS.with(|colors| colors.set(state.user_state.data_type_colors));
+    let mut responses = state.draw_graph_editor(ctx, defs).node_responses;
+
+    // Shift+D duplicates the selected node
+    let duplicate_pressed = {
+        let input = ctx.input();
+        input.modifiers.shift && input.key_pressed(egui::Key::D)
+    };
+    if let Some(node_id) = state.selected_node.filter(|_| duplicate_pressed) {
+        responses.push(NodeResponse::User(CustomNodeResponse::DuplicateNode(
+            node_id,
+        )));
+    }
+
+    for response in responses {
         match response {
             NodeResponse::DeleteNode(node_id) => {
                 if state.user_state.active_node == Some(node_id) {
@@ -191,6 +299,10 @@ pub fn draw_node_graph(ctx: &egui::CtxRef, state: &mut GraphEditorState, defs: &
                 if state.user_state.run_side_effect == Some(node_id) {
                     state.user_state.run_side_effect = None;
                 }
+                if state.user_state.freeze_node == Some(node_id) {
+                    state.user_state.freeze_node = None;
+                }
+                state.user_state.selected_nodes.retain(|n| *n != node_id);
             }
             NodeResponse::User(response) => match response {
                 graph::CustomNodeResponse::SetActiveNode(n) => {
@@ -200,8 +312,104 @@ pub fn draw_node_graph(ctx: &egui::CtxRef, state: &mut GraphEditorState, defs: &
                 graph::CustomNodeResponse::RunNodeSideEffect(n) => {
                     state.user_state.run_side_effect = Some(n)
                 }
+                graph::CustomNodeResponse::FreezeNode(n) => state.user_state.freeze_node = Some(n),
+                graph::CustomNodeResponse::DuplicateNode(n) => {
+                    match clipboard::duplicate_node(state, defs, n) {
+                        Ok(new_node) => state.selected_node = Some(new_node),
+                        Err(err) => eprintln!("There was an error duplicating node: {}", err),
+                    }
+                }
             },
             _ => {}
         }
     }
+
+    rubber_band_selection(ctx, state);
+    if let Err(err) = handle_clipboard(ctx, state, defs) {
+        eprintln!("There was an error using the clipboard: {}", err);
+    }
+}
+
+/// Lets the user select multiple nodes by dragging a rectangle while holding
+/// Shift, and highlights the selected nodes.
+fn rubber_band_selection(ctx: &egui::CtxRef, state: &mut GraphEditorState) {
+    let pointer = ctx.input().pointer.clone();
+    let shift = ctx.input().modifiers.shift;
+    let user_state = &mut state.user_state;
+
+    if pointer.primary_pressed() {
+        if shift {
+            user_state.rubber_band_start = pointer.interact_pos();
+        } else {
+            user_state.selected_nodes.clear();
+        }
+    }
+
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("rubber_band_selection"),
+    ));
+    if let (Some(start), Some(current)) = (user_state.rubber_band_start, pointer.hover_pos()) {
+        let rect = egui::Rect::from_two_pos(start, current);
+        if pointer.primary_down() {
+            painter.rect(
+                rect,
+                0.0,
+                egui::Color32::from_rgba_unmultiplied(255, 215, 0, 20),
+                egui::Stroke::new(1.0, egui::Color32::GOLD),
+            );
+        } else {
+            user_state.selected_nodes = state
+                .node_positions
+                .iter()
+                .filter(|(_, pos)| rect.contains(**pos + state.pan_zoom.pan))
+                .map(|(node_id, _)| node_id)
+                .collect();
+            user_state.rubber_band_start = None;
+        }
+    }
+
+    for node_id in &user_state.selected_nodes {
+        if let Some(pos) = state.node_positions.get(*node_id) {
+            painter.circle_filled(*pos + state.pan_zoom.pan, 6.0, egui::Color32::GOLD);
+        }
+    }
+}
+
+/// Copies the selected nodes with Ctrl+C, and pastes them at the mouse cursor
+/// with Ctrl+V, unless a text field has keyboard focus.
+fn handle_clipboard(
+    ctx: &egui::CtxRef,
+    state: &mut GraphEditorState,
+    defs: &NodeDefinitions,
+) -> Result<()> {
+    let (command, copy, paste, pointer_pos) = {
+        let input = ctx.input();
+        (
+            input.modifiers.command,
+            input.key_pressed(egui::Key::C),
+            input.key_pressed(egui::Key::V),
+            input.pointer.hover_pos(),
+        )
+    };
+    // Text fields handle their own copy and paste
+    if !command || ctx.wants_keyboard_input() {
+        return Ok(());
+    }
+    if copy {
+        let nodes = if state.user_state.selected_nodes.is_empty() {
+            state.selected_node.into_iter().collect()
+        } else {
+            state.user_state.selected_nodes.clone()
+        };
+        if !nodes.is_empty() {
+            state.user_state.clipboard = Some(clipboard::copy_nodes(state, &nodes)?);
+        }
+    } else if paste {
+        if let (Some(data), Some(pos)) = (state.user_state.clipboard.clone(), pointer_pos) {
+            let position = pos - state.pan_zoom.pan;
+            state.user_state.selected_nodes = clipboard::paste_nodes(state, defs, &data, position)?;
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,164 @@
+use crate::{prelude::graph::*, prelude::*};
+use egui_node_graph::{NodeId, NodeTemplateTrait};
+use serde::{Deserialize, Serialize};
+
+use super::node_templates::NodeDefinitions;
+
+/// A node stored in the clipboard. Nodes are stored by their definition name
+/// and rebuilt from the node definitions when pasted, so copied nodes always
+/// get fresh ids.
+#[derive(Serialize, Deserialize)]
+struct CopiedNode {
+    op_name: String,
+    label: String,
+    /// The position of this node, relative to the top-left copied node
+    offset: egui::Vec2,
+    /// The constant value of each input, and whether it's shown inline
+    inputs: Vec<(String, ValueType, bool)>,
+}
+
+/// A connection between two copied nodes, referenced by their index in the
+/// clipboard and the parameter names.
+#[derive(Serialize, Deserialize)]
+struct CopiedConnection {
+    output_node: usize,
+    output: String,
+    input_node: usize,
+    input: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CopiedNodes {
+    nodes: Vec<CopiedNode>,
+    connections: Vec<CopiedConnection>,
+}
+
+/// Serializes the given nodes, and the connections between them, to a string
+/// that can later be passed to [`paste_nodes`]. Connections to nodes outside
+/// the copied set are dropped.
+pub fn copy_nodes(state: &GraphEditorState, node_ids: &[NodeId]) -> Result<String> {
+    let graph = &state.graph;
+    let origin = node_ids
+        .iter()
+        .filter_map(|n| state.node_positions.get(*n))
+        .fold(egui::pos2(f32::INFINITY, f32::INFINITY), |acc, pos| {
+            acc.min(*pos)
+        });
+
+    let nodes = node_ids
+        .iter()
+        .map(|&node_id| {
+            let node = &graph[node_id];
+            let position = state.node_positions.get(node_id).copied().unwrap_or(origin);
+            CopiedNode {
+                op_name: node.user_data.op_name.clone(),
+                label: node.label.clone(),
+                offset: position - origin,
+                inputs: node
+                    .inputs
+                    .iter()
+                    .map(|(name, id)| {
+                        (
+                            name.clone(),
+                            graph[*id].value.clone(),
+                            graph[*id].shown_inline,
+                        )
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    let mut connections = vec![];
+    for (input_node, &node_id) in node_ids.iter().enumerate() {
+        for (input, input_id) in &graph[node_id].inputs {
+            if let Some(output_id) = graph.connection(*input_id) {
+                let other = graph[output_id].node;
+                if let Some(output_node) = node_ids.iter().position(|n| *n == other) {
+                    let output = graph[other]
+                        .outputs
+                        .iter()
+                        .find(|(_, id)| *id == output_id)
+                        .map(|(name, _)| name.clone())
+                        .ok_or_else(|| anyhow!("Output {:?} not found in its node", output_id))?;
+                    connections.push(CopiedConnection {
+                        output_node,
+                        output,
+                        input_node,
+                        input: input.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(ron::ser::to_string(&CopiedNodes { nodes, connections })?)
+}
+
+/// Adds the nodes stored in `data`, as returned by [`copy_nodes`], to the
+/// graph. The nodes are placed relative to `position`. Returns the new nodes.
+pub fn paste_nodes(
+    state: &mut GraphEditorState,
+    defs: &NodeDefinitions,
+    data: &str,
+    position: egui::Pos2,
+) -> Result<Vec<NodeId>> {
+    let copied: CopiedNodes = ron::de::from_str(data)?;
+
+    let mut new_nodes = vec![];
+    for node in &copied.nodes {
+        let definition = defs
+            .0
+            .get(&node.op_name)
+            .ok_or_else(|| anyhow!("The {} node is not defined", node.op_name))?;
+        let new_node = state.graph.add_node(
+            node.label.clone(),
+            definition.user_data(),
+            |graph, new_node| definition.build_node(graph, new_node),
+        );
+        for (name, value, shown_inline) in &node.inputs {
+            // Inputs may have changed since the node was copied, so missing
+            // ones are ignored.
+            if let Ok(input) = state.graph[new_node].get_input(name) {
+                state.graph[input].value = value.clone();
+                state.graph[input].shown_inline = *shown_inline;
+            }
+        }
+        state
+            .node_positions
+            .insert(new_node, position + node.offset);
+        state.node_order.push(new_node);
+        new_nodes.push(new_node);
+    }
+
+    for conn in &copied.connections {
+        let output = state.graph[new_nodes[conn.output_node]].get_output(&conn.output);
+        let input = state.graph[new_nodes[conn.input_node]].get_input(&conn.input);
+        if let (Ok(output), Ok(input)) = (output, input) {
+            state.graph.add_connection(output, input);
+        }
+    }
+
+    Ok(new_nodes)
+}
+
+/// The offset between a duplicated node and the original
+const DUPLICATE_OFFSET: egui::Vec2 = egui::vec2(30.0, 30.0);
+
+/// Adds a copy of the given node, with the same parameter values but no
+/// connections, slightly offset from the original. Returns the new node.
+pub fn duplicate_node(
+    state: &mut GraphEditorState,
+    defs: &NodeDefinitions,
+    node_id: NodeId,
+) -> Result<NodeId> {
+    let data = copy_nodes(state, &[node_id])?;
+    let position = state
+        .node_positions
+        .get(node_id)
+        .copied()
+        .unwrap_or(egui::Pos2::ZERO);
+    paste_nodes(state, defs, &data, position + DUPLICATE_OFFSET)?
+        .pop()
+        .ok_or_else(|| anyhow!("Node {:?} could not be duplicated", node_id))
+}
@@ -1,12 +1,13 @@
 use std::collections::BTreeMap;
 
 use anyhow::{anyhow, Result};
-use egui_node_graph::{InputParamKind, NodeTemplateTrait};
+use egui_node_graph::{InputParamKind, NodeId, NodeTemplateTrait};
+use itertools::Itertools;
 use mlua::Table;
 
 use crate::lua_engine::lua_stdlib::Vec3;
 
-use super::{DataType, ValueType};
+use super::{DataType, Graph, ValueType};
 
 #[derive(Clone, Debug)]
 pub struct InputDefinition {
@@ -43,6 +44,19 @@ pub struct NodeDefinition {
 
 pub struct NodeDefinitions(pub BTreeMap<String, NodeDefinition>);
 
+impl NodeDefinitions {
+    /// Updates the nodes of the graph to match these definitions, after the
+    /// node libraries are reloaded. Nodes whose definition no longer exists
+    /// are left untouched.
+    pub fn update_graph(&self, graph: &mut Graph) {
+        for node_id in graph.iter_nodes().collect_vec() {
+            if let Some(definition) = self.0.get(&graph[node_id].user_data.op_name) {
+                definition.update_node(graph, node_id);
+            }
+        }
+    }
+}
+
 fn data_type_from_str(s: &str) -> Result<DataType> {
     match s {
         "vec3" => Ok(DataType::Vector),
@@ -51,10 +65,24 @@ fn data_type_from_str(s: &str) -> Result<DataType> {
         "mesh" => Ok(DataType::Mesh),
         "enum" => Ok(DataType::Enum),
         "file" => Ok(DataType::NewFile),
+        "static_mesh" => Ok(DataType::StaticMesh),
         _ => Err(anyhow!("Invalid datatype in node definition {:?}", s)),
     }
 }
 
+/// Returns the kind of input parameter used for inputs of the given type
+fn input_param_kind(data_type: DataType) -> InputParamKind {
+    match data_type {
+        DataType::Vector => InputParamKind::ConnectionOrConstant,
+        DataType::Scalar => InputParamKind::ConnectionOrConstant,
+        DataType::Selection => InputParamKind::ConnectionOrConstant,
+        DataType::Mesh => InputParamKind::ConnectionOnly,
+        DataType::Enum => InputParamKind::ConstantOnly,
+        DataType::NewFile => InputParamKind::ConstantOnly,
+        DataType::StaticMesh => InputParamKind::ConstantOnly,
+    }
+}
+
 impl InputDefinition {
     pub fn from_lua(table: Table) -> Result<Self> {
         let data_type = data_type_from_str(&table.get::<_, String>("type")?)?;
@@ -78,6 +106,7 @@ impl InputDefinition {
                 selected: table.get::<_, Option<u32>>("selected")?,
             }),
             DataType::NewFile => Some(ValueType::NewFile { path: None }),
+            DataType::StaticMesh => Some(ValueType::StaticMesh { obj: None }),
         };
 
         Ok(InputDefinition {
@@ -121,6 +150,31 @@ impl NodeDefinition {
         })
     }
 
+    /// Updates an existing node of the graph, built from an older version of
+    /// this definition. The inputs and outputs added to the definition since
+    /// are appended to the node. Existing parameters, along with their values
+    /// and connections, are kept as they are.
+    pub fn update_node(&self, graph: &mut Graph, node_id: NodeId) {
+        graph[node_id].user_data = self.user_data();
+        for input in &self.inputs {
+            if graph[node_id].get_input(&input.name).is_err() {
+                graph.add_input_param(
+                    node_id,
+                    input.name.clone(),
+                    input.data_type,
+                    input.value.as_ref().unwrap_or(&ValueType::None).clone(),
+                    input_param_kind(input.data_type),
+                    true,
+                );
+            }
+        }
+        for output in &self.outputs {
+            if graph[node_id].get_output(&output.name).is_err() {
+                graph.add_output_param(node_id, output.name.clone(), output.data_type);
+            }
+        }
+    }
+
     pub fn load_nodes_from_table(table: Table) -> Result<NodeDefinitions> {
         table
             .pairs::<String, Table>()
@@ -160,21 +214,12 @@ impl NodeTemplateTrait for NodeDefinition {
         node_id: egui_node_graph::NodeId,
     ) {
         for input in &self.inputs {
-            let input_param_kind = match input.data_type {
-                DataType::Vector => InputParamKind::ConnectionOrConstant,
-                DataType::Scalar => InputParamKind::ConnectionOrConstant,
-                DataType::Selection => InputParamKind::ConnectionOrConstant,
-                DataType::Mesh => InputParamKind::ConnectionOnly,
-                DataType::Enum => InputParamKind::ConstantOnly,
-                DataType::NewFile => InputParamKind::ConstantOnly,
-            };
-
             graph.add_input_param(
                 node_id,
                 input.name.clone(),
                 input.data_type,
                 input.value.as_ref().unwrap_or(&ValueType::None).clone(),
-                input_param_kind,
+                input_param_kind(input.data_type),
                 true,
             );
         }
@@ -65,6 +65,14 @@ impl WidgetValueTrait for ValueType {
                     }
                 });
             }
+            ValueType::StaticMesh { obj } => {
+                ui.label(param_name);
+                if let Some(obj) = obj {
+                    ui.label(format!("Baked mesh ({} KB)", obj.len() / 1024));
+                } else {
+                    ui.label("No mesh data");
+                }
+            }
         }
     }
 }
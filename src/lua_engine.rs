@@ -1,12 +1,16 @@
 use std::{
+    path::PathBuf,
     sync::mpsc::{self, Receiver},
     time::Duration,
 };
 
-use crate::prelude::{graph::node_templates::NodeDefinitions, *};
+use crate::prelude::{
+    graph::node_templates::NodeDefinitions, halfedge::selection::SelectionExpression, *,
+};
 use mlua::{Function, Lua, Table};
 use notify::{DebouncedEvent, Watcher};
 
+pub mod evaluator;
 pub mod lua_stdlib;
 
 pub trait ToLuaError<T> {
@@ -19,17 +23,50 @@ impl<T> ToLuaError<T> for anyhow::Result<T> {
     }
 }
 
+/// A Lua message handler for `xpcall`. Appends the Lua stack traceback to the
+/// error, so that errors raised in node library scripts point to the script
+/// and line that raised them.
+const TRACEBACK_HANDLER: &str = "function(err) return debug.traceback(tostring(err), 2) end";
+
+/// The values produced by running a graph program
+pub struct ProgramResult {
+    /// The mesh produced by the program's final node
+    pub mesh: HalfEdgeMesh,
+    /// The value of the first of the `selection_outputs` of the final node
+    /// that holds a selection, if any
+    pub selection: Option<SelectionExpression>,
+}
+
+/// Runs a program compiled from the graph. Besides the mesh, the outputs of
+/// the final node named in `selection_outputs` are checked for a selection.
 pub fn run_program<'lua>(
     lua: &'lua Lua,
     lua_program: &str,
     input: Table<'lua>,
-) -> Result<HalfEdgeMesh> {
-    lua.load(lua_program).exec()?;
+    selection_outputs: &[String],
+) -> Result<ProgramResult> {
+    lua.load(lua_program).set_name("graph program")?.exec()?;
     let entry_point: Function = lua.globals().get("main")?;
-    let mesh = entry_point
-        .call::<_, HalfEdgeMesh>(input)
+    let handler: Function = lua.load(TRACEBACK_HANDLER).eval()?;
+    let xpcall: Function = lua.globals().get("xpcall")?;
+    let (ok, result, outputs) = xpcall
+        .call::<_, (bool, mlua::Value, Option<Table>)>((entry_point, handler, input))
         .map_err(|err| anyhow!("{}", err))?;
-    Ok(mesh)
+    if !ok {
+        bail!("{}", lua.unpack::<String>(result)?);
+    }
+    let selection = outputs.and_then(|outputs| {
+        selection_outputs.iter().find_map(|name| {
+            outputs
+                .get::<_, Option<SelectionExpression>>(name.as_str())
+                .ok()
+                .flatten()
+        })
+    });
+    Ok(ProgramResult {
+        mesh: lua.unpack::<HalfEdgeMesh>(result)?,
+        selection,
+    })
 }
 
 pub struct LuaRuntime {
@@ -37,22 +74,70 @@ pub struct LuaRuntime {
     pub node_definitions: NodeDefinitions,
     pub watcher: notify::RecommendedWatcher,
     pub watcher_channel: Receiver<notify::DebouncedEvent>,
+    /// Shared with the long-running operations invoked from this runtime.
+    /// Programs run here block the main thread, so this is only reset before
+    /// each run. See [`evaluator::Evaluator`] for programs that can be
+    /// cancelled from the UI.
+    pub progress: Progress,
+    /// The folders the node libraries are loaded from
+    pub node_library_paths: Vec<PathBuf>,
 }
 
+/// The folder node libraries are loaded from when `NODE_PATH_ENV_VAR` is not
+/// set, relative to the working directory.
 const NODE_LIBRARIES_PATH: &str = "node_libraries";
 
+/// An environment variable with a list of folders to load node libraries
+/// from, separated like the `PATH` variable of the platform.
+pub const NODE_PATH_ENV_VAR: &str = "BLACKJACK_NODE_PATH";
+
+/// Returns the folders node libraries should be loaded from, as configured by
+/// the `BLACKJACK_NODE_PATH` environment variable, or the default
+/// `node_libraries` folder.
+pub fn node_library_paths() -> Vec<PathBuf> {
+    match std::env::var_os(NODE_PATH_ENV_VAR) {
+        Some(paths) if !paths.is_empty() => std::env::split_paths(&paths).collect(),
+        _ => vec![PathBuf::from(NODE_LIBRARIES_PATH)],
+    }
+}
+
+/// Creates a Lua state with all the libraries loaded, including the node
+/// libraries in `node_library_paths`. Long-running operations report to, and
+/// check for cancellation through, the given `progress` handle.
+fn init_lua(
+    progress: &Progress,
+    node_library_paths: &[PathBuf],
+) -> anyhow::Result<(Lua, NodeDefinitions)> {
+    let lua = Lua::new();
+    lua.set_app_data(progress.clone());
+    lua_stdlib::load_host_libraries(&lua)?;
+    lua_stdlib::load_lua_libraries(&lua)?;
+    let node_definitions = lua_stdlib::load_node_libraries(&lua, node_library_paths)?;
+    Ok((lua, node_definitions))
+}
+
 impl LuaRuntime {
     pub fn initialize() -> anyhow::Result<LuaRuntime> {
-        let lua = Lua::new();
-        lua_stdlib::load_host_libraries(&lua)?;
-        lua_stdlib::load_lua_libraries(&lua)?;
-        let node_definitions = lua_stdlib::load_node_libraries(&lua)?;
+        let progress = Progress::new();
+        let node_library_paths = node_library_paths();
+        let (lua, node_definitions) = init_lua(&progress, &node_library_paths)?;
         let (watcher, watcher_channel) = {
             let (tx, rx) = mpsc::channel();
             let mut watcher = notify::watcher(tx, Duration::from_secs(1))?;
-            watcher
-                .watch(NODE_LIBRARIES_PATH, notify::RecursiveMode::Recursive)
-                .unwrap();
+            for path in &node_library_paths {
+                // A missing folder in the node path shouldn't prevent loading
+                // the libraries in the others
+                if !path.is_dir() {
+                    eprintln!(
+                        "Warning: The node library folder {} does not exist",
+                        path.display()
+                    );
+                    continue;
+                }
+                watcher
+                    .watch(path, notify::RecursiveMode::Recursive)
+                    .map_err(|err| anyhow!("Cannot watch {}: {}", path.display(), err))?;
+            }
             (watcher, rx)
         };
 
@@ -61,10 +146,21 @@ impl LuaRuntime {
             node_definitions,
             watcher,
             watcher_channel,
+            progress,
+            node_library_paths,
         })
     }
 
-    pub fn watch_for_changes(&mut self) -> anyhow::Result<()> {
+    /// Sets the global animation time, accessible from Lua as the `Time`
+    /// global variable.
+    pub fn set_time(&self, time: f32) -> anyhow::Result<()> {
+        self.lua.globals().set("Time", time)?;
+        Ok(())
+    }
+
+    /// Reloads the node libraries when any of their files change. Returns
+    /// true when the node definitions were reloaded.
+    pub fn watch_for_changes(&mut self) -> anyhow::Result<bool> {
         if let Ok(msg) = self.watcher_channel.try_recv() {
             match msg {
                 DebouncedEvent::Create(_)
@@ -72,11 +168,13 @@ impl LuaRuntime {
                 | DebouncedEvent::Remove(_)
                 | DebouncedEvent::Rename(_, _) => {
                     println!("Reloading Lua scripts...");
-                    self.node_definitions = lua_stdlib::load_node_libraries(&self.lua)?;
+                    self.node_definitions =
+                        lua_stdlib::load_node_libraries(&self.lua, &self.node_library_paths)?;
+                    return Ok(true);
                 }
                 _ => {}
             }
         }
-        Ok(())
+        Ok(false)
     }
 }
@@ -0,0 +1,240 @@
+use std::{
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    time::{Duration, Instant},
+};
+
+use crate::prelude::{graph::ValueType, *};
+
+use super::{lua_stdlib, ProgramResult};
+
+/// A graph program to be evaluated in the background, along with all the
+/// inputs it needs. Two equal requests produce the same result, so a request
+/// equal to the previous one is not evaluated again.
+#[derive(Clone, PartialEq)]
+pub struct EvaluationRequest {
+    pub lua_program: String,
+    /// The values of the program parameters, as returned by
+    /// [`crate::graph::graph_compiler::extract_param_values`]
+    pub params: Vec<(String, ValueType)>,
+    /// See [`super::run_program`]
+    pub selection_outputs: Vec<String>,
+    /// The animation time, available as the `Time` global variable
+    pub time: f32,
+}
+
+enum Message {
+    Evaluate(EvaluationRequest),
+    ReloadNodeLibraries,
+}
+
+/// The result of a program run on the worker thread.
+struct SendResult(Result<ProgramResult>);
+
+// SAFETY: `HalfEdgeMesh` is not `Send` because its channels are stored in
+// `Rc`s. The mesh in a `ProgramResult` is a deep copy taken out of the Lua
+// state by `run_program`, and cloning a mesh duplicates the channels instead
+// of the `Rc`s, so the worker keeps no reference to anything sent here.
+unsafe impl Send for SendResult {}
+
+/// Runs graph programs on a worker thread, with its own Lua state, so that
+/// the UI stays responsive while they run. Only one program runs at a time.
+/// Requests made meanwhile are queued, and each one replaces the previous.
+pub struct Evaluator {
+    messages: Sender<Message>,
+    results: Receiver<SendResult>,
+    /// Shared with the long-running operations on the worker thread
+    progress: Progress,
+    /// When the running program started, if any
+    running_since: Option<Instant>,
+    queued: Option<EvaluationRequest>,
+    last_request: Option<EvaluationRequest>,
+}
+
+impl Evaluator {
+    /// Spawns the worker thread, which loads the node libraries from the
+    /// given folders.
+    pub fn spawn(node_library_paths: Vec<PathBuf>) -> Evaluator {
+        let (messages, messages_rx) = mpsc::channel();
+        let (results_tx, results) = mpsc::channel();
+        let progress = Progress::new();
+        let worker_progress = progress.clone();
+        std::thread::spawn(move || {
+            Self::worker(node_library_paths, worker_progress, messages_rx, results_tx)
+        });
+        Evaluator {
+            messages,
+            results,
+            progress,
+            running_since: None,
+            queued: None,
+            last_request: None,
+        }
+    }
+
+    fn worker(
+        node_library_paths: Vec<PathBuf>,
+        progress: Progress,
+        messages: Receiver<Message>,
+        results: Sender<SendResult>,
+    ) {
+        let lua = super::init_lua(&progress, &node_library_paths).map(|(lua, _)| lua);
+        for message in messages {
+            match message {
+                Message::Evaluate(request) => {
+                    let result = match &lua {
+                        Ok(lua) => Self::evaluate(lua, &request),
+                        Err(err) => Err(anyhow!("Could not initialize Lua: {}", err)),
+                    };
+                    if results.send(SendResult(result)).is_err() {
+                        break;
+                    }
+                }
+                Message::ReloadNodeLibraries => {
+                    if let Ok(lua) = &lua {
+                        if let Err(err) = lua_stdlib::load_node_libraries(lua, &node_library_paths)
+                        {
+                            eprintln!("There was an error reloading the node libraries: {}", err);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn evaluate(lua: &mlua::Lua, request: &EvaluationRequest) -> Result<ProgramResult> {
+        lua.globals().set("Time", request.time)?;
+        let params = crate::graph::graph_compiler::params_to_lua(lua, &request.params)?;
+        super::run_program(
+            lua,
+            &request.lua_program,
+            params,
+            &request.selection_outputs,
+        )
+    }
+
+    /// Requests evaluating a program. Does nothing when the request is equal
+    /// to the last one.
+    pub fn request(&mut self, request: EvaluationRequest) {
+        if self.last_request.as_ref() == Some(&request) {
+            return;
+        }
+        self.last_request = Some(request.clone());
+        if self.running_since.is_some() {
+            self.queued = Some(request);
+        } else {
+            self.send(request);
+        }
+    }
+
+    fn send(&mut self, request: EvaluationRequest) {
+        self.progress.reset();
+        if self.messages.send(Message::Evaluate(request)).is_ok() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+
+    /// Returns the result of the last program that finished running, if it
+    /// wasn't returned already.
+    pub fn poll(&mut self) -> Option<Result<ProgramResult>> {
+        let result = match self.results.try_recv() {
+            Ok(SendResult(result)) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => {
+                self.running_since.take()?;
+                Err(anyhow!("The evaluation thread stopped unexpectedly"))
+            }
+        };
+        self.running_since = None;
+        if let Some(request) = self.queued.take() {
+            self.send(request);
+        }
+        Some(result)
+    }
+
+    /// Makes the next request be evaluated even if it's equal to the last one
+    pub fn invalidate(&mut self) {
+        self.last_request = None;
+    }
+
+    /// Makes the worker reload its node libraries, and invalidates the last
+    /// request.
+    pub fn reload_node_libraries(&mut self) {
+        let _ = self.messages.send(Message::ReloadNodeLibraries);
+        self.invalidate();
+    }
+
+    /// Returns how long the running program has been running for, if any
+    pub fn running_time(&self) -> Option<Duration> {
+        self.running_since.map(|since| since.elapsed())
+    }
+
+    /// The fraction of the running operation that has been completed
+    pub fn fraction(&self) -> f32 {
+        self.progress.fraction()
+    }
+
+    /// Cancels the running program, and drops any queued request. The last
+    /// request is not evaluated again until something changes.
+    pub fn cancel(&mut self) {
+        self.progress.cancel();
+        self.queued = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn wait_for_result(evaluator: &mut Evaluator) -> Result<ProgramResult> {
+        let start = Instant::now();
+        loop {
+            if let Some(result) = evaluator.poll() {
+                return result;
+            }
+            assert!(start.elapsed() < Duration::from_secs(30));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    pub fn test_evaluator() {
+        let mut evaluator = Evaluator::spawn(vec![]);
+        let request = EvaluationRequest {
+            lua_program: r#"
+                function main(input_params)
+                    local size = input_params.size + Time
+                    local mesh = Primitives.cube(Vec3(0, 0, 0), Vec3(size, size, size))
+                    return mesh, {}
+                end
+            "#
+            .into(),
+            params: vec![(
+                "size".into(),
+                ValueType::Scalar {
+                    value: 1.0,
+                    min: 0.0,
+                    max: 2.0,
+                },
+            )],
+            selection_outputs: vec![],
+            time: 1.0,
+        };
+
+        evaluator.request(request.clone());
+        assert!(evaluator.running_time().is_some());
+        let result = wait_for_result(&mut evaluator).unwrap();
+        assert_eq!(result.mesh.read_connectivity().num_vertices(), 8);
+        assert!(evaluator.running_time().is_none());
+
+        // The same request is not evaluated twice
+        evaluator.request(request.clone());
+        assert!(evaluator.running_time().is_none());
+
+        evaluator.request(EvaluationRequest {
+            lua_program: "function main(input_params) error('failed') end".into(),
+            ..request
+        });
+        assert!(wait_for_result(&mut evaluator).is_err());
+    }
+}
@@ -0,0 +1,22 @@
+-- Non-destructive versions of the in-place mesh operations in `Ops`. Each
+-- function takes the same arguments as its `Ops` counterpart, but runs the
+-- operation on a copy of the mesh, leaving the input untouched. The copy is
+-- returned first, followed by any values returned by the operation.
+--
+-- Only the operations listed in `InPlaceOps` get a functional version. Ops that
+-- already return a new mesh, like `Ops.dual`, don't need one.
+local FunctionalOps = {}
+
+for name, mesh_arg in pairs(InPlaceOps) do
+    local op = Ops[name]
+    assert(op, "Ops." .. name .. " is not defined")
+    FunctionalOps[name] = function(...)
+        local args = table.pack(...)
+        assert(args[mesh_arg], "FunctionalOps." .. name .. " expects a mesh as argument " .. mesh_arg)
+        local mesh = args[mesh_arg]:clone()
+        args[mesh_arg] = mesh
+        return mesh, op(table.unpack(args, 1, args.n))
+    end
+end
+
+return FunctionalOps
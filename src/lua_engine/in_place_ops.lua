@@ -0,0 +1,32 @@
+-- The mesh operations in `Ops` that modify the mesh they're given, instead of
+-- returning a new one. Maps the name of each operation to the position of its
+-- mesh argument.
+local InPlaceOps = {
+    chamfer = 3,
+    bevel = 5,
+    extrude = 3,
+    inset = 3,
+    poke = 3,
+    flip_normals = 2,
+    recompute_normals = 1,
+    bridge = 3,
+    merge = 1,
+    fill_holes = 1,
+    transform = 4,
+    translate = 3,
+    scale = 4,
+    rotate = 5,
+    relax = 4,
+    compute_curvature = 1,
+    compute_normals = 1,
+    smooth = 5,
+    jitter = 4,
+    dissolve_edges = 2,
+    dissolve_vertices = 2,
+    delete_faces = 3,
+    delete_vertices = 2,
+    loop_cut = 3,
+    set_symmetry = 3,
+}
+
+return InPlaceOps
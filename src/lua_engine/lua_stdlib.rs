@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use mlua::{AnyUserData, AsChunk, FromLua, Lua, Table, ToLua, UserData};
 
 use crate::{
@@ -6,6 +8,7 @@ use crate::{
         compact_mesh::CompactMesh,
         graph::node_templates::{NodeDefinition, NodeDefinitions},
         halfedge::{
+            history::{HistoryEntry, HistoryValue},
             id_types::{FaceId, HalfEdgeId, VertexId},
             ChannelKeyType, ChannelValueType, HalfEdgeMesh,
         },
@@ -43,7 +46,8 @@ mod lua_mesh_library;
 mod lua_node_libraries;
 mod lua_primitives_library;
 
-/// Loads pure Lua libraries that are part of the blackjack core APIs
+/// Loads pure Lua libraries that are part of the blackjack core APIs. Some of
+/// them wrap the host libraries, so those must be loaded first.
 pub fn load_lua_libraries(lua: &Lua) -> anyhow::Result<()> {
     macro_rules! def_library {
         ($name:expr, $file:expr) => {
@@ -52,7 +56,10 @@ pub fn load_lua_libraries(lua: &Lua) -> anyhow::Result<()> {
         };
     }
 
+    def_library!("InPlaceOps", "in_place_ops.lua");
+    def_library!("MeshHistory", "mesh_history.lua");
     def_library!("NodeLibrary", "node_library.lua");
+    def_library!("FunctionalOps", "functional_ops.lua");
     Ok(())
 }
 
@@ -66,8 +73,116 @@ pub fn load_host_libraries(lua: &Lua) -> anyhow::Result<()> {
 }
 
 /// Loads all the registered node libraries by running all the files in the
-/// NODE_LIBRARIES_PATH folder and returns the node definitions for the
-/// registered nodes found.
-pub fn load_node_libraries(lua: &Lua) -> anyhow::Result<NodeDefinitions> {
-    lua_node_libraries::load(lua)
+/// given folders and returns the node definitions for the registered nodes
+/// found.
+pub fn load_node_libraries(lua: &Lua, paths: &[PathBuf]) -> anyhow::Result<NodeDefinitions> {
+    lua_node_libraries::load(lua, paths)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn init_lua() -> Lua {
+        let lua = Lua::new();
+        load_host_libraries(&lua).unwrap();
+        load_lua_libraries(&lua).unwrap();
+        lua
+    }
+
+    #[test]
+    pub fn test_replay_history() {
+        let lua = init_lua();
+        let (original, replayed): (HalfEdgeMesh, HalfEdgeMesh) = lua
+            .load(
+                r#"
+                local mesh = Blackjack.empty_mesh()
+                mesh:enable_history()
+                Ops.merge(mesh, Primitives.cube(Vec3(0, 0, 0), Vec3(1, 1, 1)))
+                Ops.extrude(Blackjack.selection("0"), 0.5, mesh)
+                Ops.translate(Blackjack.selection("*"), Vec3(1, 2, 3), mesh)
+                local replayed = MeshHistory.replay(mesh:history(), Blackjack.empty_mesh())
+                return mesh, replayed
+                "#,
+            )
+            .eval()
+            .unwrap();
+
+        let (conn_a, conn_b) = (original.read_connectivity(), replayed.read_connectivity());
+        assert_eq!(conn_a.num_vertices(), conn_b.num_vertices());
+        assert_eq!(conn_a.num_faces(), conn_b.num_faces());
+        // The cube and the sides of the extruded face
+        assert_eq!(conn_a.num_faces(), 6 + 4);
+
+        let (pos_a, pos_b) = (original.read_positions(), replayed.read_positions());
+        for (v, _) in conn_a.iter_vertices() {
+            assert!(pos_a[v].distance(pos_b[v]) < 1e-6);
+        }
+    }
+
+    #[test]
+    pub fn test_chamfer_returns_new_faces() {
+        let lua = init_lua();
+        let (mesh, faces): (HalfEdgeMesh, SelectionExpression) = lua
+            .load(
+                r#"
+                local mesh = Primitives.cube(Vec3(0, 0, 0), Vec3(1, 1, 1))
+                local faces = Ops.chamfer(Blackjack.selection("0, 1"), 0.25, mesh)
+                return mesh, faces
+                "#,
+            )
+            .eval()
+            .unwrap();
+
+        let conn = mesh.read_connectivity();
+        let faces: HashSet<FaceId> = conn
+            .resolve_face_selection_full(faces)
+            .into_iter()
+            .collect();
+        // The cube only has quads, so the triangles are the chamfered corners
+        let triangles: HashSet<FaceId> = conn
+            .iter_faces()
+            .map(|(f, _)| f)
+            .filter(|f| conn.face_vertices(*f).len() == 3)
+            .collect();
+        assert_eq!(faces.len(), 2);
+        assert_eq!(faces, triangles);
+    }
+
+    #[test]
+    pub fn test_program_selection_output() {
+        let lua = init_lua();
+        // Compiled programs return the mesh and the table of node outputs
+        let program = r#"
+            function main(input_params)
+                local mesh = Primitives.cube(Vec3(0, 0, 0), Vec3(1, 1, 1))
+                local faces = Ops.chamfer(Blackjack.selection("0"), 0.25, mesh)
+                local outputs = {out_mesh = mesh, new_faces = faces}
+                return outputs.out_mesh, outputs
+            end
+        "#;
+        let run = |selection_outputs: &[String]| {
+            let params = lua.create_table().unwrap();
+            crate::lua_engine::run_program(&lua, program, params, selection_outputs).unwrap()
+        };
+
+        let result = run(&["missing".into(), "new_faces".into()]);
+        let conn = result.mesh.read_connectivity();
+        let faces = conn.resolve_face_selection_full(result.selection.clone().unwrap());
+        assert_eq!(faces.len(), 1);
+        assert_eq!(conn.face_vertices(faces[0]).len(), 3);
+
+        assert!(run(&[]).selection.is_none());
+    }
+
+    #[test]
+    pub fn test_history_requires_empty_mesh() {
+        let lua = init_lua();
+        let result = lua
+            .load("Primitives.cube(Vec3(0, 0, 0), Vec3(1, 1, 1)):enable_history()")
+            .exec();
+        assert!(result.is_err());
+    }
 }
@@ -27,6 +27,19 @@ pub fn load(lua: &Lua) -> anyhow::Result<()> {
         })?))
     });
 
+    lua_fn!(
+        lua,
+        blackjack,
+        "mesh_from_obj",
+        |obj: mlua::String| -> HalfEdgeMesh {
+            HalfEdgeMesh::from_wavefront_obj_str(obj.to_str()?).map_lua_err()
+        }
+    );
+
+    lua_fn!(lua, blackjack, "empty_mesh", || -> HalfEdgeMesh {
+        Ok(HalfEdgeMesh::new())
+    });
+
     lua_fn!(lua, blackjack, "perlin", || -> PerlinNoise {
         Ok(PerlinNoise(noise::Perlin::new()))
     });
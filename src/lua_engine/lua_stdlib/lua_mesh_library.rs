@@ -1,9 +1,22 @@
 use std::{cell::RefCell, rc::Rc};
 
-use crate::prelude::halfedge::{DynChannel, RawChannelId};
+use crate::prelude::{
+    halfedge::{
+        history::MeshHistory, ChannelOp, ChannelOperand, DynChannel, MeshConnectivity, RawChannelId,
+    },
+    Progress,
+};
 
 use super::*;
 
+/// Returns the progress handle of the runtime, which long-running operations
+/// report to and check for cancellation.
+fn progress(lua: &Lua) -> Progress {
+    lua.app_data_ref::<Progress>()
+        .map(|p| p.clone())
+        .unwrap_or_default()
+}
+
 pub fn load(lua: &Lua) -> anyhow::Result<()> {
     let globals = lua.globals();
     let ops = lua.create_table()?;
@@ -12,26 +25,32 @@ pub fn load(lua: &Lua) -> anyhow::Result<()> {
     lua_fn!(lua, ops, "chamfer", |vertices: SelectionExpression,
                                   amount: f32,
                                   mesh: AnyUserData|
-     -> () {
+     -> SelectionExpression {
         let mesh = mesh.borrow_mut::<HalfEdgeMesh>()?;
         mesh.write_connectivity().clear_debug();
         let verts = mesh
             .read_connectivity()
             .resolve_vertex_selection_full(vertices);
+        let verts = mesh.symmetric_vertex_selection(verts);
+        let mut faces = vec![];
         for v in verts {
-            crate::mesh::halfedge::edit_ops::chamfer_vertex(
+            let (face, _) = crate::mesh::halfedge::edit_ops::chamfer_vertex(
                 &mut mesh.write_connectivity(),
                 &mut mesh.write_positions(),
                 v,
                 amount,
             )
             .map_lua_err()?;
+            faces.push(face);
         }
-        Ok(())
+        let conn = mesh.read_connectivity();
+        Ok(conn.face_selection_from_ids(&faces))
     });
 
     lua_fn!(lua, ops, "bevel", |edges: SelectionExpression,
                                 amount: f32,
+                                segments: u32,
+                                profile: f32,
                                 mesh: AnyUserData|
      -> () {
         let result = mesh.borrow_mut::<HalfEdgeMesh>()?;
@@ -39,17 +58,125 @@ pub fn load(lua: &Lua) -> anyhow::Result<()> {
             let edges = result
                 .read_connectivity()
                 .resolve_halfedge_selection_full(edges);
+            let edges = result.symmetric_halfedge_selection(edges).map_lua_err()?;
             crate::mesh::halfedge::edit_ops::bevel_edges(
                 &mut result.write_connectivity(),
                 &mut result.write_positions(),
                 &edges,
                 amount,
+                segments,
+                profile,
+            )
+            .map_lua_err()?;
+        }
+        Ok(())
+    });
+
+    lua_fn!(lua, ops, "dissolve_edges", |edges: SelectionExpression,
+                                         mesh: AnyUserData|
+     -> () {
+        let result = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        {
+            let edges = result
+                .read_connectivity()
+                .resolve_halfedge_selection_full(edges);
+            let edges = result.symmetric_halfedge_selection(edges).map_lua_err()?;
+            crate::mesh::halfedge::edit_ops::dissolve_edges(
+                &mut result.write_connectivity(),
+                &edges,
             )
             .map_lua_err()?;
         }
         Ok(())
     });
 
+    lua_fn!(
+        lua,
+        ops,
+        "dissolve_vertices",
+        |verts: SelectionExpression, mesh: AnyUserData| -> () {
+            let result = mesh.borrow_mut::<HalfEdgeMesh>()?;
+            {
+                let verts = result
+                    .read_connectivity()
+                    .resolve_vertex_selection_full(verts);
+                let verts = result.symmetric_vertex_selection(verts);
+                crate::mesh::halfedge::edit_ops::dissolve_vertices(
+                    &mut result.write_connectivity(),
+                    &verts,
+                )
+                .map_lua_err()?;
+            }
+            Ok(())
+        }
+    );
+
+    lua_fn!(lua, ops, "delete_faces", |faces: SelectionExpression,
+                                       keep_boundary: bool,
+                                       mesh: AnyUserData|
+     -> () {
+        let result = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        {
+            let faces = result
+                .read_connectivity()
+                .resolve_face_selection_full(faces);
+            let faces = result.symmetric_face_selection(faces).map_lua_err()?;
+            crate::mesh::halfedge::edit_ops::delete_faces(
+                &mut result.write_connectivity(),
+                &faces,
+                keep_boundary,
+            )
+            .map_lua_err()?;
+        }
+        Ok(())
+    });
+
+    lua_fn!(lua, ops, "delete_vertices", |verts: SelectionExpression,
+                                          mesh: AnyUserData|
+     -> () {
+        let result = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        {
+            let verts = result
+                .read_connectivity()
+                .resolve_vertex_selection_full(verts);
+            let verts = result.symmetric_vertex_selection(verts);
+            crate::mesh::halfedge::edit_ops::delete_vertices(
+                &mut result.write_connectivity(),
+                &verts,
+            )
+            .map_lua_err()?;
+        }
+        Ok(())
+    });
+
+    lua_fn!(lua, ops, "loop_cut", |start_edge: SelectionExpression,
+                                   cuts: u32,
+                                   mesh: AnyUserData|
+     -> SelectionExpression {
+        let result = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        let start = result
+            .read_connectivity()
+            .resolve_halfedge_selection_full(start_edge)
+            .first()
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("The loop cut needs a starting edge"))
+            .map_lua_err()?;
+        let edge_loop =
+            crate::mesh::halfedge::selection::edge_loop(&result.read_connectivity(), start)
+                .map_lua_err()?;
+        let new_edges = crate::mesh::halfedge::edit_ops::loop_cut(
+            &mut result.write_connectivity(),
+            &mut result.write_positions(),
+            &edge_loop,
+            cuts,
+        )
+        .map_lua_err()?;
+        let selection = result
+            .read_connectivity()
+            .halfedge_selection_from_ids(&new_edges);
+        Ok(selection)
+    });
+
     lua_fn!(lua, ops, "extrude", |faces: SelectionExpression,
                                   amount: f32,
                                   mesh: AnyUserData|
@@ -59,6 +186,7 @@ pub fn load(lua: &Lua) -> anyhow::Result<()> {
             let faces = result
                 .read_connectivity()
                 .resolve_face_selection_full(faces);
+            let faces = result.symmetric_face_selection(faces).map_lua_err()?;
             crate::mesh::halfedge::edit_ops::extrude_faces(
                 &mut result.write_connectivity(),
                 &mut result.write_positions(),
@@ -70,6 +198,85 @@ pub fn load(lua: &Lua) -> anyhow::Result<()> {
         Ok(())
     });
 
+    lua_fn!(lua, ops, "inset", |faces: SelectionExpression,
+                                amount: f32,
+                                mesh: AnyUserData|
+     -> SelectionExpression {
+        let result = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        let faces = result
+            .read_connectivity()
+            .resolve_face_selection_full(faces);
+        let faces = result.symmetric_face_selection(faces).map_lua_err()?;
+        let inner = crate::mesh::halfedge::edit_ops::inset_faces(
+            &mut result.write_connectivity(),
+            &mut result.write_positions(),
+            &faces,
+            amount,
+        )
+        .map_lua_err()?;
+        let conn = result.read_connectivity();
+        Ok(conn.face_selection_from_ids(&inner))
+    });
+
+    lua_fn!(lua, ops, "poke", |faces: SelectionExpression,
+                               offset: f32,
+                               mesh: AnyUserData|
+     -> SelectionExpression {
+        let result = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        let faces = result
+            .read_connectivity()
+            .resolve_face_selection_full(faces);
+        let faces = result.symmetric_face_selection(faces).map_lua_err()?;
+        let centers = crate::mesh::halfedge::edit_ops::poke_faces(
+            &mut result.write_connectivity(),
+            &mut result.write_positions(),
+            &faces,
+            offset,
+        )
+        .map_lua_err()?;
+        let conn = result.read_connectivity();
+        Ok(conn.vertex_selection_from_ids(&centers))
+    });
+
+    lua_fn!(lua, ops, "flip_normals", |faces: SelectionExpression,
+                                       mesh: AnyUserData|
+     -> () {
+        let mesh = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        let faces = mesh.read_connectivity().resolve_face_selection_full(faces);
+        let faces = mesh.symmetric_face_selection(faces).map_lua_err()?;
+        crate::mesh::halfedge::edit_ops::flip_faces(&mut mesh.write_connectivity(), &faces)
+            .map_lua_err()?;
+        Ok(())
+    });
+
+    lua_fn!(lua, ops, "recompute_normals", |mesh: AnyUserData| -> () {
+        let mesh = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        crate::mesh::halfedge::edit_ops::recompute_normals(
+            &mut mesh.write_connectivity(),
+            &mesh.read_positions(),
+        )
+        .map_lua_err()?;
+        Ok(())
+    });
+
+    lua_fn!(lua, ops, "bridge", |loop_a: SelectionExpression,
+                                 loop_b: SelectionExpression,
+                                 mesh: AnyUserData|
+     -> SelectionExpression {
+        let mesh = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        let mut conn = mesh.write_connectivity();
+        let loop_a = conn.resolve_halfedge_selection_full(loop_a);
+        let loop_b = conn.resolve_halfedge_selection_full(loop_b);
+        let faces = crate::mesh::halfedge::edit_ops::bridge_loops(
+            &mut conn,
+            &mesh.read_positions(),
+            &loop_a,
+            &loop_b,
+        )
+        .map_lua_err()?;
+        Ok(conn.face_selection_from_ids(&faces))
+    });
+
     lua_fn!(lua, ops, "merge", |a: AnyUserData, b: AnyUserData| -> () {
         let mut a = a.borrow_mut::<HalfEdgeMesh>()?;
         let b = b.borrow::<HalfEdgeMesh>()?;
@@ -77,15 +284,527 @@ pub fn load(lua: &Lua) -> anyhow::Result<()> {
         Ok(())
     });
 
+    lua_fn!(lua, ops, "instance", |base: AnyUserData,
+                                   points: Vec<Vec3>,
+                                   seed: Option<u32>,
+                                   min_scale: Option<f32>,
+                                   max_scale: Option<f32>,
+                                   rotation_axis: Option<Vec3>|
+     -> HalfEdgeMesh {
+        use crate::mesh::halfedge::edit_ops::{
+            instance_on_points_with_variation, InstanceVariation,
+        };
+        let base = base.borrow::<HalfEdgeMesh>()?;
+        let points = points.iter().map(|p| p.0).collect::<Vec<_>>();
+        let variation = InstanceVariation {
+            seed: seed.unwrap_or(0) as u64,
+            min_scale: min_scale.unwrap_or(1.0),
+            max_scale: max_scale.or(min_scale).unwrap_or(1.0),
+            rotation_axis: rotation_axis.map(|axis| axis.0),
+        };
+        instance_on_points_with_variation(&base, &points, None, &variation).map_lua_err()
+    });
+
+    lua_fn!(lua, ops, "array", |mesh: AnyUserData,
+                                count: u32,
+                                offset: Vec3,
+                                merge_adjacent: Option<bool>|
+     -> HalfEdgeMesh {
+        let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+        crate::mesh::halfedge::edit_ops::array(
+            &mesh,
+            count,
+            offset.0,
+            merge_adjacent.unwrap_or(false),
+        )
+        .map_lua_err()
+    });
+
+    lua_fn!(lua, ops, "fill_holes", |mesh: AnyUserData,
+                                     max_edges: usize|
+     -> usize {
+        let mesh = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        let new_faces =
+            crate::mesh::halfedge::edit_ops::fill_holes(&mut mesh.write_connectivity(), max_edges)
+                .map_lua_err()?;
+        Ok(new_faces.len())
+    });
+
+    lua_fn!(lua, ops, "extract_edges", |edges: SelectionExpression,
+                                        mesh: AnyUserData|
+     -> Vec<Vec<Vec3>> {
+        let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+        let conn = mesh.read_connectivity();
+        let halfedges = conn.resolve_halfedge_selection_full(edges);
+        let edges = crate::mesh::halfedge::edit_ops::extract_edges(
+            &conn,
+            &mesh.read_positions(),
+            &halfedges,
+        )
+        .map_lua_err()?;
+        Ok(edges
+            .into_iter()
+            .map(|(a, b)| vec![Vec3(a), Vec3(b)])
+            .collect())
+    });
+
+    lua_fn!(lua, ops, "dual", |mesh: AnyUserData| -> HalfEdgeMesh {
+        let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+        crate::mesh::halfedge::edit_ops::dual(&mesh.read_connectivity(), &mesh.read_positions())
+            .map_lua_err()
+    });
+
+    lua_fn!(lua, ops, "solidify", |mesh: AnyUserData,
+                                   thickness: f32|
+     -> HalfEdgeMesh {
+        let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+        crate::mesh::halfedge::edit_ops::solidify(
+            &mesh.read_connectivity(),
+            &mesh.read_positions(),
+            thickness,
+        )
+        .map_lua_err()
+    });
+
+    lua_fn!(
+        lua,
+        ops,
+        "convex_hull",
+        |mesh: AnyUserData| -> HalfEdgeMesh {
+            let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+            let conn = mesh.read_connectivity();
+            let positions = mesh.read_positions();
+            let points = conn
+                .iter_vertices()
+                .map(|(v, _)| positions[v])
+                .collect_vec();
+            crate::mesh::halfedge::edit_ops::convex_hull(&points).map_lua_err()
+        }
+    );
+
+    lua_fn!(lua, ops, "mirror", |origin: Vec3,
+                                 normal: Vec3,
+                                 weld: bool,
+                                 mesh: AnyUserData|
+     -> HalfEdgeMesh {
+        let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+        crate::mesh::halfedge::edit_ops::mirror(
+            &mesh.read_connectivity(),
+            &mesh.read_positions(),
+            origin.0,
+            normal.0,
+            weld,
+        )
+        .map_lua_err()
+    });
+
+    lua_fn!(lua, ops, "symmetrize", |axis: String,
+                                     positive_to_negative: bool,
+                                     mesh: AnyUserData|
+     -> HalfEdgeMesh {
+        use crate::mesh::halfedge::selection::SymmetryAxis;
+        let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+        let axis = match axis.as_str() {
+            "X" | "x" => SymmetryAxis::X,
+            "Y" | "y" => SymmetryAxis::Y,
+            "Z" | "z" => SymmetryAxis::Z,
+            other => {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "Invalid symmetry axis: {other}"
+                )))
+            }
+        };
+        crate::mesh::halfedge::edit_ops::symmetrize(
+            &mesh.read_connectivity(),
+            &mesh.read_positions(),
+            axis,
+            positive_to_negative,
+        )
+        .map_lua_err()
+    });
+
+    lua_fn!(lua, ops, "revolve", |profile: AnyUserData,
+                                  axis_origin: Vec3,
+                                  axis_dir: Vec3,
+                                  angle: f32,
+                                  segments: u32|
+     -> HalfEdgeMesh {
+        let profile = profile.borrow::<HalfEdgeMesh>()?;
+        crate::mesh::halfedge::edit_ops::revolve(
+            &profile,
+            axis_origin.0,
+            axis_dir.0,
+            angle,
+            segments,
+        )
+        .map_lua_err()
+    });
+
+    lua_fn!(lua, ops, "sweep", |profile: AnyUserData,
+                                path: Vec<Vec3>|
+     -> HalfEdgeMesh {
+        let profile = profile.borrow::<HalfEdgeMesh>()?;
+        let path = path.iter().map(|p| p.0).collect::<Vec<_>>();
+        crate::mesh::halfedge::edit_ops::sweep(&profile, &path).map_lua_err()
+    });
+
+    lua_fn!(lua, ops, "wireframe", |mesh: AnyUserData,
+                                    thickness: f32|
+     -> HalfEdgeMesh {
+        let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+        crate::mesh::halfedge::edit_ops::wireframe_mesh(
+            &mesh.read_connectivity(),
+            &mesh.read_positions(),
+            thickness,
+        )
+        .map_lua_err()
+    });
+
+    lua_fn!(lua, ops, "transform", |translate: Vec3,
+                                    rotate: Vec3,
+                                    scale: Vec3,
+                                    mesh: AnyUserData|
+     -> () {
+        let mesh = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        let transform = glam::Mat4::from_scale_rotation_translation(
+            scale.0,
+            glam::Quat::from_euler(
+                glam::EulerRot::XYZ,
+                rotate.0.x.to_radians(),
+                rotate.0.y.to_radians(),
+                rotate.0.z.to_radians(),
+            ),
+            translate.0,
+        );
+        for (_, pos) in mesh.write_positions().iter_mut() {
+            *pos = transform.transform_point3(*pos);
+        }
+        Ok(())
+    });
+
+    lua_fn!(lua, ops, "translate", |verts: SelectionExpression,
+                                    offset: Vec3,
+                                    mesh: AnyUserData|
+     -> () {
+        transform_selection(verts, glam::Mat4::from_translation(offset.0), &mesh)
+    });
+
+    lua_fn!(lua, ops, "scale", |verts: SelectionExpression,
+                                pivot: Vec3,
+                                factors: Vec3,
+                                mesh: AnyUserData|
+     -> () {
+        let matrix = glam::Mat4::from_translation(pivot.0)
+            * glam::Mat4::from_scale(factors.0)
+            * glam::Mat4::from_translation(-pivot.0);
+        transform_selection(verts, matrix, &mesh)
+    });
+
+    lua_fn!(lua, ops, "rotate", |verts: SelectionExpression,
+                                 pivot: Vec3,
+                                 axis: Vec3,
+                                 degrees: f32,
+                                 mesh: AnyUserData|
+     -> () {
+        let axis = axis.0.normalize_or_zero();
+        if axis == glam::Vec3::ZERO {
+            return Err(mlua::Error::RuntimeError(
+                "The rotation axis can't be zero".into(),
+            ));
+        }
+        let matrix = glam::Mat4::from_translation(pivot.0)
+            * glam::Mat4::from_axis_angle(axis, degrees.to_radians())
+            * glam::Mat4::from_translation(-pivot.0);
+        transform_selection(verts, matrix, &mesh)
+    });
+
+    lua_fn!(lua, ops, "relax", |pinned: SelectionExpression,
+                                iterations: usize,
+                                stiffness: f32,
+                                mesh: AnyUserData|
+     -> () {
+        let mesh = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        let pinned = mesh
+            .read_connectivity()
+            .resolve_vertex_selection_full(pinned);
+        let pinned = mesh.symmetric_vertex_selection(pinned);
+        let conn = mesh.read_connectivity();
+        crate::mesh::halfedge::edit_ops::relax_gravity(
+            &conn,
+            &mut mesh.write_positions(),
+            &pinned,
+            iterations,
+            stiffness,
+            &progress(lua),
+        )
+        .map_lua_err()?;
+        Ok(())
+    });
+
+    lua_fn!(lua, ops, "compute_curvature", |mesh: AnyUserData| -> () {
+        let mut mesh = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        crate::mesh::halfedge::edit_ops::compute_curvature(&mut mesh).map_lua_err()?;
+        Ok(())
+    });
+
+    lua_fn!(lua, ops, "compute_normals", |mesh: AnyUserData,
+                                          weighting: Option<String>|
+     -> () {
+        use crate::mesh::halfedge::edit_ops::NormalWeighting;
+        let mut mesh = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        let weighting = match weighting.as_deref() {
+            None => NormalWeighting::default(),
+            Some("uniform") => NormalWeighting::Uniform,
+            Some("area") => NormalWeighting::Area,
+            Some("angle") => NormalWeighting::Angle,
+            Some(other) => {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "Invalid normal weighting: {other}"
+                )))
+            }
+        };
+        crate::mesh::halfedge::edit_ops::compute_vertex_normals(&mut mesh, weighting)
+            .map_lua_err()?;
+        Ok(())
+    });
+
+    lua_fn!(lua, ops, "smooth", |verts: SelectionExpression,
+                                 iterations: u32,
+                                 factor: f32,
+                                 pin_boundary: bool,
+                                 mesh: AnyUserData|
+     -> () {
+        let mesh = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        let verts = mesh
+            .read_connectivity()
+            .resolve_vertex_selection_full(verts);
+        let verts = mesh.symmetric_vertex_selection(verts);
+        let conn = mesh.read_connectivity();
+        crate::mesh::halfedge::edit_ops::laplacian_smooth(
+            &conn,
+            &mut mesh.write_positions(),
+            &verts,
+            iterations,
+            factor,
+            pin_boundary,
+            &progress(lua),
+        )
+        .map_lua_err()?;
+        Ok(())
+    });
+
+    lua_fn!(lua, ops, "jitter", |verts: SelectionExpression,
+                                 amount: f32,
+                                 seed: u64,
+                                 mesh: AnyUserData|
+     -> () {
+        let mesh = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        let verts = mesh
+            .read_connectivity()
+            .resolve_vertex_selection_full(verts);
+        let mirrors = mesh.symmetric_vertex_pairs(&verts);
+        crate::mesh::halfedge::edit_ops::jitter_vertices(
+            &mesh.read_connectivity(),
+            &mut mesh.write_positions(),
+            &verts,
+            amount,
+            seed,
+        )
+        .map_lua_err()?;
+        mesh.mirror_positions(&mirrors);
+        Ok(())
+    });
+
     lua_fn!(lua, ops, "subdivide", |mesh: AnyUserData,
                                     iterations: usize,
                                     catmull_clark: bool|
      -> HalfEdgeMesh {
         let mesh = &mesh.borrow::<HalfEdgeMesh>()?;
         let new_mesh = CompactMesh::<false>::from_halfedge(mesh).map_lua_err()?;
-        Ok(new_mesh
-            .subdivide_multi(iterations, catmull_clark)
-            .to_halfedge())
+        let mut result = new_mesh
+            .subdivide_multi_with_progress(iterations, catmull_clark, &progress(lua))
+            .map_lua_err()?
+            .to_halfedge_from_source(mesh)
+            .map_lua_err()?;
+        result.symmetry = mesh.symmetry;
+        Ok(result)
+    });
+
+    lua_fn!(lua, ops, "subdivide_faces", |faces: SelectionExpression,
+                                          iterations: u32,
+                                          mesh: AnyUserData|
+     -> HalfEdgeMesh {
+        let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+        let faces = mesh.read_connectivity().resolve_face_selection_full(faces);
+        let faces = mesh.symmetric_face_selection(faces).map_lua_err()?;
+        let mut result = crate::mesh::halfedge::edit_ops::subdivide_selection(
+            &mesh.read_connectivity(),
+            &mesh.read_positions(),
+            &faces,
+            iterations,
+            &progress(lua),
+        )
+        .map_lua_err()?;
+        result.symmetry = mesh.symmetry;
+        Ok(result)
+    });
+
+    lua_fn!(lua, ops, "set_symmetry", |axis: Option<String>,
+                                       offset: Option<f32>,
+                                       mesh: AnyUserData|
+     -> () {
+        use crate::mesh::halfedge::selection::{SymmetryAxis, SymmetryPlane};
+        let mut mesh = mesh.borrow_mut::<HalfEdgeMesh>()?;
+        let axis = match axis.as_deref() {
+            Some("X" | "x") => Some(SymmetryAxis::X),
+            Some("Y" | "y") => Some(SymmetryAxis::Y),
+            Some("Z" | "z") => Some(SymmetryAxis::Z),
+            None | Some("None") => None,
+            Some(other) => {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "Invalid symmetry axis: {other}"
+                )))
+            }
+        };
+        mesh.symmetry = axis.map(|axis| SymmetryPlane {
+            axis,
+            offset: offset.unwrap_or(0.0),
+        });
+        Ok(())
+    });
+
+    lua_fn!(
+        lua,
+        ops,
+        "select_linked",
+        |vertices: SelectionExpression, mesh: AnyUserData| -> SelectionExpression {
+            let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+            let conn = mesh.read_connectivity();
+            let seeds = conn.resolve_vertex_selection_full(vertices);
+            let linked =
+                crate::mesh::halfedge::selection::select_linked(&conn, &seeds).map_lua_err()?;
+            Ok(conn.vertex_selection_from_ids(&linked))
+        }
+    );
+
+    lua_fn!(
+        lua,
+        ops,
+        "select_linked_faces",
+        |faces: SelectionExpression, mesh: AnyUserData| -> SelectionExpression {
+            let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+            let conn = mesh.read_connectivity();
+            let seeds = conn.resolve_face_selection_full(faces);
+            let linked = crate::mesh::halfedge::selection::select_linked_faces(&conn, &seeds)
+                .map_lua_err()?;
+            Ok(conn.face_selection_from_ids(&linked))
+        }
+    );
+
+    lua_fn!(lua, ops, "edge_loop", |mesh: AnyUserData,
+                                    start_edges: SelectionExpression|
+     -> SelectionExpression {
+        let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+        let conn = mesh.read_connectivity();
+        let mut loops = vec![];
+        for start in conn.resolve_halfedge_selection_full(start_edges) {
+            loops.extend(crate::mesh::halfedge::selection::edge_loop(&conn, start).map_lua_err()?);
+        }
+        Ok(conn.halfedge_selection_from_ids(&loops))
+    });
+
+    lua_fn!(
+        lua,
+        ops,
+        "select_boundary",
+        |mesh: AnyUserData| -> SelectionExpression {
+            let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+            let conn = mesh.read_connectivity();
+            Ok(conn.halfedge_selection_from_ids(&conn.boundary_halfedges()))
+        }
+    );
+
+    lua_fn!(lua, ops, "select_by_normal", |mesh: AnyUserData,
+                                           direction: Vec3,
+                                           angle_degrees: f32|
+     -> SelectionExpression {
+        let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+        let conn = mesh.read_connectivity();
+        let faces = crate::mesh::halfedge::selection::select_by_normal(
+            &conn,
+            &mesh.read_positions(),
+            direction.0,
+            angle_degrees,
+        )
+        .map_lua_err()?;
+        Ok(conn.face_selection_from_ids(&faces))
+    });
+
+    lua_fn!(
+        lua,
+        ops,
+        "grow_selection",
+        |vertices: SelectionExpression, mesh: AnyUserData| -> SelectionExpression {
+            let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+            let conn = mesh.read_connectivity();
+            let vertices = conn.resolve_vertex_selection_full(vertices);
+            let vertices = conn.grow_vertex_selection(&vertices).map_lua_err()?;
+            Ok(conn.vertex_selection_from_ids(&vertices))
+        }
+    );
+
+    lua_fn!(
+        lua,
+        ops,
+        "shrink_selection",
+        |vertices: SelectionExpression, mesh: AnyUserData| -> SelectionExpression {
+            let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+            let conn = mesh.read_connectivity();
+            let vertices = conn.resolve_vertex_selection_full(vertices);
+            let vertices = conn.shrink_vertex_selection(&vertices).map_lua_err()?;
+            Ok(conn.vertex_selection_from_ids(&vertices))
+        }
+    );
+
+    lua_fn!(
+        lua,
+        ops,
+        "grow_face_selection",
+        |faces: SelectionExpression, mesh: AnyUserData| -> SelectionExpression {
+            let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+            let conn = mesh.read_connectivity();
+            let faces = conn.resolve_face_selection_full(faces);
+            let faces = conn.grow_face_selection(&faces).map_lua_err()?;
+            Ok(conn.face_selection_from_ids(&faces))
+        }
+    );
+
+    lua_fn!(
+        lua,
+        ops,
+        "shrink_face_selection",
+        |faces: SelectionExpression, mesh: AnyUserData| -> SelectionExpression {
+            let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+            let conn = mesh.read_connectivity();
+            let faces = conn.resolve_face_selection_full(faces);
+            let faces = conn.shrink_face_selection(&faces).map_lua_err()?;
+            Ok(conn.face_selection_from_ids(&faces))
+        }
+    );
+
+    lua_fn!(
+        lua,
+        ops,
+        "split_components",
+        |mesh: AnyUserData| -> Vec<HalfEdgeMesh> {
+            let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+            mesh.split_components().map_lua_err()
+        }
+    );
+
+    lua_fn!(lua, ops, "count_components", |mesh: AnyUserData| -> usize {
+        let mesh = mesh.borrow::<HalfEdgeMesh>()?;
+        Ok(mesh.vertex_components().map_lua_err()?.len())
     });
 
     let types = lua.create_table()?;
@@ -94,21 +813,44 @@ pub fn load(lua: &Lua) -> anyhow::Result<()> {
     types.set("HalfEdgeId", ChannelKeyType::HalfEdgeId)?;
     types.set("Vec3", ChannelValueType::Vec3)?;
     types.set("f32", ChannelValueType::f32)?;
+    types.set("i32", ChannelValueType::i32)?;
+    types.set("String", ChannelValueType::String)?;
     globals.set("Types", types)?;
 
     Ok(())
 }
 
-fn mesh_channel_to_lua_table<'lua>(
-    lua: &'lua Lua,
-    mesh: &HalfEdgeMesh,
+/// Applies `matrix` to the selected vertices of the mesh
+fn transform_selection(
+    verts: SelectionExpression,
+    matrix: glam::Mat4,
+    mesh: &AnyUserData,
+) -> mlua::Result<()> {
+    let mesh = mesh.borrow_mut::<HalfEdgeMesh>()?;
+    let verts = mesh
+        .read_connectivity()
+        .resolve_vertex_selection_full(verts);
+    // The mirror images get the mirrored transform
+    let mirrors = mesh.symmetric_vertex_pairs(&verts);
+    crate::mesh::halfedge::edit_ops::transform(
+        &mesh.read_connectivity(),
+        &mut mesh.write_positions(),
+        &verts,
+        matrix,
+    )
+    .map_lua_err()?;
+    mesh.mirror_positions(&mirrors);
+    Ok(())
+}
+
+/// Returns the ids of all the mesh elements of the given key type, as the raw
+/// u64 values expected by the dynamic channel API.
+fn mesh_channel_keys(
+    conn: &MeshConnectivity,
     kty: ChannelKeyType,
-    vty: ChannelValueType,
-    ch_id: RawChannelId,
-) -> mlua::Result<mlua::Table<'lua>> {
+) -> Box<dyn Iterator<Item = u64> + '_> {
     use slotmap::Key;
-    let conn = mesh.read_connectivity();
-    let keys: Box<dyn Iterator<Item = u64>> = match kty {
+    match kty {
         ChannelKeyType::VertexId => {
             Box::new(conn.iter_vertices().map(|(v_id, _)| v_id.data().as_ffi()))
         }
@@ -116,12 +858,22 @@ fn mesh_channel_to_lua_table<'lua>(
         ChannelKeyType::HalfEdgeId => {
             Box::new(conn.iter_halfedges().map(|(h_id, _)| h_id.data().as_ffi()))
         }
-    };
+    }
+}
+
+fn mesh_channel_to_lua_table<'lua>(
+    lua: &'lua Lua,
+    mesh: &HalfEdgeMesh,
+    kty: ChannelKeyType,
+    vty: ChannelValueType,
+    ch_id: RawChannelId,
+) -> mlua::Result<mlua::Table<'lua>> {
+    let conn = mesh.read_connectivity();
     Ok(mesh
         .channels
         .dyn_read_channel(kty, vty, ch_id)
         .map_lua_err()?
-        .to_table(keys, lua))
+        .to_table(mesh_channel_keys(&conn, kty), lua))
 }
 
 impl UserData for HalfEdgeMesh {
@@ -137,27 +889,67 @@ impl UserData for HalfEdgeMesh {
                 mesh_channel_to_lua_table(lua, this, kty, vty, ch_id)
             },
         );
-        methods.add_method("set_channel", |lua, this, (kty, vty, name, table)| {
-            use slotmap::Key;
-            let name: String = name;
-            let conn = this.read_connectivity();
-            let keys: Box<dyn Iterator<Item = u64>> = match kty {
-                ChannelKeyType::VertexId => {
-                    Box::new(conn.iter_vertices().map(|(v_id, _)| v_id.data().as_ffi()))
-                }
-                ChannelKeyType::FaceId => {
-                    Box::new(conn.iter_faces().map(|(f_id, _)| f_id.data().as_ffi()))
-                }
-                ChannelKeyType::HalfEdgeId => {
-                    Box::new(conn.iter_halfedges().map(|(h_id, _)| h_id.data().as_ffi()))
+        methods.add_method(
+            "channel",
+            |_lua, this, (kty, vty, name): (ChannelKeyType, ChannelValueType, String)| {
+                Ok(SharedChannel(
+                    this.channels
+                        .channel_rc_dyn(kty, vty, &name)
+                        .map_lua_err()?,
+                ))
+            },
+        );
+        methods.add_method_mut(
+            "set_channel",
+            |lua,
+             this,
+             (kty, vty, name, value): (ChannelKeyType, ChannelValueType, String, mlua::Value)| {
+                // The value can be either a table, like the ones returned by
+                // `get_channel`, or a channel object.
+                match value {
+                    mlua::Value::UserData(ud) => {
+                        let channel = ud.borrow::<SharedChannel>()?;
+                        let channel = channel.0.borrow();
+                        this.channels
+                            .set_channel_dyn(kty, vty, &name, &*channel)
+                            .map_lua_err()
+                    }
+                    value => {
+                        let table = mlua::Table::from_lua(value, lua)?;
+                        let conn = this.read_connectivity();
+                        this.channels
+                            .dyn_write_channel_by_name(kty, vty, &name)
+                            .map_lua_err()?
+                            .set_from_table(mesh_channel_keys(&conn, kty), lua, table)
+                            .map_lua_err()
+                    }
                 }
-            };
-            this.channels
-                .dyn_write_channel_by_name(kty, vty, &name)
-                .map_lua_err()?
-                .set_from_table(keys, lua, table)
-                .map_lua_err()
-        });
+            },
+        );
+        methods.add_method_mut(
+            "fill_channel",
+            |lua,
+             this,
+             (kty, vty, name, value): (ChannelKeyType, ChannelValueType, String, mlua::Value)| {
+                this.channels.ensure_channel_dyn(kty, vty, &name);
+                let conn = this.read_connectivity();
+                this.channels
+                    .dyn_write_channel_by_name(kty, vty, &name)
+                    .map_lua_err()?
+                    .fill_lua(mesh_channel_keys(&conn, kty), lua, value)
+                    .map_lua_err()
+            },
+        );
+        methods.add_method(
+            "clear_channel",
+            |_lua, this, (kty, vty, name): (ChannelKeyType, ChannelValueType, String)| {
+                this.channels
+                    .dyn_write_channel_by_name(kty, vty, &name)
+                    .map_lua_err()?
+                    .clear_dyn();
+                Ok(())
+            },
+        );
         methods.add_method_mut(
             "ensure_channel",
             |lua, this, (kty, vty, name): (ChannelKeyType, ChannelValueType, String)| {
@@ -165,27 +957,179 @@ impl UserData for HalfEdgeMesh {
                 mesh_channel_to_lua_table(lua, this, kty, vty, id)
             },
         );
+        methods.add_method_mut(
+            "rename_channel",
+            |_lua,
+             this,
+             (kty, vty, old_name, new_name): (
+                ChannelKeyType,
+                ChannelValueType,
+                String,
+                String,
+            )| {
+                this.channels
+                    .rename_channel_dyn(kty, vty, &old_name, &new_name)
+                    .map_lua_err()
+            },
+        );
+        methods.add_method_mut("preview_channel", |_lua, this, name: String| {
+            this.preview_channel(&name).map_lua_err()
+        });
+        methods.add_method("get_position", |_lua, this, vertex: VertexId| {
+            if !this.read_connectivity().vertex_exists(vertex) {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "Vertex {:?} does not exist",
+                    vertex
+                )));
+            }
+            Ok(Vec3(this.read_positions()[vertex]))
+        });
+        methods.add_method(
+            "set_position",
+            |_lua, this, (vertex, position): (VertexId, Vec3)| {
+                if !this.read_connectivity().vertex_exists(vertex) {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "Vertex {:?} does not exist",
+                        vertex
+                    )));
+                }
+                this.write_positions()[vertex] = position.0;
+                Ok(())
+            },
+        );
+        methods.add_method("face_centroid", |_lua, this, face: FaceId| {
+            let conn = this.read_connectivity();
+            if conn.face(face).is_none() {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "Face {:?} does not exist",
+                    face
+                )));
+            }
+            Ok(Vec3(conn.face_vertex_average(&this.read_positions(), face)))
+        });
+        methods.add_method("face_normal", |_lua, this, face: FaceId| {
+            let conn = this.read_connectivity();
+            if conn.face(face).is_none() {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "Face {:?} does not exist",
+                    face
+                )));
+            }
+            // Degenerate faces have no normal
+            Ok(conn.face_normal(&this.read_positions(), face).map(Vec3))
+        });
+        methods.add_method("stats", |lua, this, ()| {
+            let conn = this.read_connectivity();
+            let boundary_loops = conn.boundary_loops().map_lua_err()?.len();
+            let stats = lua.create_table()?;
+            stats.set("vertices", conn.num_vertices())?;
+            stats.set("edges", conn.num_edges())?;
+            stats.set("faces", conn.num_faces())?;
+            stats.set("boundary_loops", boundary_loops)?;
+            stats.set("closed", boundary_loops == 0)?;
+            // Empty meshes have no bounding box, so these are left as nil
+            if let Some((min, max)) = this.bounding_box() {
+                stats.set("bounds_min", Vec3(min))?;
+                stats.set("bounds_max", Vec3(max))?;
+            }
+            Ok(stats)
+        });
+        methods.add_method("build_accel", |_lua, this, ()| {
+            Ok(crate::mesh::halfedge::accel::BvhAccel::build(this))
+        });
         methods.add_method("iter_vertices", |lua, this, ()| {
             let vertices: Vec<VertexId> = this
                 .read_connectivity()
                 .iter_vertices()
                 .map(|(id, _)| id)
                 .collect();
-            let mut i = 0;
-            lua.create_function_mut(move |lua, ()| {
-                let val = if i < vertices.len() {
-                    vertices[i].to_lua(lua)?
-                } else {
-                    mlua::Value::Nil
-                };
-                i += 1;
-                Ok(val)
-            })
+            lua_iterator(lua, vertices)
+        });
+        methods.add_method("iter_faces", |lua, this, ()| {
+            let faces: Vec<FaceId> = this
+                .read_connectivity()
+                .iter_faces()
+                .map(|(id, _)| id)
+                .collect();
+            lua_iterator(lua, faces)
+        });
+        methods.add_method("iter_halfedges", |lua, this, ()| {
+            let halfedges: Vec<HalfEdgeId> = this
+                .read_connectivity()
+                .iter_halfedges()
+                .map(|(id, _)| id)
+                .collect();
+            lua_iterator(lua, halfedges)
+        });
+        methods.add_method("face_vertices", |lua, this, face: FaceId| {
+            let conn = this.read_connectivity();
+            if conn.face(face).is_none() {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "Face {:?} does not exist",
+                    face
+                )));
+            }
+            lua_iterator(lua, conn.face_vertices(face).to_vec())
         });
         methods.add_method("clone", |_lua, this, ()| Ok(this.clone()));
+        methods.add_method_mut("enable_history", |_lua, this, ()| {
+            if this.history.is_some() {
+                return Ok(());
+            }
+            // The history has no baseline, so replaying it can only reproduce
+            // meshes that started out empty.
+            if this.read_connectivity().num_vertices() > 0 {
+                return Err(mlua::Error::RuntimeError(
+                    "History can only be enabled on an empty mesh. Use Ops.merge to \
+                     add other meshes to it afterwards."
+                        .into(),
+                ));
+            }
+            this.history = Some(MeshHistory::new());
+            Ok(())
+        });
+        methods.add_method("has_history", |_lua, this, ()| Ok(this.history.is_some()));
+        methods.add_method("history", |_lua, this, ()| {
+            Ok(this.history.as_ref().map(|history| history.entries.clone()))
+        });
+        // Called by the wrappers in `mesh_history.lua`. The arguments include
+        // this mesh, at position `mesh_arg`, which doesn't get recorded.
+        methods.add_method_mut(
+            "record_op",
+            |lua, this, (op, mesh_arg, args): (String, usize, mlua::Variadic<mlua::Value>)| {
+                let args = args
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, _)| i + 1 != mesh_arg)
+                    .map(|(_, arg)| HistoryValue::from_lua(arg, lua))
+                    .collect::<mlua::Result<Vec<_>>>()?;
+                if let Some(history) = &mut this.history {
+                    history.record(op, args);
+                }
+                Ok(())
+            },
+        );
     }
 }
 
+/// Returns a Lua function that yields each of the given values in order, and
+/// then nil. Can be used in a generic `for` loop.
+fn lua_iterator<'lua, T: ToLua<'lua> + Copy + 'static>(
+    lua: &'lua Lua,
+    values: Vec<T>,
+) -> mlua::Result<mlua::Function<'lua>> {
+    let mut i = 0;
+    lua.create_function_mut(move |lua, ()| {
+        let val = if i < values.len() {
+            values[i].to_lua(lua)?
+        } else {
+            mlua::Value::Nil
+        };
+        i += 1;
+        Ok(val)
+    })
+}
+
 pub struct SharedChannel(pub Rc<RefCell<dyn DynChannel>>);
 impl Clone for SharedChannel {
     fn clone(&self) -> Self {
@@ -193,8 +1137,58 @@ impl Clone for SharedChannel {
     }
 }
 
+/// Implements an arithmetic metamethod for channels. Either operand can be a
+/// channel, a number or a vector, but at least one of them is a channel.
+fn channel_arithmetic<'lua>(
+    lua: &'lua Lua,
+    op: ChannelOp,
+    lhs: mlua::Value<'lua>,
+    rhs: mlua::Value<'lua>,
+) -> mlua::Result<SharedChannel> {
+    fn as_channel(value: &mlua::Value) -> Option<SharedChannel> {
+        match value {
+            mlua::Value::UserData(ud) => ud.borrow::<SharedChannel>().ok().map(|ch| ch.clone()),
+            _ => None,
+        }
+    }
+    let (channel, other, reversed) = match (as_channel(&lhs), as_channel(&rhs)) {
+        (Some(channel), _) => (channel, rhs, false),
+        (None, Some(channel)) => (channel, lhs, true),
+        (None, None) => {
+            return Err(mlua::Error::RuntimeError(
+                "Channel arithmetic needs at least one channel operand".into(),
+            ))
+        }
+    };
+    let other_channel = as_channel(&other);
+    let other_borrow = other_channel.as_ref().map(|ch| ch.0.borrow());
+    let operand = match (&other_borrow, other) {
+        (Some(other), _) => ChannelOperand::Channel(&**other),
+        (None, mlua::Value::Vector(x, y, z)) => ChannelOperand::Vector(glam::Vec3::new(x, y, z)),
+        (None, value) => ChannelOperand::Scalar(f32::from_lua(value, lua)?),
+    };
+    let result = channel
+        .0
+        .borrow()
+        .binary_op_dyn(op, operand, reversed)
+        .map_lua_err()?;
+    Ok(SharedChannel(result))
+}
+
 impl UserData for SharedChannel {
     fn add_methods<'lua, M: mlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_function(mlua::MetaMethod::Add, |lua, (a, b)| {
+            channel_arithmetic(lua, ChannelOp::Add, a, b)
+        });
+        methods.add_meta_function(mlua::MetaMethod::Sub, |lua, (a, b)| {
+            channel_arithmetic(lua, ChannelOp::Sub, a, b)
+        });
+        methods.add_meta_function(mlua::MetaMethod::Mul, |lua, (a, b)| {
+            channel_arithmetic(lua, ChannelOp::Mul, a, b)
+        });
+        methods.add_meta_function(mlua::MetaMethod::Div, |lua, (a, b)| {
+            channel_arithmetic(lua, ChannelOp::Div, a, b)
+        });
         methods.add_meta_method(
             mlua::MetaMethod::NewIndex,
             |lua, this, (key, val): (mlua::Value, mlua::Value)| {
@@ -215,3 +1209,17 @@ impl UserData for SharedChannel {
         );
     }
 }
+
+impl UserData for crate::mesh::halfedge::accel::BvhAccel {
+    fn add_methods<'lua, M: mlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("nearest_vertex", |_lua, this, point: Vec3| {
+            Ok(this.nearest_vertex(point.0))
+        });
+        methods.add_method("raycast", |_lua, this, (origin, dir): (Vec3, Vec3)| {
+            Ok(match this.raycast(origin.0, dir.0) {
+                Some((face, point)) => (Some(face), Some(Vec3(point))),
+                None => (None, None),
+            })
+        });
+    }
+}
@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, path::PathBuf};
 
 use super::*;
 
@@ -16,9 +16,16 @@ impl<'lua> AsChunk<'lua> for LuaSourceFile {
     }
 }
 
-pub fn load(lua: &Lua) -> anyhow::Result<NodeDefinitions> {
-    for entry in walkdir::WalkDir::new("node_libraries")
-        .into_iter()
+/// Runs all the Lua files inside each of the given folders, in order. When
+/// two files define a node with the same name, the last one wins, and a
+/// warning mentioning both files is printed.
+pub fn load(lua: &Lua, paths: &[PathBuf]) -> anyhow::Result<NodeDefinitions> {
+    let node_library = lua.globals().get::<_, Table>("NodeLibrary")?;
+    // Node definitions may change, so previous results can't be reused
+    node_library.set("cache", lua.create_table()?)?;
+    for entry in paths
+        .iter()
+        .flat_map(|path| walkdir::WalkDir::new(path).into_iter())
         .filter_map(|e| e.ok())
     {
         let is_lua_file = entry.file_type().is_file()
@@ -34,20 +41,19 @@ pub fn load(lua: &Lua) -> anyhow::Result<NodeDefinitions> {
             let path_display = format!("{}", path.display());
 
             println!("Loading Lua file {}", path_display);
+            node_library.set("current_file", path_display.clone())?;
 
             lua.load(&LuaSourceFile {
                 contents: std::fs::read_to_string(path).unwrap_or_else(|err| {
                     format!("error('Error reading file \"{:?}\". {}')", path, err)
                 }),
-                name: path_display,
+                name: path_display.clone(),
             })
-            .exec()?;
+            .exec()
+            .map_err(|err| anyhow::anyhow!("Error loading Lua file {}: {}", path_display, err))?;
         }
     }
 
-    let table = lua
-        .globals()
-        .get::<_, Table>("NodeLibrary")?
-        .get::<_, Table>("nodes")?;
-    NodeDefinition::load_nodes_from_table(table)
+    node_library.set("current_file", mlua::Value::Nil)?;
+    NodeDefinition::load_nodes_from_table(node_library.get::<_, Table>("nodes")?)
 }
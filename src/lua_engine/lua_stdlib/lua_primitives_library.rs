@@ -26,5 +26,40 @@ pub fn load(lua: &Lua) -> anyhow::Result<()> {
         ))
     });
 
+    lua_fn!(lua, primitives, "uv_sphere", |center: Vec3,
+                                           radius: f32,
+                                           rings: u32,
+                                           segments: u32|
+     -> HalfEdgeMesh {
+        crate::mesh::halfedge::primitives::UvSphere::build(center.0, radius, rings, segments)
+            .map_lua_err()
+    });
+
+    lua_fn!(lua, primitives, "icosphere", |center: Vec3,
+                                           radius: f32,
+                                           subdivisions: u32|
+     -> HalfEdgeMesh {
+        crate::mesh::halfedge::primitives::Icosphere::build(center.0, radius, subdivisions)
+            .map_lua_err()
+    });
+
+    lua_fn!(lua, primitives, "cylinder", |center: Vec3,
+                                          radius: f32,
+                                          height: f32,
+                                          segments: u32|
+     -> HalfEdgeMesh {
+        crate::mesh::halfedge::primitives::Cylinder::build(center.0, radius, height, segments)
+            .map_lua_err()
+    });
+
+    lua_fn!(lua, primitives, "cone", |center: Vec3,
+                                      base_radius: f32,
+                                      height: f32,
+                                      segments: u32|
+     -> HalfEdgeMesh {
+        crate::mesh::halfedge::primitives::Cone::build(center.0, base_radius, height, segments)
+            .map_lua_err()
+    });
+
     Ok(())
 }
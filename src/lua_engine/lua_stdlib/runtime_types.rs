@@ -23,6 +23,72 @@ impl<'lua> FromLua<'lua> for Vec3 {
 
 impl UserData for SelectionExpression {}
 
+impl<'lua> FromLua<'lua> for HistoryValue {
+    fn from_lua(lua_value: mlua::Value<'lua>, _lua: &'lua Lua) -> mlua::Result<Self> {
+        let type_name = lua_value.type_name();
+        let value = match lua_value {
+            mlua::Value::Nil => HistoryValue::Nil,
+            mlua::Value::Boolean(b) => HistoryValue::Bool(b),
+            mlua::Value::Integer(i) => HistoryValue::Integer(i),
+            mlua::Value::Number(n) => HistoryValue::Number(n),
+            mlua::Value::String(s) => HistoryValue::String(s.to_str()?.to_owned()),
+            mlua::Value::Vector(x, y, z) => HistoryValue::Vec3(glam::Vec3::new(x, y, z)),
+            mlua::Value::UserData(ud) => {
+                if let Ok(selection) = ud.borrow::<SelectionExpression>() {
+                    HistoryValue::Selection(selection.clone())
+                } else if let Ok(mesh) = ud.borrow::<HalfEdgeMesh>() {
+                    HistoryValue::Mesh(mesh.clone())
+                } else {
+                    return Err(mlua::Error::FromLuaConversionError {
+                        from: type_name,
+                        to: "HistoryValue",
+                        message: Some("Only selections and meshes can be recorded".into()),
+                    });
+                }
+            }
+            _ => {
+                return Err(mlua::Error::FromLuaConversionError {
+                    from: type_name,
+                    to: "HistoryValue",
+                    message: None,
+                })
+            }
+        };
+        Ok(value)
+    }
+}
+impl<'lua> ToLua<'lua> for HistoryValue {
+    fn to_lua(self, lua: &'lua Lua) -> mlua::Result<mlua::Value<'lua>> {
+        match self {
+            HistoryValue::Nil => Ok(mlua::Value::Nil),
+            HistoryValue::Bool(b) => Ok(mlua::Value::Boolean(b)),
+            HistoryValue::Integer(i) => Ok(mlua::Value::Integer(i)),
+            HistoryValue::Number(n) => Ok(mlua::Value::Number(n)),
+            HistoryValue::String(s) => s.to_lua(lua),
+            HistoryValue::Vec3(v) => Vec3(v).to_lua(lua),
+            HistoryValue::Selection(selection) => selection.to_lua(lua),
+            HistoryValue::Mesh(mesh) => mesh.to_lua(lua),
+        }
+    }
+}
+
+/// Entries are converted to a table with the `op` name and a list of `args`.
+/// The list stores its length in the `n` field, like `table.pack`, since some
+/// of the arguments may be nil.
+impl<'lua> ToLua<'lua> for HistoryEntry {
+    fn to_lua(self, lua: &'lua Lua) -> mlua::Result<mlua::Value<'lua>> {
+        let args = lua.create_table()?;
+        args.set("n", self.args.len())?;
+        for (i, arg) in self.args.into_iter().enumerate() {
+            args.set(i + 1, arg)?;
+        }
+        let entry = lua.create_table()?;
+        entry.set("op", self.op)?;
+        entry.set("args", args)?;
+        Ok(mlua::Value::Table(entry))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Path(pub std::path::PathBuf);
 impl UserData for Path {}
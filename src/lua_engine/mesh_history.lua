@@ -0,0 +1,52 @@
+-- Records the operations applied to meshes that have their history enabled
+-- with `mesh:enable_history()`. Every in-place operation in `Ops` is wrapped,
+-- so that after it succeeds, its name and arguments get appended to the
+-- history of the mesh it was applied to.
+--
+-- History can only be enabled on empty meshes. Other meshes, like primitives
+-- or the results of ops returning a new mesh, get recorded by merging them in:
+--
+--     local mesh = Blackjack.empty_mesh()
+--     mesh:enable_history()
+--     Ops.merge(mesh, Primitives.cube(Vec3(0, 0, 0), Vec3(1, 1, 1)))
+--
+-- The recorded history, as returned by `mesh:history()`, can be replayed on
+-- another mesh with `MeshHistory.replay`.
+local MeshHistory = {}
+
+for name, mesh_arg in pairs(InPlaceOps) do
+    local op = Ops[name]
+    assert(op, "Ops." .. name .. " is not defined")
+    Ops[name] = function(...)
+        local results = table.pack(op(...))
+        local mesh = select(mesh_arg, ...)
+        if mesh ~= nil and mesh:has_history() then
+            mesh:record_op(name, mesh_arg, ...)
+        end
+        return table.unpack(results, 1, results.n)
+    end
+end
+
+-- Applies the operations in `history` to `mesh`, in order, and returns the
+-- mesh. Replaying the history of a mesh on `Blackjack.empty_mesh()` produces
+-- a copy of it, with the same element ids.
+function MeshHistory.replay(history, mesh)
+    for _, entry in ipairs(history) do
+        local mesh_arg = InPlaceOps[entry.op]
+        assert(mesh_arg, "Cannot replay the unknown operation " .. entry.op)
+        local args = {}
+        for i = 1, entry.args.n + 1 do
+            if i < mesh_arg then
+                args[i] = entry.args[i]
+            elseif i == mesh_arg then
+                args[i] = mesh
+            else
+                args[i] = entry.args[i - 1]
+            end
+        end
+        Ops[entry.op](table.unpack(args, 1, entry.args.n + 1))
+    end
+    return mesh
+end
+
+return MeshHistory
@@ -1,17 +1,31 @@
 NodeLibrary = {
-    nodes = {}
+    nodes = {},
+    -- The file each node was defined in, by node name
+    origins = {},
+    -- The file being loaded, set by the engine
+    current_file = nil,
+    -- The outputs of the last evaluation of each graph node, by node. See
+    -- `callNodeCached`.
+    cache = {},
+    -- Incremented every time a node is evaluated
+    cache_version = 0,
 }
 
 function NodeLibrary:addNodes(nodes)
     assert(type(nodes) == "table")
 
+    local file = self.current_file or "<unknown>"
     for k, v in pairs(nodes) do
-        if self.nodes[k] then
+        local origin = self.origins[k]
+        if origin and origin ~= file then
+            print("[Engine] Node "..k.." from "..origin.." is redefined by "..file)
+        elseif self.nodes[k] then
             print("[Engine] Redefinition for node "..k)
         else
             print("[Engine] Loading new node definition for "..k)
         end
         self.nodes[k] = v
+        self.origins[k] = file
     end
 end
 
@@ -27,4 +41,49 @@ function NodeLibrary:callNode(node_name, args)
     return self.nodes[node_name].op(args)
 end
 
+-- Like `callNode`, but reuses the outputs from the last time the graph node
+-- identified by `key` was evaluated, when nothing changed since. The `hash`
+-- identifies the node's parameters and connections, and `deps` lists the
+-- keys of the nodes it reads its inputs from, which must have been evaluated
+-- already. The node is evaluated again when its hash changes or when any of
+-- its dependencies got evaluated again.
+--
+-- Executable nodes, and nodes with `cacheable = false` in their definition,
+-- are always evaluated. Cached outputs are shared between evaluations, so
+-- ops must not modify their inputs in place.
+function NodeLibrary:callNodeCached(key, hash, deps, node_name, args)
+    local node = self.nodes[node_name]
+    local dep_versions = {}
+    for i, dep in ipairs(deps) do
+        dep_versions[i] = self.cache[dep].version
+    end
+
+    local entry = self.cache[key]
+    local valid = entry ~= nil and entry.hash == hash and not node.executable
+        and node.cacheable ~= false
+    if valid then
+        for i, version in ipairs(dep_versions) do
+            if entry.dep_versions[i] ~= version then
+                valid = false
+                break
+            end
+        end
+    end
+    if valid then
+        return entry.outputs
+    end
+
+    -- Drop the entry first, so a failing op doesn't leave stale outputs
+    self.cache[key] = nil
+    local outputs = node.op(args)
+    self.cache_version = self.cache_version + 1
+    self.cache[key] = {
+        hash = hash,
+        dep_versions = dep_versions,
+        version = self.cache_version,
+        outputs = outputs,
+    }
+    return outputs
+end
+
 return NodeLibrary
\ No newline at end of file
@@ -31,12 +31,21 @@ pub mod edit_ops;
 /// Import / Export of HalfEdgeMesh data structure to Wavefront OBJ files
 pub mod wavefront_obj;
 
+/// Export of HalfEdgeMesh data structure to STL files, for 3d printing
+pub mod stl;
+
 /// A compact halfedge graph specifically optimized for some operations
 pub mod compact_mesh;
 
 /// Types to represent a selection of a subset of faces, vertices or edges.
 pub mod selection;
 
+/// Spatial acceleration structures, for nearest vertex and raycast queries
+pub mod accel;
+
+/// An opt-in journal of the operations applied to a mesh
+pub mod history;
+
 /// Generate vertex and index buffers suitable to be uploaded to the GPU for rendering
 pub mod gpu_buffer_generation;
 pub use gpu_buffer_generation::*;
@@ -112,6 +121,13 @@ pub struct HalfEdgeMesh {
     connectivity: RefCell<MeshConnectivity>,
     pub channels: MeshChannels,
     default_channels: DefaultChannels,
+    /// When set, edit operations applied to a selection of this mesh are also
+    /// applied to the mirror image of the selection. Ops that move vertices
+    /// move the mirror images to the mirrored positions. `loop_cut` and
+    /// `bridge`, which work on a single edge loop or pair of loops, ignore it.
+    pub symmetry: Option<selection::SymmetryPlane>,
+    /// When set, the operations applied to this mesh get recorded here.
+    pub history: Option<history::MeshHistory>,
 }
 
 pub type SVec<T> = SmallVec<[T; 4]>;
@@ -400,7 +416,9 @@ impl MeshConnectivity {
         &'a self,
         channel: &'a Channel<VertexId, T>,
     ) -> impl Iterator<Item = (VertexId, &Vertex, T)> + 'a {
-        self.vertices.iter().map(|(id, v)| (id, v, channel[id]))
+        self.vertices
+            .iter()
+            .map(|(id, v)| (id, v, channel[id].clone()))
     }
 
     pub fn iter_faces(&self) -> impl Iterator<Item = (FaceId, &Face)> {
@@ -411,7 +429,9 @@ impl MeshConnectivity {
         &'a self,
         channel: &'a Channel<FaceId, T>,
     ) -> impl Iterator<Item = (FaceId, &Face, T)> + 'a {
-        self.faces.iter().map(|(id, v)| (id, v, channel[id]))
+        self.faces
+            .iter()
+            .map(|(id, v)| (id, v, channel[id].clone()))
     }
 
     pub fn iter_halfedges(&self) -> impl Iterator<Item = (HalfEdgeId, &HalfEdge)> {
@@ -422,7 +442,9 @@ impl MeshConnectivity {
         &'a self,
         channel: &'a Channel<HalfEdgeId, T>,
     ) -> impl Iterator<Item = (HalfEdgeId, &HalfEdge, T)> + 'a {
-        self.halfedges.iter().map(|(id, v)| (id, v, channel[id]))
+        self.halfedges
+            .iter()
+            .map(|(id, v)| (id, v, channel[id].clone()))
     }
 
     /// Adds a new vertex to the mesh, disconnected from everything else. Returns its handle.
@@ -523,7 +545,7 @@ impl MeshConnectivity {
     // Returns the normal of the face. The first three vertices are used to
     // compute the normal. If the vertices of the face are not coplanar,
     // the result will not be correct.
-    fn face_normal(&self, positions: &Positions, face: FaceId) -> Option<Vec3> {
+    pub fn face_normal(&self, positions: &Positions, face: FaceId) -> Option<Vec3> {
         let verts = self.face_vertices(face);
         if verts.len() >= 3 {
             let v01 = positions[verts[0]] - positions[verts[1]];
@@ -534,6 +556,19 @@ impl MeshConnectivity {
         }
     }
 
+    /// Returns a vector perpendicular to the face, computed with Newell's
+    /// method, whose length is twice the area of the face. Unlike
+    /// [`MeshConnectivity::face_normal`], this is robust for concave and
+    /// non-planar polygons. Degenerate faces give a zero vector.
+    pub fn face_area_normal(&self, positions: &Positions, face: FaceId) -> Vec3 {
+        self.face_vertices(face)
+            .iter()
+            .circular_tuple_windows()
+            .fold(Vec3::ZERO, |normal, (&v, &next)| {
+                normal + positions[v].cross(positions[next])
+            })
+    }
+
     pub fn num_halfedges(&self) -> usize {
         self.halfedges.len()
     }
@@ -545,6 +580,31 @@ impl MeshConnectivity {
     pub fn num_faces(&self) -> usize {
         self.faces.len()
     }
+
+    /// Returns the number of edges in the mesh. Every edge is made of a pair
+    /// of twin halfedges.
+    pub fn num_edges(&self) -> usize {
+        self.halfedges.len() / 2
+    }
+
+    /// Returns the boundary loops of the mesh. Each loop is a cycle of
+    /// halfedges with no face. A mesh without boundary loops is closed.
+    pub fn boundary_loops(&self) -> Result<Vec<SVec<HalfEdgeId>>> {
+        let mut visited = HashSet::new();
+        let mut boundary_loops = vec![];
+        for (h, halfedge) in self.iter_halfedges() {
+            if halfedge.face.is_some() || visited.contains(&h) {
+                continue;
+            }
+            if halfedge.next.is_none() {
+                bail!("Boundary halfedge {:?} is not part of a loop", h);
+            }
+            let boundary_loop = self.halfedge_loop(h);
+            visited.extend(boundary_loop.iter_cpy());
+            boundary_loops.push(boundary_loop);
+        }
+        Ok(boundary_loops)
+    }
 }
 
 impl HalfEdgeMesh {
@@ -555,6 +615,8 @@ impl HalfEdgeMesh {
             channels,
             default_channels,
             connectivity: RefCell::new(MeshConnectivity::new()),
+            symmetry: None,
+            history: None,
         }
     }
 
@@ -743,6 +805,27 @@ impl HalfEdgeMesh {
     /// Merges this halfedge mesh with another one. No additional connectivity
     /// data is generated between the two.
     pub fn merge_with(&mut self, mesh_b: &HalfEdgeMesh) {
+        let (vertices, faces, halfedges) = {
+            let b_conn = mesh_b.read_connectivity();
+            (
+                b_conn.iter_vertices().map(|(v, _)| v).collect_vec(),
+                b_conn.iter_faces().map(|(f, _)| f).collect_vec(),
+                b_conn.iter_halfedges().map(|(h, _)| h).collect_vec(),
+            )
+        };
+        self.merge_elements_with(mesh_b, &vertices, &faces, &halfedges)
+    }
+
+    /// Like [`HalfEdgeMesh::merge_with`], but only the given elements of
+    /// `mesh_b`, along with their channel values, are added to this mesh. The
+    /// elements must not point to any element of `mesh_b` outside of them.
+    fn merge_elements_with(
+        &mut self,
+        mesh_b: &HalfEdgeMesh,
+        vertices: &[VertexId],
+        faces: &[FaceId],
+        halfedges: &[HalfEdgeId],
+    ) {
         let mut vmap = SecondaryMap::<VertexId, VertexId>::new();
         let mut hmap = SecondaryMap::<HalfEdgeId, HalfEdgeId>::new();
         let mut fmap = SecondaryMap::<FaceId, FaceId>::new();
@@ -752,13 +835,13 @@ impl HalfEdgeMesh {
 
         // On a first pass, we reserve new vertices, faces and halfedges without
         // setting any of their pointers and store their ids in a mapping.
-        for (vertex_id, _vertex) in b_conn.iter_vertices() {
+        for &vertex_id in vertices {
             vmap.insert(vertex_id, a_conn.alloc_vertex_raw(None));
         }
-        for (face_id, _) in b_conn.iter_faces() {
+        for &face_id in faces {
             fmap.insert(face_id, a_conn.alloc_face(None));
         }
-        for (halfedge_id, _) in b_conn.iter_halfedges() {
+        for &halfedge_id in halfedges {
             hmap.insert(
                 halfedge_id,
                 a_conn.alloc_halfedge(HalfEdge {
@@ -772,17 +855,18 @@ impl HalfEdgeMesh {
 
         // The second pass uses the mapping and the original data to set all the
         // inner pointers.
-        for (vertex_id, vertex) in b_conn.iter_vertices() {
-            if let Some(h) = vertex.halfedge {
+        for &vertex_id in vertices {
+            if let Some(h) = b_conn[vertex_id].halfedge {
                 a_conn[vmap[vertex_id]].halfedge = Some(hmap[h])
             }
         }
-        for (face_id, face) in b_conn.iter_faces() {
-            if let Some(h) = face.halfedge {
+        for &face_id in faces {
+            if let Some(h) = b_conn[face_id].halfedge {
                 a_conn[fmap[face_id]].halfedge = Some(hmap[h])
             }
         }
-        for (halfedge_id, halfedge) in b_conn.iter_halfedges() {
+        for &halfedge_id in halfedges {
+            let halfedge = &b_conn[halfedge_id];
             if let Some(twin) = halfedge.twin {
                 a_conn[hmap[halfedge_id]].twin = Some(hmap[twin]);
             }
@@ -816,11 +900,9 @@ impl HalfEdgeMesh {
         /// since collected vectors are contiguous, unlike the slotmaps,
         /// there will not be holes and thus no required branching.
         use slotmap::Key;
-        let raw_vertices: Rc<Vec<_>> =
-            Rc::new(b_conn.iter_vertices().map(|(k, _)| k.data()).collect());
-        let raw_faces: Rc<Vec<_>> = Rc::new(b_conn.iter_faces().map(|(k, _)| k.data()).collect());
-        let raw_halfedges: Rc<Vec<_>> =
-            Rc::new(b_conn.iter_halfedges().map(|(k, _)| k.data()).collect());
+        let raw_vertices: Rc<Vec<_>> = Rc::new(vertices.iter().map(|k| k.data()).collect());
+        let raw_faces: Rc<Vec<_>> = Rc::new(faces.iter().map(|k| k.data()).collect());
+        let raw_halfedges: Rc<Vec<_>> = Rc::new(halfedges.iter().map(|k| k.data()).collect());
         let get_ids = move |kty| match kty {
             ChannelKeyType::VertexId => Rc::clone(&raw_vertices),
             ChannelKeyType::FaceId => Rc::clone(&raw_faces),
@@ -835,6 +917,139 @@ impl HalfEdgeMesh {
 
         self.channels.merge_with(&mesh_b.channels, get_ids, id_map)
     }
+
+    /// Returns the axis-aligned bounding box of the vertices of this mesh, as
+    /// a `(min, max)` pair. Returns `None` when the mesh has no vertices.
+    pub fn bounding_box(&self) -> Option<(Vec3, Vec3)> {
+        let positions = self.read_positions();
+        self.read_connectivity()
+            .iter_vertices_with_channel(&positions)
+            .map(|(_, _, pos)| (pos, pos))
+            .reduce(|(min, max), (p, _)| (min.min(p), max.max(p)))
+    }
+
+    /// Returns the connected components of this mesh, as groups of faces. Two
+    /// faces belong to the same component when there is a path of edges
+    /// linking them.
+    pub fn connected_components(&self) -> Result<Vec<Vec<FaceId>>> {
+        let conn = self.read_connectivity();
+        let mut visited = HashSet::<FaceId>::new();
+        let mut components = vec![];
+        for (face, _) in conn.iter_faces() {
+            if visited.contains(&face) {
+                continue;
+            }
+            let component = selection::select_linked_faces(&conn, &[face])?;
+            visited.extend(component.iter_cpy());
+            components.push(component);
+        }
+        Ok(components)
+    }
+
+    /// Returns the groups of vertices linked by edges in this mesh. Unlike
+    /// [`HalfEdgeMesh::connected_components`], isolated vertices and loose
+    /// edges form components of their own.
+    pub fn vertex_components(&self) -> Result<Vec<Vec<VertexId>>> {
+        let conn = self.read_connectivity();
+        let mut visited = HashSet::<VertexId>::new();
+        let mut components = vec![];
+        for (start, _) in conn.iter_vertices() {
+            if !visited.insert(start) {
+                continue;
+            }
+            let mut component = vec![start];
+            let mut stack = vec![start];
+            while let Some(v) = stack.pop() {
+                for h in conn.at_vertex(v).outgoing_halfedges()? {
+                    let next = conn.at_halfedge(h).dst_vertex().try_end()?;
+                    if visited.insert(next) {
+                        component.push(next);
+                        stack.push(next);
+                    }
+                }
+            }
+            components.push(component);
+        }
+        Ok(components)
+    }
+
+    /// Splits this mesh into separate meshes, one for each of its components,
+    /// as returned by [`HalfEdgeMesh::vertex_components`]. Each new mesh only
+    /// has the channel values of its own elements.
+    pub fn split_components(&self) -> Result<Vec<HalfEdgeMesh>> {
+        let components = self.vertex_components()?;
+        let conn = self.read_connectivity();
+        components
+            .iter()
+            .map(|vertices| {
+                let mut halfedges = vec![];
+                for &v in vertices {
+                    halfedges.extend(conn.at_vertex(v).outgoing_halfedges()?);
+                }
+                let faces = halfedges
+                    .iter()
+                    .filter_map(|h| conn[*h].face)
+                    .unique()
+                    .collect_vec();
+
+                let mut mesh = HalfEdgeMesh::new();
+                mesh.merge_elements_with(self, vertices, &faces, &halfedges);
+                Ok(mesh)
+            })
+            .collect()
+    }
+
+    /// Maps the values of the scalar vertex channel with the given name to a
+    /// color ramp. The values are normalized to the range of the channel.
+    pub fn channel_preview_colors(&self, name: &str) -> Result<SecondaryMap<VertexId, Vec3>> {
+        let channel = self.channels.read_channel_by_name::<VertexId, f32>(name)?;
+        let conn = self.read_connectivity();
+        let (min, max) = conn
+            .iter_vertices()
+            .map(|(v, _)| channel[v])
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), x| {
+                (min.min(x), max.max(x))
+            });
+        let range = (max - min).max(f32::EPSILON);
+        Ok(conn
+            .iter_vertices()
+            .map(|(v, _)| (v, color_ramp((channel[v] - min) / range)))
+            .collect())
+    }
+
+    /// Visualizes the scalar vertex channel with the given name. The colors
+    /// from [`HalfEdgeMesh::channel_preview_colors`] are written into the
+    /// vertex color channel.
+    pub fn preview_channel(&mut self, name: &str) -> Result<()> {
+        let colors = self.channel_preview_colors(name)?;
+        let color_ch = self
+            .channels
+            .ensure_channel::<VertexId, Vec3>(VERTEX_COLOR_CHANNEL);
+        let mut color_ch = self.channels.write_channel(color_ch)?;
+        for (v, color) in colors {
+            color_ch[v] = color;
+        }
+        Ok(())
+    }
+}
+
+/// The name of the vertex channel storing the colors used by the vertex color
+/// shading mode of the viewport.
+pub const VERTEX_COLOR_CHANNEL: &str = "color";
+
+/// The name of the halfedge channel storing the colors used to draw the
+/// wireframe in the viewport. Full edges use the color of one of their
+/// halfedges, so both halfedges of an edge should usually have the same color.
+pub const EDGE_COLOR_CHANNEL: &str = "color";
+
+/// Maps a value in the [0, 1] range to a blue-green-red color ramp.
+pub fn color_ramp(t: f32) -> Vec3 {
+    let t = t.clamp(0.0, 1.0);
+    if t < 0.5 {
+        Vec3::new(0.0, 0.0, 1.0).lerp(Vec3::new(0.0, 1.0, 0.0), t * 2.0)
+    } else {
+        Vec3::new(0.0, 1.0, 0.0).lerp(Vec3::new(1.0, 0.0, 0.0), (t - 0.5) * 2.0)
+    }
 }
 
 impl Default for HalfEdgeMesh {
@@ -883,6 +1098,1145 @@ pub mod test {
         );
     }
 
+    #[test]
+    pub fn test_split_components() {
+        let mut mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        mesh.merge_with(&primitives::Box::build(Vec3::X * 3.0, Vec3::ONE));
+        assert_eq!(mesh.connected_components().unwrap().len(), 2);
+        assert_eq!(mesh.vertex_components().unwrap().len(), 2);
+
+        let split = mesh.split_components().unwrap();
+        assert_eq!(split.len(), 2);
+        for part in split {
+            let conn = part.read_connectivity();
+            assert_eq!(conn.num_vertices(), 8);
+            assert_eq!(conn.num_faces(), 6);
+            assert_eq!(conn.num_halfedges(), 24);
+        }
+
+        // Isolated vertices are components of their own, and channel values
+        // end up in the component of their element.
+        let ch = mesh.channels.ensure_channel::<VertexId, f32>("weight");
+        let isolated = mesh.write_connectivity().alloc_vertex(
+            &mut mesh.write_positions(),
+            Vec3::Y * 5.0,
+            None,
+        );
+        mesh.channels.write_channel(ch).unwrap()[isolated] = 1.0;
+
+        assert_eq!(mesh.connected_components().unwrap().len(), 2);
+        let split = mesh.split_components().unwrap();
+        assert_eq!(split.len(), 3);
+        let lone = split
+            .iter()
+            .find(|part| part.read_connectivity().num_vertices() == 1)
+            .unwrap();
+        assert_eq!(lone.read_connectivity().num_faces(), 0);
+        let v = lone
+            .read_connectivity()
+            .iter_vertices()
+            .map(|(v, _)| v)
+            .next()
+            .unwrap();
+        assert_eq!(lone.read_positions()[v], Vec3::Y * 5.0);
+        let weights = lone
+            .channels
+            .read_channel_by_name::<VertexId, f32>("weight");
+        assert_eq!(weights.unwrap()[v], 1.0);
+        for part in split.iter().filter(|part| !std::ptr::eq(*part, lone)) {
+            let weights = part
+                .channels
+                .read_channel_by_name::<VertexId, f32>("weight")
+                .unwrap();
+            assert!(weights.iter().all(|(_, w)| *w == 0.0));
+        }
+    }
+
+    #[test]
+    pub fn test_grow_shrink_selection() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let conn = mesh.read_connectivity();
+
+        let (v, _) = conn.iter_vertices().next().unwrap();
+        let grown = conn.grow_vertex_selection(&[v]).unwrap();
+        assert_eq!(grown.len(), 4);
+        assert!(conn.shrink_vertex_selection(&grown).unwrap().is_empty());
+        let all_verts: Vec<_> = conn.iter_vertices().map(|(v, _)| v).collect();
+        assert_eq!(conn.shrink_vertex_selection(&all_verts).unwrap().len(), 8);
+
+        let (f, _) = conn.iter_faces().next().unwrap();
+        let grown = conn.grow_face_selection(&[f]).unwrap();
+        assert_eq!(grown.len(), 5);
+        assert_eq!(conn.shrink_face_selection(&grown).unwrap(), vec![f]);
+    }
+
+    #[test]
+    pub fn test_select_by_normal() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+
+        let up = selection::select_by_normal(&conn, &positions, Vec3::Y, 10.0).unwrap();
+        assert_eq!(up.len(), 1);
+        let f = up[0];
+        assert!(conn
+            .at_face(f)
+            .vertices()
+            .unwrap()
+            .iter()
+            .all(|v| positions[*v].y > 0.0));
+
+        let upper = selection::select_by_normal(&conn, &positions, Vec3::Y, 90.0).unwrap();
+        assert_eq!(upper.len(), 5);
+        assert!(selection::select_by_normal(&conn, &positions, Vec3::ZERO, 10.0).is_err());
+    }
+
+    #[test]
+    pub fn test_edge_loop() {
+        // On a sphere, loops along a parallel are closed, and loops along a
+        // meridian stop at the poles, which have more than four edges.
+        let mesh = primitives::UvSphere::build(Vec3::ZERO, 1.0, 8, 12).unwrap();
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        let is_pole = |v: VertexId| positions[v].y.abs() > 0.999;
+        let find_edge = |vertical: bool| {
+            conn.iter_halfedges()
+                .map(|(h, _)| h)
+                .find(|h| {
+                    let (v, w) = conn.at_halfedge(*h).src_dst_pair().unwrap();
+                    let dy = (positions[v].y - positions[w].y).abs();
+                    !is_pole(v) && !is_pole(w) && (dy > 1e-3) == vertical
+                })
+                .unwrap()
+        };
+
+        let parallel = selection::edge_loop(&conn, find_edge(false)).unwrap();
+        assert_eq!(parallel.len(), 12);
+
+        let start = find_edge(true);
+        let meridian = selection::edge_loop(&conn, start).unwrap();
+        assert_eq!(meridian.len(), 8);
+        assert!(meridian.contains(&start));
+        assert!(is_pole(
+            conn.at_halfedge(meridian[0])
+                .src_vertex()
+                .try_end()
+                .unwrap()
+        ));
+        assert!(is_pole(
+            conn.at_halfedge(*meridian.last().unwrap())
+                .dst_vertex()
+                .try_end()
+                .unwrap()
+        ));
+        for (a, b) in meridian.iter().tuple_windows() {
+            assert_eq!(
+                conn.at_halfedge(*a).dst_vertex().try_end().unwrap(),
+                conn.at_halfedge(*b).src_vertex().try_end().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_boundary_halfedges() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        assert!(mesh.read_connectivity().boundary_halfedges().is_empty());
+
+        let mesh = primitives::Quad::build(Vec3::ZERO, Vec3::Y, Vec3::X, Vec2::ONE);
+        let conn = mesh.read_connectivity();
+        let boundary = conn.boundary_halfedges();
+        assert_eq!(boundary.len(), 4);
+        assert!(boundary.iter().all(|h| conn[*h].face.is_some()));
+    }
+
+    #[test]
+    pub fn test_convex_hull() {
+        // The corners of a cube, plus some points inside it
+        let mut points = vec![];
+        for x in [-1.0, 1.0] {
+            for y in [-1.0, 1.0] {
+                for z in [-1.0, 1.0] {
+                    points.push(Vec3::new(x, y, z));
+                    points.push(Vec3::new(x, y, z) * 0.5);
+                }
+            }
+        }
+        let hull = edit_ops::convex_hull(&points).unwrap();
+        let conn = hull.read_connectivity();
+        let positions = hull.read_positions();
+        assert_eq!(conn.num_vertices(), 8);
+        assert!(conn
+            .iter_vertices()
+            .all(|(v, _)| positions[v].abs() == Vec3::ONE));
+        assert!(conn.iter_halfedges().all(|(_, h)| h.face.is_some()));
+
+        // Normals must point outwards
+        for (f, _) in conn.iter_faces() {
+            let normal = conn.face_normal(&positions, f).unwrap();
+            let center = conn
+                .face_vertices(f)
+                .iter()
+                .map(|v| positions[*v])
+                .sum::<Vec3>();
+            assert!(normal.dot(center) > 0.0);
+        }
+
+        let flat = [Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::X + Vec3::Y];
+        assert!(edit_ops::convex_hull(&flat).is_err());
+        assert!(edit_ops::convex_hull(&flat[..3]).is_err());
+    }
+
+    #[test]
+    pub fn test_subdivide_selection() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+        let top = selection::select_by_normal(&conn, &positions, Vec3::Y, 10.0).unwrap();
+
+        let result =
+            edit_ops::subdivide_selection(&conn, &positions, &top, 1, &Progress::new()).unwrap();
+        let result_conn = result.read_connectivity();
+        // The top face becomes 4 quads. The 4 side faces are each stitched
+        // with a fan of 5 triangles, and the bottom face is untouched.
+        assert_eq!(result_conn.num_faces(), 4 + 4 * 5 + 1);
+        assert!(result_conn.iter_halfedges().all(|(_, h)| h.face.is_some()));
+
+        let result =
+            edit_ops::subdivide_selection(&conn, &positions, &top, 2, &Progress::new()).unwrap();
+        // The side triangles touching the top get split again into 4
+        assert_eq!(
+            result.read_connectivity().num_faces(),
+            16 + 4 * (3 + 2 * 4) + 1
+        );
+    }
+
+    #[test]
+    pub fn test_compute_vertex_normals() {
+        for weighting in [
+            edit_ops::NormalWeighting::Uniform,
+            edit_ops::NormalWeighting::Area,
+            edit_ops::NormalWeighting::Angle,
+        ] {
+            let mut mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+            let ch_id = edit_ops::compute_vertex_normals(&mut mesh, weighting).unwrap();
+            let conn = mesh.read_connectivity();
+            let positions = mesh.read_positions();
+            let normals = mesh.channels.read_channel(ch_id).unwrap();
+            // By symmetry, cube normals point away from the center
+            for (v, _) in conn.iter_vertices() {
+                assert!(normals[v].distance(positions[v].normalize()) < 1e-5);
+            }
+        }
+
+        // Boundary vertices of an open mesh get the normal of their faces
+        let mut quad = primitives::Quad::build(Vec3::ZERO, Vec3::Y, Vec3::X, Vec2::ONE);
+        let ch_id =
+            edit_ops::compute_vertex_normals(&mut quad, edit_ops::NormalWeighting::default())
+                .unwrap();
+        let conn = quad.read_connectivity();
+        let normals = quad.channels.read_channel(ch_id).unwrap();
+        let (f, _) = conn.iter_faces().next().unwrap();
+        let face_normal = conn.face_normal(&quad.read_positions(), f).unwrap();
+        assert!(conn
+            .iter_vertices()
+            .all(|(v, _)| normals[v].distance(face_normal) < 1e-5));
+    }
+
+    #[test]
+    pub fn test_smooth_buffers_use_normal_channel() {
+        let mut mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let ch_id = mesh
+            .channels
+            .ensure_channel::<VertexId, Vec3>(wavefront_obj::NORMAL_CHANNEL);
+        {
+            let conn = mesh.read_connectivity();
+            let mut normals = mesh.channels.write_channel(ch_id).unwrap();
+            for (v, _) in conn.iter_vertices() {
+                normals[v] = Vec3::Y * 2.0;
+            }
+        }
+        let buffers = mesh.generate_triangle_buffers_smooth().unwrap();
+        assert!(buffers.normals.iter().all(|n| n.distance(Vec3::Y) < 1e-5));
+    }
+
+    #[test]
+    pub fn test_point_colors() {
+        let mut mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        assert!(mesh.generate_point_colors(None).is_none());
+        mesh.channels
+            .ensure_channel::<VertexId, Vec3>(VERTEX_COLOR_CHANNEL);
+        let colors = mesh.generate_point_colors(None).unwrap();
+        assert_eq!(colors.len(), mesh.generate_point_buffers().positions.len());
+    }
+
+    #[test]
+    pub fn test_channel_preview_colors() {
+        let mut mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let height_ch = mesh.channels.ensure_channel::<VertexId, f32>("height");
+        {
+            let positions = mesh.read_positions();
+            let mut height_ch = mesh.channels.write_channel(height_ch).unwrap();
+            for (v, _) in mesh.read_connectivity().iter_vertices() {
+                height_ch[v] = positions[v].y;
+            }
+        }
+
+        // Previewing in the viewport doesn't modify the mesh
+        let preview = mesh.channel_preview_colors("height").unwrap();
+        assert!(mesh
+            .channels
+            .read_channel_by_name::<VertexId, Vec3>(VERTEX_COLOR_CHANNEL)
+            .is_err());
+        let colors = mesh.generate_point_colors(Some(&preview)).unwrap();
+        let positions = mesh.read_positions();
+        let conn = mesh.read_connectivity();
+        for ((v, _), color) in conn.iter_vertices().zip(colors) {
+            let expected = if positions[v].y > 0.0 { 1.0 } else { 0.0 };
+            assert_eq!(color, color_ramp(expected));
+        }
+        drop((positions, conn));
+
+        // Baking the preview writes the same colors into the channel
+        mesh.preview_channel("height").unwrap();
+        let color_ch = mesh
+            .channels
+            .read_channel_by_name::<VertexId, Vec3>(VERTEX_COLOR_CHANNEL)
+            .unwrap();
+        assert!(preview.iter().all(|(v, color)| color_ch[v] == *color));
+    }
+
+    #[test]
+    pub fn test_edge_colors() {
+        let mut mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let buffers = mesh.generate_line_buffers().unwrap();
+        assert!(buffers.colors.iter().all(|c| *c == Vec3::ONE));
+
+        let ch_id = mesh
+            .channels
+            .ensure_channel::<HalfEdgeId, Vec3>(EDGE_COLOR_CHANNEL);
+
+        // Edges without a color in the channel are drawn white
+        {
+            let conn = mesh.read_connectivity();
+            let mut colors = mesh.channels.write_channel(ch_id).unwrap();
+            let (h, _) = conn.iter_halfedges().next().unwrap();
+            colors[h] = Vec3::X;
+            colors[conn.at_halfedge(h).twin().try_end().unwrap()] = Vec3::X;
+        }
+        let buffers = mesh.generate_line_buffers().unwrap();
+        assert_eq!(buffers.colors.iter().filter(|c| **c == Vec3::X).count(), 1);
+        assert_eq!(
+            buffers.colors.iter().filter(|c| **c == Vec3::ONE).count(),
+            11
+        );
+
+        {
+            let conn = mesh.read_connectivity();
+            let mut colors = mesh.channels.write_channel(ch_id).unwrap();
+            for (h, _) in conn.iter_halfedges() {
+                colors[h] = Vec3::X;
+            }
+        }
+        let buffers = mesh.generate_line_buffers().unwrap();
+        assert_eq!(buffers.colors.len(), 12);
+        assert!(buffers.colors.iter().all(|c| *c == Vec3::X));
+    }
+
+    #[test]
+    pub fn test_face_normal_buffers() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let LineBuffers { positions, colors } = mesh.generate_face_normal_buffers(0.5);
+        assert_eq!(colors.len(), 6);
+        // Normals of a box centered at the origin point outwards
+        for line in positions.chunks(2) {
+            assert!((line[1].length() - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    pub fn test_bounding_box() {
+        assert!(HalfEdgeMesh::new().bounding_box().is_none());
+        let mesh = primitives::Box::build(Vec3::new(1.0, 2.0, 3.0), Vec3::new(2.0, 4.0, 6.0));
+        let (min, max) = mesh.bounding_box().unwrap();
+        assert!(min.distance(Vec3::ZERO) < 1e-5);
+        assert!(max.distance(Vec3::new(2.0, 4.0, 6.0)) < 1e-5);
+    }
+
+    #[test]
+    pub fn test_boundary_loops() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let conn = mesh.read_connectivity();
+        assert_eq!(conn.num_edges(), 12);
+        assert!(conn.boundary_loops().unwrap().is_empty());
+
+        let mesh = primitives::Quad::build(Vec3::ZERO, Vec3::Y, Vec3::X, Vec2::ONE);
+        let conn = mesh.read_connectivity();
+        let loops = conn.boundary_loops().unwrap();
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].len(), 4);
+    }
+
+    #[test]
+    pub fn test_uv_sphere() {
+        let mesh = primitives::UvSphere::build(Vec3::ZERO, 1.0, 4, 6).unwrap();
+        let conn = mesh.read_connectivity();
+        assert_eq!(conn.num_vertices(), 2 + 3 * 6);
+        assert_eq!(conn.num_faces(), 4 * 6);
+        assert!(conn.iter_halfedges().all(|(_, h)| h.face.is_some()));
+
+        assert!(primitives::UvSphere::build(Vec3::ZERO, 1.0, 2, 6).is_err());
+        assert!(primitives::UvSphere::build(Vec3::ZERO, 1.0, 4, 2).is_err());
+        // Over the element limit
+        assert!(primitives::UvSphere::build(Vec3::ZERO, 1.0, 10_000, 10_000).is_err());
+    }
+
+    #[test]
+    pub fn test_icosphere() {
+        let mesh = primitives::Icosphere::build(Vec3::ZERO, 2.0, 2).unwrap();
+        let conn = mesh.read_connectivity();
+        assert_eq!(conn.num_faces(), 20 * 16);
+        // Euler characteristic of a sphere: V - E + F = 2
+        assert_eq!(
+            conn.num_vertices() + conn.num_faces(),
+            conn.num_halfedges() / 2 + 2
+        );
+        let positions = mesh.read_positions();
+        for (v, _) in conn.iter_vertices() {
+            assert!((positions[v].length() - 2.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    pub fn test_cylinder_and_cone() {
+        let cylinder = primitives::Cylinder::build(Vec3::ZERO, 1.0, 2.0, 8).unwrap();
+        let conn = cylinder.read_connectivity();
+        assert_eq!(conn.num_vertices(), 16);
+        assert_eq!(conn.num_faces(), 8 + 2);
+        assert!(conn.iter_halfedges().all(|(_, h)| h.face.is_some()));
+
+        let cone = primitives::Cone::build(Vec3::ZERO, 1.0, 2.0, 8).unwrap();
+        let conn = cone.read_connectivity();
+        assert_eq!(conn.num_vertices(), 9);
+        assert_eq!(conn.num_faces(), 8 + 1);
+        assert!(conn.iter_halfedges().all(|(_, h)| h.face.is_some()));
+
+        assert!(primitives::Cylinder::build(Vec3::ZERO, 1.0, 2.0, 2).is_err());
+        assert!(primitives::Cone::build(Vec3::ZERO, 1.0, 2.0, 2).is_err());
+        // Over the element limit
+        assert!(primitives::Cylinder::build(Vec3::ZERO, 1.0, 2.0, u32::MAX).is_err());
+        assert!(primitives::Cone::build(Vec3::ZERO, 1.0, 2.0, u32::MAX).is_err());
+    }
+
+    #[test]
+    pub fn test_inset_faces() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let face = mesh.read_connectivity().iter_faces().next().unwrap().0;
+        let inner = edit_ops::inset_faces(
+            &mut mesh.write_connectivity(),
+            &mut mesh.write_positions(),
+            &[face],
+            10.0,
+        )
+        .unwrap();
+        assert_eq!(inner.len(), 1);
+
+        let conn = mesh.read_connectivity();
+        assert_eq!(conn.num_vertices(), 8 + 4);
+        assert_eq!(conn.num_faces(), 6 + 4);
+        assert_eq!(conn.num_face_edges(inner[0]), 4);
+        for (h, halfedge) in conn.iter_halfedges() {
+            assert!(halfedge.face.is_some());
+            assert_eq!(conn[halfedge.twin.unwrap()].twin, Some(h));
+        }
+        // Insetting more than the inradius clamps instead of inverting
+        let positions = mesh.read_positions();
+        let centroid = conn.face_vertex_average(&positions, inner[0]);
+        for v in conn.face_vertices(inner[0]) {
+            assert!(positions[v].distance(centroid) < 0.5);
+        }
+    }
+
+    #[test]
+    pub fn test_poke_faces() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let faces = mesh
+            .read_connectivity()
+            .iter_faces()
+            .map(|(f, _)| f)
+            .collect_vec();
+        let centers = edit_ops::poke_faces(
+            &mut mesh.write_connectivity(),
+            &mut mesh.write_positions(),
+            &faces,
+            0.5,
+        )
+        .unwrap();
+        assert_eq!(centers.len(), 6);
+
+        let conn = mesh.read_connectivity();
+        assert_eq!(conn.num_vertices(), 8 + 6);
+        assert_eq!(conn.num_faces(), 6 * 4);
+        for (h, halfedge) in conn.iter_halfedges() {
+            assert!(halfedge.face.is_some());
+            assert_eq!(conn[halfedge.twin.unwrap()].twin, Some(h));
+        }
+        let positions = mesh.read_positions();
+        for v in centers {
+            assert!((positions[v].length() - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    pub fn test_dissolve() {
+        fn assert_valid(conn: &MeshConnectivity) {
+            for (h, halfedge) in conn.iter_halfedges() {
+                assert!(halfedge.face.is_some());
+                assert_eq!(conn[halfedge.twin.unwrap()].twin, Some(h));
+            }
+        }
+
+        // Dissolving all the edges around a corner removes the corner
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let corner = mesh.read_connectivity().iter_vertices().next().unwrap().0;
+        let edges = mesh
+            .read_connectivity()
+            .at_vertex(corner)
+            .outgoing_halfedges()
+            .unwrap();
+        edit_ops::dissolve_edges(&mut mesh.write_connectivity(), &edges).unwrap();
+        let conn = mesh.read_connectivity();
+        assert_valid(&conn);
+        assert_eq!(conn.num_vertices(), 7);
+        assert_eq!(conn.num_faces(), 4);
+        assert!(!conn.vertex_exists(corner));
+        drop(conn);
+
+        // Dissolving a vertex in the middle of an edge joins the edge back
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let h = mesh.read_connectivity().iter_halfedges().next().unwrap().0;
+        let middle = edit_ops::divide_edge(
+            &mut mesh.write_connectivity(),
+            &mut mesh.write_positions(),
+            h,
+            0.5,
+        )
+        .unwrap();
+        edit_ops::dissolve_vertices(&mut mesh.write_connectivity(), &[middle]).unwrap();
+        let conn = mesh.read_connectivity();
+        assert_valid(&conn);
+        assert_eq!(conn.num_vertices(), 8);
+        assert_eq!(conn.num_halfedges(), 24);
+        assert_eq!(conn.num_faces(), 6);
+        assert!(conn
+            .iter_faces()
+            .all(|(f, _)| conn.face_edges(f).len() == 4));
+        drop(conn);
+
+        // Dissolving a corner merges the faces around it
+        let corner = mesh.read_connectivity().iter_vertices().next().unwrap().0;
+        edit_ops::dissolve_vertices(&mut mesh.write_connectivity(), &[corner]).unwrap();
+        let conn = mesh.read_connectivity();
+        assert_valid(&conn);
+        assert_eq!(conn.num_vertices(), 7);
+        assert_eq!(conn.num_faces(), 4);
+    }
+
+    #[test]
+    pub fn test_delete() {
+        let top_face = |mesh: &HalfEdgeMesh| {
+            let conn = mesh.read_connectivity();
+            let positions = mesh.read_positions();
+            conn.iter_faces()
+                .map(|(f, _)| f)
+                .find(|f| conn.face_normal(&positions, *f).unwrap().y.abs() > 0.99)
+                .unwrap()
+        };
+
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let face = top_face(&mesh);
+        edit_ops::delete_faces(&mut mesh.write_connectivity(), &[face], true).unwrap();
+        let conn = mesh.read_connectivity();
+        assert_eq!(conn.num_faces(), 5);
+        assert_eq!(conn.num_halfedges(), 24);
+        assert_eq!(conn.boundary_loops().unwrap().len(), 1);
+        drop(conn);
+
+        // Deleting the faces on both sides of an edge removes the edge
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let face = top_face(&mesh);
+        let side_face = {
+            let conn = mesh.read_connectivity();
+            let h = conn.face_edges(face)[0];
+            conn.at_halfedge(h).twin().face().try_end().unwrap()
+        };
+        edit_ops::delete_faces(&mut mesh.write_connectivity(), &[face, side_face], false).unwrap();
+        let conn = mesh.read_connectivity();
+        assert_eq!(conn.num_faces(), 4);
+        assert_eq!(conn.num_vertices(), 8);
+        assert_eq!(conn.num_halfedges(), 24 - 2);
+        assert_eq!(conn.boundary_loops().unwrap().len(), 1);
+        drop(conn);
+
+        // Deleting a corner removes its faces and edges
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let corner = mesh.read_connectivity().iter_vertices().next().unwrap().0;
+        edit_ops::delete_vertices(&mut mesh.write_connectivity(), &[corner]).unwrap();
+        let conn = mesh.read_connectivity();
+        assert!(!conn.vertex_exists(corner));
+        assert_eq!(conn.num_vertices(), 7);
+        assert_eq!(conn.num_faces(), 3);
+        assert_eq!(conn.num_halfedges(), 24 - 6);
+        for (v, _) in conn.iter_vertices() {
+            assert!(!conn.at_vertex(v).outgoing_halfedges().unwrap().is_empty());
+        }
+        assert_eq!(conn.boundary_loops().unwrap().len(), 1);
+    }
+
+    #[test]
+    pub fn test_loop_cut() {
+        // Cutting next to a parallel of a sphere splits the band of quads
+        // below it into three bands
+        let mesh = primitives::UvSphere::build(Vec3::ZERO, 1.0, 8, 12).unwrap();
+        let (vertices, faces) = {
+            let conn = mesh.read_connectivity();
+            (conn.num_vertices(), conn.num_faces())
+        };
+        let edge_loop = {
+            let conn = mesh.read_connectivity();
+            let positions = mesh.read_positions();
+            let start = conn
+                .iter_halfedges()
+                .map(|(h, _)| h)
+                .find(|h| {
+                    let (v, w) = conn.at_halfedge(*h).src_dst_pair().unwrap();
+                    let y = positions[v].y;
+                    y.abs() < 0.999
+                        && (y - positions[w].y).abs() < 1e-5
+                        && conn
+                            .face_edges(conn.at_halfedge(*h).face().try_end().unwrap())
+                            .len()
+                            == 4
+                })
+                .unwrap();
+            selection::edge_loop(&conn, start).unwrap()
+        };
+        assert_eq!(edge_loop.len(), 12);
+        let new_edges = edit_ops::loop_cut(
+            &mut mesh.write_connectivity(),
+            &mut mesh.write_positions(),
+            &edge_loop,
+            2,
+        )
+        .unwrap();
+        assert_eq!(new_edges.len(), 24);
+
+        let conn = mesh.read_connectivity();
+        assert_eq!(conn.num_vertices(), vertices + 24);
+        assert_eq!(conn.num_faces(), faces + 24);
+        for (h, halfedge) in conn.iter_halfedges() {
+            assert!(halfedge.face.is_some());
+            assert_eq!(conn[halfedge.twin.unwrap()].twin, Some(h));
+        }
+        assert!(conn
+            .iter_faces()
+            .all(|(f, _)| conn.face_edges(f).len() <= 4));
+        // The new vertices lie on the edges they divided
+        let positions = mesh.read_positions();
+        for h in new_edges {
+            let (v, w) = conn.at_halfedge(h).src_dst_pair().unwrap();
+            assert!((positions[v].y - positions[w].y).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    pub fn test_bevel_segments() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE * 2.0);
+        let edge = {
+            let conn = mesh.read_connectivity();
+            let positions = mesh.read_positions();
+            conn.iter_halfedges()
+                .map(|(h, _)| h)
+                .find(|h| {
+                    let (v, w) = conn.at_halfedge(*h).src_dst_pair().unwrap();
+                    [v, w]
+                        .iter()
+                        .all(|v| positions[*v].y > 0.0 && positions[*v].z > 0.0)
+                })
+                .unwrap()
+        };
+        edit_ops::bevel_edges(
+            &mut mesh.write_connectivity(),
+            &mut mesh.write_positions(),
+            &[edge],
+            0.2,
+            4,
+            0.5,
+        )
+        .unwrap();
+
+        // A chamfered box, with the bevel face cut into four strips
+        let conn = mesh.read_connectivity();
+        assert_eq!(conn.num_vertices(), 16);
+        assert_eq!(conn.num_faces(), 10);
+        assert!(conn.iter_halfedges().all(|(_, h)| h.face.is_some()));
+
+        // With a round profile, the bevel vertices lie on a quarter circle
+        let positions = mesh.read_positions();
+        for (v, _) in conn.iter_vertices() {
+            let pos = positions[v];
+            if pos.y > 0.75 && pos.z > 0.75 {
+                let radius = Vec2::new(pos.y - 0.8, pos.z - 0.8).length();
+                assert!((radius - 0.2).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_flip_and_recompute_normals() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let face = mesh.read_connectivity().iter_faces().next().unwrap().0;
+        let normal = |mesh: &HalfEdgeMesh| {
+            mesh.read_connectivity()
+                .face_normal(&mesh.read_positions(), face)
+                .unwrap()
+        };
+        let original = normal(&mesh);
+
+        edit_ops::flip_faces(&mut mesh.write_connectivity(), &[face]).unwrap();
+        assert!(normal(&mesh).dot(original) < 0.0);
+        {
+            let conn = mesh.read_connectivity();
+            for (h, halfedge) in conn.iter_halfedges() {
+                let twin = halfedge.twin.unwrap();
+                assert_eq!(
+                    conn.at_halfedge(h).src_vertex().end(),
+                    conn.at_halfedge(twin).dst_vertex().end()
+                );
+            }
+        }
+
+        edit_ops::recompute_normals(&mut mesh.write_connectivity(), &mesh.read_positions())
+            .unwrap();
+        assert!(normal(&mesh).dot(original) > 0.0);
+    }
+
+    #[test]
+    pub fn test_solidify() {
+        let quad = primitives::Quad::build(Vec3::ZERO, Vec3::Y, Vec3::X, Vec2::ONE);
+        let solid =
+            edit_ops::solidify(&quad.read_connectivity(), &quad.read_positions(), 0.1).unwrap();
+        let conn = solid.read_connectivity();
+        assert_eq!(conn.num_vertices(), 8);
+        assert_eq!(conn.num_faces(), 6);
+        assert!(conn.iter_halfedges().all(|(_, h)| h.face.is_some()));
+    }
+
+    #[test]
+    pub fn test_mirror() {
+        let quad = primitives::Quad::build(Vec3::X * 0.5, Vec3::Y, Vec3::X, Vec2::ONE);
+        let (conn, positions) = (quad.read_connectivity(), quad.read_positions());
+
+        let welded = edit_ops::mirror(&conn, &positions, Vec3::ZERO, Vec3::X, true).unwrap();
+        assert_eq!(welded.read_connectivity().num_vertices(), 6);
+        assert_eq!(welded.read_connectivity().num_faces(), 2);
+        assert_eq!(welded.connected_components().unwrap().len(), 1);
+
+        let separate = edit_ops::mirror(&conn, &positions, Vec3::ZERO, Vec3::X, false).unwrap();
+        assert_eq!(separate.read_connectivity().num_vertices(), 8);
+        assert_eq!(separate.connected_components().unwrap().len(), 2);
+    }
+
+    #[test]
+    pub fn test_symmetrize() {
+        // A box from x = -0.25 to x = 0.75, straddling the YZ plane
+        let cube = primitives::Box::build(Vec3::X * 0.25, Vec3::ONE);
+        let (conn, positions) = (cube.read_connectivity(), cube.read_positions());
+
+        // Every vertex is either on the plane or at `extent` on either side.
+        // Vertices from the discarded side must not survive, and each vertex
+        // off the plane must have a mirrored copy.
+        let check_symmetric = |mesh: &HalfEdgeMesh, extent: f32| {
+            let conn = mesh.read_connectivity();
+            let positions = mesh.read_positions();
+            for (v, _) in conn.iter_vertices() {
+                let pos = positions[v];
+                assert!(pos.x.abs() < 1e-5 || (pos.x.abs() - extent).abs() < 1e-5);
+                let mirrored = pos * Vec3::new(-1.0, 1.0, 1.0);
+                assert!(conn
+                    .iter_vertices()
+                    .any(|(w, _)| positions[w].distance(mirrored) < 1e-5));
+            }
+        };
+
+        let positive =
+            edit_ops::symmetrize(&conn, &positions, selection::SymmetryAxis::X, true).unwrap();
+        // The face at x = -0.25 is dropped. The four side faces straddle the
+        // plane, so they are kept with their vertices at x = -0.25 clamped
+        // onto it, and those get welded to their reflection.
+        assert_eq!(positive.read_connectivity().num_vertices(), 12);
+        assert_eq!(positive.read_connectivity().num_faces(), 10);
+        assert_eq!(positive.connected_components().unwrap().len(), 1);
+        let (min, max) = positive.bounding_box().unwrap();
+        assert!((min.x + 0.75).abs() < 1e-5 && (max.x - 0.75).abs() < 1e-5);
+        check_symmetric(&positive, 0.75);
+
+        let negative =
+            edit_ops::symmetrize(&conn, &positions, selection::SymmetryAxis::X, false).unwrap();
+        assert_eq!(negative.read_connectivity().num_vertices(), 12);
+        assert_eq!(negative.read_connectivity().num_faces(), 10);
+        let (min, max) = negative.bounding_box().unwrap();
+        assert!((min.x + 0.25).abs() < 1e-5 && (max.x - 0.25).abs() < 1e-5);
+        check_symmetric(&negative, 0.25);
+    }
+
+    #[test]
+    pub fn test_revolve() {
+        let profile = primitives::Quad::build(Vec3::X * 2.0, Vec3::Z, Vec3::X, Vec2::ONE);
+        let is_closed = |mesh: &HalfEdgeMesh| {
+            mesh.read_connectivity()
+                .iter_halfedges()
+                .all(|(_, h)| h.face.is_some())
+        };
+
+        // A full turn gives a torus, with the seam welded
+        let torus = edit_ops::revolve(&profile, Vec3::ZERO, Vec3::Y, 360.0, 8).unwrap();
+        assert_eq!(torus.read_connectivity().num_vertices(), 32);
+        assert_eq!(torus.read_connectivity().num_faces(), 32);
+        assert!(is_closed(&torus));
+
+        // A partial turn is capped with the profile at both ends
+        let bend = edit_ops::revolve(&profile, Vec3::ZERO, Vec3::Y, 90.0, 4).unwrap();
+        assert_eq!(bend.read_connectivity().num_vertices(), 20);
+        assert_eq!(bend.read_connectivity().num_faces(), 18);
+        assert!(is_closed(&bend));
+
+        // Vertices on the axis are shared by all the segments
+        let on_axis = primitives::Quad::build(Vec3::X * 0.5, Vec3::Z, Vec3::X, Vec2::ONE);
+        let cylinder = edit_ops::revolve(&on_axis, Vec3::ZERO, Vec3::Y, 360.0, 8).unwrap();
+        assert_eq!(cylinder.read_connectivity().num_vertices(), 18);
+        assert!(is_closed(&cylinder));
+
+        assert!(edit_ops::revolve(&profile, Vec3::ZERO, Vec3::ZERO, 90.0, 4).is_err());
+    }
+
+    #[test]
+    pub fn test_sweep() {
+        let profile = primitives::Quad::build(Vec3::ZERO, Vec3::Z, Vec3::X, Vec2::ONE);
+        let path = [
+            Vec3::ZERO,
+            Vec3::Z * 2.0,
+            // A repeated point should be ignored
+            Vec3::Z * 2.0,
+            Vec3::new(2.0, 0.0, 2.0),
+            Vec3::new(2.0, 2.0, 2.0),
+        ];
+        let pipe = edit_ops::sweep(&profile, &path).unwrap();
+        let conn = pipe.read_connectivity();
+        assert_eq!(conn.num_vertices(), 16);
+        assert_eq!(conn.num_faces(), 14);
+        assert!(conn.iter_halfedges().all(|(_, h)| h.face.is_some()));
+
+        // The cross-sections at the ends are perpendicular to the path
+        let positions = pipe.read_positions();
+        for (v, _) in conn.iter_vertices() {
+            let pos = positions[v];
+            if pos.distance(path[0]) < 1.0 {
+                assert!(pos.z.abs() < 1e-5);
+            }
+            if pos.distance(path[4]) < 1.0 {
+                assert!((pos.y - 2.0).abs() < 1e-5);
+            }
+        }
+
+        assert!(edit_ops::sweep(&profile, &[Vec3::ZERO, Vec3::ZERO]).is_err());
+    }
+
+    #[test]
+    pub fn test_array() {
+        let quad = primitives::Quad::build(Vec3::ZERO, Vec3::Y, Vec3::X, Vec2::ONE);
+
+        let separate = edit_ops::array(&quad, 3, Vec3::X, false).unwrap();
+        assert_eq!(separate.read_connectivity().num_vertices(), 12);
+        assert_eq!(separate.connected_components().unwrap().len(), 3);
+
+        let merged = edit_ops::array(&quad, 3, Vec3::X, true).unwrap();
+        assert_eq!(merged.read_connectivity().num_vertices(), 8);
+        assert_eq!(merged.read_connectivity().num_faces(), 3);
+        assert_eq!(merged.connected_components().unwrap().len(), 1);
+    }
+
+    #[test]
+    pub fn test_bridge_loops() {
+        let mesh = HalfEdgeMesh::build_from_polygons(
+            &[
+                Vec3::new(-1.0, 0.0, -1.0),
+                Vec3::new(1.0, 0.0, -1.0),
+                Vec3::new(1.0, 0.0, 1.0),
+                Vec3::new(-1.0, 0.0, 1.0),
+                Vec3::new(-1.0, 1.0, 1.0),
+                Vec3::new(1.0, 1.0, 1.0),
+                Vec3::new(1.0, 1.0, -1.0),
+                Vec3::new(-1.0, 1.0, -1.0),
+            ],
+            &[[0, 1, 2, 3], [4, 5, 6, 7]],
+        )
+        .unwrap();
+        let (bottom, top) = {
+            let conn = mesh.read_connectivity();
+            let mut faces = conn.iter_faces().map(|(f, _)| f);
+            let bottom = conn.at_face(faces.next().unwrap()).halfedge().end();
+            let top = conn.at_face(faces.next().unwrap()).halfedge().end();
+            (bottom, top)
+        };
+
+        let faces = edit_ops::bridge_loops(
+            &mut mesh.write_connectivity(),
+            &mesh.read_positions(),
+            &[bottom],
+            &[top],
+        )
+        .unwrap();
+        assert_eq!(faces.len(), 4);
+
+        let conn = mesh.read_connectivity();
+        assert_eq!(conn.num_faces(), 6);
+        for (h, halfedge) in conn.iter_halfedges() {
+            assert!(halfedge.face.is_some());
+            assert_eq!(conn[halfedge.twin.unwrap()].twin, Some(h));
+        }
+        let positions = mesh.read_positions();
+        for face in faces {
+            // Side faces are vertical, so the bridge isn't twisted
+            let normal = conn.face_normal(&positions, face).unwrap();
+            assert!(normal.y.abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    pub fn test_compute_curvature() {
+        let mut mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let ch_id = edit_ops::compute_curvature(&mut mesh).unwrap();
+        let curvature = mesh.channels.read_channel(ch_id).unwrap();
+        for (v, _) in mesh.read_connectivity().iter_vertices() {
+            assert!(curvature[v] > 0.0);
+        }
+    }
+
+    #[test]
+    pub fn test_laplacian_smooth() {
+        // A 4x4 grid of vertices, with the four interior ones displaced
+        let n = 4;
+        let mut points = vec![];
+        for z in 0..n {
+            for x in 0..n {
+                let interior = x > 0 && x < n - 1 && z > 0 && z < n - 1;
+                let noise = if interior {
+                    ((x * 3 + z) % 4) as f32 * 0.3
+                } else {
+                    0.0
+                };
+                points.push(Vec3::new(x as f32, noise, z as f32));
+            }
+        }
+        let mut quads = vec![];
+        for z in 0..n - 1 {
+            for x in 0..n - 1 {
+                let i = z * n + x;
+                quads.push([i, i + n, i + n + 1, i + 1]);
+            }
+        }
+        let mesh = HalfEdgeMesh::build_from_polygons(&points, &quads).unwrap();
+        let conn = mesh.read_connectivity();
+        let original: HashMap<VertexId, Vec3> = {
+            let positions = mesh.read_positions();
+            conn.iter_vertices()
+                .map(|(v, _)| (v, positions[v]))
+                .collect()
+        };
+        let is_interior = |pos: Vec3| pos.x > 0.5 && pos.x < 2.5 && pos.z > 0.5 && pos.z < 2.5;
+
+        // One of the interior vertices is left out of the selection
+        let pinned = *original
+            .iter()
+            .find(|(_, pos)| is_interior(**pos))
+            .unwrap()
+            .0;
+        let verts: Vec<VertexId> = original.keys().copied().filter(|v| *v != pinned).collect();
+        edit_ops::laplacian_smooth(
+            &conn,
+            &mut mesh.write_positions(),
+            &verts,
+            1,
+            0.5,
+            true,
+            &Progress::new(),
+        )
+        .unwrap();
+
+        let positions = mesh.read_positions();
+        let mut moved = 0;
+        for (&v, &pos) in &original {
+            if v == pinned || !is_interior(pos) {
+                assert_eq!(positions[v], pos);
+                continue;
+            }
+            let neighbors = conn.at_vertex(v).outgoing_halfedges().unwrap();
+            let average = neighbors
+                .iter()
+                .map(|h| original[&conn.at_halfedge(*h).dst_vertex().end()])
+                .fold(Vec3::ZERO, |acc, p| acc + p)
+                / neighbors.len() as f32;
+            assert!(positions[v].distance(pos.lerp(average, 0.5)) < 1e-5);
+            assert!(positions[v].distance(average) < pos.distance(average) + 1e-6);
+            moved += 1;
+        }
+        assert_eq!(moved, 3);
+    }
+
+    #[test]
+    pub fn test_fill_holes() {
+        let (a, b, c, d) = quad_abcd();
+        let quad = || HalfEdgeMesh::build_from_polygons(&[a, b, c, d], &[[0, 1, 2, 3]]).unwrap();
+
+        // The boundary loop is longer than the limit, so it is left open
+        let mesh = quad();
+        let mut conn = mesh.write_connectivity();
+        assert!(edit_ops::fill_holes(&mut conn, 3).unwrap().is_empty());
+        assert_eq!(conn.num_faces(), 1);
+        assert_eq!(conn.boundary_loops().unwrap().len(), 1);
+
+        let mesh = quad();
+        let mut conn = mesh.write_connectivity();
+        let (front, _) = conn.iter_faces().next().unwrap();
+        let new_faces = edit_ops::fill_holes(&mut conn, 4).unwrap();
+        assert_eq!(new_faces.len(), 1);
+        assert_eq!(conn.num_faces(), 2);
+        assert!(conn.boundary_loops().unwrap().is_empty());
+
+        // The cap uses the same vertices as the quad, in the opposite order
+        let mut front_verts = conn.face_vertices(front);
+        let back_verts = conn.face_vertices(new_faces[0]);
+        front_verts.reverse();
+        assert_eq!(back_verts.len(), 4);
+        let offset = back_verts
+            .iter()
+            .position(|v| *v == front_verts[0])
+            .unwrap();
+        assert!((0..4).all(|i| back_verts[(offset + i) % 4] == front_verts[i]));
+    }
+
+    #[test]
+    pub fn test_dual() {
+        // The dual of a cube is an octahedron
+        let cube = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let dual = edit_ops::dual(&cube.read_connectivity(), &cube.read_positions()).unwrap();
+        let conn = dual.read_connectivity();
+        let positions = dual.read_positions();
+        assert_eq!(conn.num_vertices(), 6);
+        assert_eq!(conn.num_faces(), 8);
+        assert!(conn.boundary_loops().unwrap().is_empty());
+
+        for (v, _) in conn.iter_vertices() {
+            // Each vertex sits at the center of one of the cube faces
+            assert!((positions[v].abs().max_element() - 0.5).abs() < 1e-5);
+            assert!((positions[v].length() - 0.5).abs() < 1e-5);
+        }
+        for (face, _) in conn.iter_faces() {
+            assert_eq!(conn.face_vertices(face).len(), 3);
+            // Faces keep the same outward orientation as the original mesh
+            let centroid = conn.face_vertex_average(&positions, face);
+            assert!(conn.face_area_normal(&positions, face).dot(centroid) > 0.0);
+        }
+    }
+
+    #[test]
+    pub fn test_transform() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let conn = mesh.read_connectivity();
+        let original: HashMap<VertexId, Vec3> = {
+            let positions = mesh.read_positions();
+            conn.iter_vertices()
+                .map(|(v, _)| (v, positions[v]))
+                .collect()
+        };
+        // The top face of the box
+        let top: Vec<VertexId> = original
+            .iter()
+            .filter(|(_, pos)| pos.y > 0.0)
+            .map(|(v, _)| *v)
+            .collect();
+        assert_eq!(top.len(), 4);
+
+        let translation = Mat4::from_translation(Vec3::Y);
+        edit_ops::transform(&conn, &mut mesh.write_positions(), &top, translation).unwrap();
+        // A quarter turn around the Y axis, with the pivot at the top corner
+        let pivot = Vec3::new(0.5, 1.5, 0.5);
+        let rotation = Mat4::from_translation(pivot)
+            * Mat4::from_rotation_y(std::f32::consts::FRAC_PI_2)
+            * Mat4::from_translation(-pivot);
+        edit_ops::transform(&conn, &mut mesh.write_positions(), &top, rotation).unwrap();
+
+        let positions = mesh.read_positions();
+        for (v, original) in original {
+            if top.contains(&v) {
+                let moved = original + Vec3::Y;
+                let expected =
+                    pivot + Quat::from_rotation_y(std::f32::consts::FRAC_PI_2) * (moved - pivot);
+                assert!(positions[v].distance(expected) < 1e-5);
+            } else {
+                assert_eq!(positions[v], original);
+            }
+        }
+        drop(positions);
+
+        assert!(edit_ops::transform(
+            &conn,
+            &mut mesh.write_positions(),
+            &[VertexId::default()],
+            translation
+        )
+        .is_err());
+    }
+
+    #[test]
+    pub fn test_jitter_vertices() {
+        let jittered = |seed: u64| {
+            let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+            let verts: Vec<VertexId> = mesh
+                .read_connectivity()
+                .iter_vertices()
+                .map(|(v, _)| v)
+                .collect();
+            edit_ops::jitter_vertices(
+                &mesh.read_connectivity(),
+                &mut mesh.write_positions(),
+                &verts,
+                0.1,
+                seed,
+            )
+            .unwrap();
+            let positions = mesh.read_positions();
+            verts.iter().map(|v| positions[*v]).collect::<Vec<_>>()
+        };
+
+        let original = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let original: Vec<Vec3> = {
+            let positions = original.read_positions();
+            original
+                .read_connectivity()
+                .iter_vertices()
+                .map(|(v, _)| positions[v])
+                .collect()
+        };
+
+        let a = jittered(42);
+        assert_eq!(a, jittered(42));
+        assert_ne!(a, jittered(43));
+        for (jittered, original) in a.iter().zip(&original) {
+            assert!(jittered.distance(*original) <= 0.1 + 1e-6);
+        }
+    }
+
     #[test]
     pub fn test_face_size() {
         let hem = HalfEdgeMesh::new();
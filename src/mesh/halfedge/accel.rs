@@ -0,0 +1,372 @@
+use crate::prelude::*;
+
+/// Maximum number of elements stored in a leaf node of the BVH
+const MAX_LEAF_SIZE: usize = 4;
+
+#[derive(Clone, Copy, Debug)]
+struct Aabb {
+    min: Vec3,
+    max: Vec3,
+}
+
+impl Aabb {
+    fn empty() -> Self {
+        Self {
+            min: Vec3::splat(f32::INFINITY),
+            max: Vec3::splat(f32::NEG_INFINITY),
+        }
+    }
+
+    fn from_points(points: &[Vec3]) -> Self {
+        points.iter().fold(Self::empty(), |aabb, p| aabb.grow(*p))
+    }
+
+    fn grow(self, p: Vec3) -> Self {
+        Self {
+            min: self.min.min(p),
+            max: self.max.max(p),
+        }
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Squared distance from `p` to the closest point in the box. Zero when
+    /// `p` is inside.
+    fn distance_squared(&self, p: Vec3) -> f32 {
+        let closest = p.clamp(self.min, self.max);
+        closest.distance_squared(p)
+    }
+
+    /// Slab test. Returns the distance along the ray at which it enters the
+    /// box, if it does so before `max_t`.
+    fn ray_entry(&self, origin: Vec3, inv_dir: Vec3, max_t: f32) -> Option<f32> {
+        let t1 = (self.min - origin) * inv_dir;
+        let t2 = (self.max - origin) * inv_dir;
+        let t_near = t1.min(t2).max_element().max(0.0);
+        let t_far = t1.max(t2).min_element().min(max_t);
+        if t_near <= t_far {
+            Some(t_near)
+        } else {
+            None
+        }
+    }
+}
+
+enum BvhNode {
+    Leaf {
+        bounds: Aabb,
+        start: usize,
+        end: usize,
+    },
+    Inner {
+        bounds: Aabb,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl BvhNode {
+    fn bounds(&self) -> &Aabb {
+        match self {
+            BvhNode::Leaf { bounds, .. } | BvhNode::Inner { bounds, .. } => bounds,
+        }
+    }
+}
+
+/// A bounding volume hierarchy over a list of elements, each with its own
+/// bounding box. The tree only stores indices into that list.
+struct Bvh {
+    nodes: Vec<BvhNode>,
+    /// Element indices, ordered so that every leaf covers a contiguous range
+    order: Vec<usize>,
+}
+
+impl Bvh {
+    fn build(bounds: &[Aabb]) -> Self {
+        let mut bvh = Self {
+            nodes: vec![],
+            order: (0..bounds.len()).collect(),
+        };
+        if !bounds.is_empty() {
+            bvh.build_node(bounds, 0, bounds.len());
+        }
+        bvh
+    }
+
+    /// Builds the subtree for the elements in `order[start..end]`, splitting
+    /// them at the median along the longest axis. Returns the node index.
+    fn build_node(&mut self, bounds: &[Aabb], start: usize, end: usize) -> usize {
+        let node_bounds = self.order[start..end]
+            .iter()
+            .fold(Aabb::empty(), |acc, i| acc.union(bounds[*i]));
+
+        if end - start <= MAX_LEAF_SIZE {
+            self.nodes.push(BvhNode::Leaf {
+                bounds: node_bounds,
+                start,
+                end,
+            });
+            return self.nodes.len() - 1;
+        }
+
+        let centers = self.order[start..end]
+            .iter()
+            .fold(Aabb::empty(), |acc, i| acc.grow(bounds[*i].center()));
+        let extent = centers.max - centers.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        let mid = (start + end) / 2;
+        self.order[start..end].select_nth_unstable_by(mid - start, |a, b| {
+            bounds[*a].center()[axis]
+                .partial_cmp(&bounds[*b].center()[axis])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        // Reserve the slot for this node before building the children
+        let idx = self.nodes.len();
+        self.nodes.push(BvhNode::Leaf {
+            bounds: node_bounds,
+            start,
+            end,
+        });
+        let left = self.build_node(bounds, start, mid);
+        let right = self.build_node(bounds, mid, end);
+        self.nodes[idx] = BvhNode::Inner {
+            bounds: node_bounds,
+            left,
+            right,
+        };
+        idx
+    }
+}
+
+/// An acceleration structure for spatial queries over the vertices and faces
+/// of a mesh. The structure is a snapshot: It must be rebuilt after the mesh
+/// is modified.
+pub struct BvhAccel {
+    vertices: Vec<(VertexId, Vec3)>,
+    vertex_bvh: Bvh,
+    /// Faces get fan-triangulated, so a face may have several triangles
+    triangles: Vec<(FaceId, [Vec3; 3])>,
+    triangle_bvh: Bvh,
+}
+
+impl BvhAccel {
+    pub fn build(mesh: &HalfEdgeMesh) -> Self {
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+
+        let vertices = conn
+            .iter_vertices_with_channel(&positions)
+            .map(|(v, _, pos)| (v, pos))
+            .collect_vec();
+        let vertex_bvh = Bvh::build(
+            &vertices
+                .iter()
+                .map(|(_, pos)| Aabb::from_points(&[*pos]))
+                .collect_vec(),
+        );
+
+        let mut triangles = vec![];
+        for (face, _) in conn.iter_faces() {
+            let verts = conn.face_vertices(face);
+            for i in 1..verts.len().saturating_sub(1) {
+                triangles.push((
+                    face,
+                    [
+                        positions[verts[0]],
+                        positions[verts[i]],
+                        positions[verts[i + 1]],
+                    ],
+                ));
+            }
+        }
+        let triangle_bvh = Bvh::build(
+            &triangles
+                .iter()
+                .map(|(_, tri)| Aabb::from_points(tri))
+                .collect_vec(),
+        );
+
+        Self {
+            vertices,
+            vertex_bvh,
+            triangles,
+            triangle_bvh,
+        }
+    }
+
+    /// Returns the vertex closest to `point`, or `None` if the mesh has no
+    /// vertices.
+    pub fn nearest_vertex(&self, point: Vec3) -> Option<VertexId> {
+        let mut best: Option<(VertexId, f32)> = None;
+        let mut stack = vec![];
+        if !self.vertex_bvh.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(node) = stack.pop() {
+            let node = &self.vertex_bvh.nodes[node];
+            if let Some((_, best_dist)) = best {
+                if node.bounds().distance_squared(point) >= best_dist {
+                    continue;
+                }
+            }
+            match node {
+                BvhNode::Leaf { start, end, .. } => {
+                    for i in &self.vertex_bvh.order[*start..*end] {
+                        let (v, pos) = self.vertices[*i];
+                        let dist = pos.distance_squared(point);
+                        if best.map(|(_, d)| dist < d).unwrap_or(true) {
+                            best = Some((v, dist));
+                        }
+                    }
+                }
+                BvhNode::Inner { left, right, .. } => {
+                    // Push the closest child last, so it's visited first
+                    let dist_l = self.vertex_bvh.nodes[*left]
+                        .bounds()
+                        .distance_squared(point);
+                    let dist_r = self.vertex_bvh.nodes[*right]
+                        .bounds()
+                        .distance_squared(point);
+                    if dist_l < dist_r {
+                        stack.extend([*right, *left]);
+                    } else {
+                        stack.extend([*left, *right]);
+                    }
+                }
+            }
+        }
+        best.map(|(v, _)| v)
+    }
+
+    /// Casts a ray from `origin` along `dir`. Returns the first face hit by
+    /// the ray, and the hit point.
+    pub fn raycast(&self, origin: Vec3, dir: Vec3) -> Option<(FaceId, Vec3)> {
+        let dir = dir.normalize_or_zero();
+        if dir == Vec3::ZERO || self.triangle_bvh.nodes.is_empty() {
+            return None;
+        }
+        let inv_dir = dir.recip();
+
+        let mut best: Option<(FaceId, f32)> = None;
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.triangle_bvh.nodes[node];
+            let max_t = best.map(|(_, t)| t).unwrap_or(f32::INFINITY);
+            if node.bounds().ray_entry(origin, inv_dir, max_t).is_none() {
+                continue;
+            }
+            match node {
+                BvhNode::Leaf { start, end, .. } => {
+                    for i in &self.triangle_bvh.order[*start..*end] {
+                        let (face, tri) = &self.triangles[*i];
+                        if let Some(t) = ray_triangle_intersection(origin, dir, tri) {
+                            if best.map(|(_, best_t)| t < best_t).unwrap_or(true) {
+                                best = Some((*face, t));
+                            }
+                        }
+                    }
+                }
+                BvhNode::Inner { left, right, .. } => {
+                    stack.extend([*left, *right]);
+                }
+            }
+        }
+        best.map(|(face, t)| (face, origin + dir * t))
+    }
+}
+
+/// Möller-Trumbore ray-triangle intersection. Returns the distance along the
+/// ray to the hit point. Both sides of the triangle are considered.
+fn ray_triangle_intersection(origin: Vec3, dir: Vec3, [a, b, c]: &[Vec3; 3]) -> Option<f32> {
+    let e1 = *b - *a;
+    let e2 = *c - *a;
+    let p = dir.cross(e2);
+    let det = e1.dot(p);
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let s = origin - *a;
+    let u = s.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(e1);
+    let v = dir.dot(q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = e2.dot(q) * inv_det;
+    if t >= 0.0 {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_nearest_vertex() {
+        assert!(BvhAccel::build(&HalfEdgeMesh::new())
+            .nearest_vertex(Vec3::ZERO)
+            .is_none());
+
+        let mesh = primitives::UvSphere::build(Vec3::ZERO, 1.0, 8, 12).unwrap();
+        let accel = BvhAccel::build(&mesh);
+        let positions = mesh.read_positions();
+        let conn = mesh.read_connectivity();
+        for point in [
+            Vec3::new(0.3, 2.0, -0.1),
+            Vec3::X * 0.5,
+            Vec3::new(-1.0, -1.0, 1.0),
+        ] {
+            let (expected, _, _) = conn
+                .iter_vertices_with_channel(&positions)
+                .min_by(|(_, _, a), (_, _, b)| {
+                    a.distance(point).partial_cmp(&b.distance(point)).unwrap()
+                })
+                .unwrap();
+            let found = accel.nearest_vertex(point).unwrap();
+            assert!(
+                (positions[found].distance(point) - positions[expected].distance(point)).abs()
+                    < 1e-6
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_raycast() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let accel = BvhAccel::build(&mesh);
+
+        let (face, hit) = accel.raycast(Vec3::new(0.1, 5.0, 0.2), -Vec3::Y).unwrap();
+        assert!(hit.distance(Vec3::new(0.1, 0.5, 0.2)) < 1e-5);
+        let normal = mesh
+            .read_connectivity()
+            .face_normal(&mesh.read_positions(), face)
+            .unwrap();
+        assert!(normal.dot(Vec3::Y).abs() > 0.99);
+
+        assert!(accel.raycast(Vec3::new(0.1, 5.0, 0.2), Vec3::Y).is_none());
+        assert!(accel.raycast(Vec3::new(2.0, 5.0, 0.0), -Vec3::Y).is_none());
+    }
+}
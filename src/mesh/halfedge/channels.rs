@@ -60,10 +60,24 @@ impl Introspect for f32 {
     }
 }
 
+impl Introspect for i32 {
+    fn introspect(&self) -> String {
+        format!("{: >6}", self)
+    }
+}
+
+impl Introspect for String {
+    fn introspect(&self) -> String {
+        self.clone()
+    }
+}
+
 /// The value of a channel is the data that is associated to a specific key.
-/// Values can be scalars (f32) or vectors (Vec3).
+/// Values can be scalars (f32), integers (i32), vectors (Vec3) or strings.
+/// Values are only required to be `Clone`, not `Copy`, so generic code must
+/// clone values explicitly.
 pub trait ChannelValue:
-    Default + Debug + Clone + Copy + Sized + FromToLua + Introspect + 'static
+    Default + Debug + Clone + Sized + FromToLua + Introspect + ChannelArithmetic + 'static
 {
     fn value_type() -> ChannelValueType;
     fn name() -> &'static str;
@@ -83,6 +97,105 @@ macro_rules! impl_channel_value {
 }
 impl_channel_value!(Vec3);
 impl_channel_value!(f32);
+impl_channel_value!(i32);
+impl_channel_value!(String);
+
+/// The element-wise arithmetic operations supported between channels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// The right hand side of an arithmetic operation on a channel. Scalars and
+/// vectors get applied to every element of the channel.
+pub enum ChannelOperand<'a> {
+    Channel(&'a dyn DynChannel),
+    Scalar(f32),
+    Vector(Vec3),
+}
+
+/// Arithmetic on channel values, used to implement element-wise operations
+/// between channels.
+pub trait ChannelArithmetic: Sized {
+    /// Converts a scalar to this type. Vectors get the scalar on all
+    /// components.
+    fn from_scalar(x: f32) -> Self;
+    /// Converts a vector to this type. Fails for scalar types.
+    fn from_vector(v: Vec3) -> Result<Self>;
+    fn apply_op(self, op: ChannelOp, other: Self) -> Result<Self>;
+}
+
+impl ChannelArithmetic for f32 {
+    fn from_scalar(x: f32) -> Self {
+        x
+    }
+    fn from_vector(_: Vec3) -> Result<Self> {
+        bail!("Can't operate a vector with a scalar channel")
+    }
+    fn apply_op(self, op: ChannelOp, other: Self) -> Result<Self> {
+        Ok(match op {
+            ChannelOp::Add => self + other,
+            ChannelOp::Sub => self - other,
+            ChannelOp::Mul => self * other,
+            ChannelOp::Div => self / other,
+        })
+    }
+}
+
+impl ChannelArithmetic for Vec3 {
+    fn from_scalar(x: f32) -> Self {
+        Vec3::splat(x)
+    }
+    fn from_vector(v: Vec3) -> Result<Self> {
+        Ok(v)
+    }
+    fn apply_op(self, op: ChannelOp, other: Self) -> Result<Self> {
+        Ok(match op {
+            ChannelOp::Add => self + other,
+            ChannelOp::Sub => self - other,
+            ChannelOp::Mul => self * other,
+            ChannelOp::Div => self / other,
+        })
+    }
+}
+
+/// Strings can only be concatenated, using the `Add` operation
+impl ChannelArithmetic for String {
+    fn from_scalar(x: f32) -> Self {
+        x.to_string()
+    }
+    fn from_vector(_: Vec3) -> Result<Self> {
+        bail!("Can't operate a vector with a string channel")
+    }
+    fn apply_op(self, op: ChannelOp, other: Self) -> Result<Self> {
+        match op {
+            ChannelOp::Add => Ok(self + &other),
+            _ => bail!("Only addition (concatenation) is supported on string channels"),
+        }
+    }
+}
+
+impl ChannelArithmetic for i32 {
+    fn from_scalar(x: f32) -> Self {
+        x as i32
+    }
+    fn from_vector(_: Vec3) -> Result<Self> {
+        bail!("Can't operate a vector with an integer channel")
+    }
+    fn apply_op(self, op: ChannelOp, other: Self) -> Result<Self> {
+        Ok(match op {
+            ChannelOp::Add => self.wrapping_add(other),
+            ChannelOp::Sub => self.wrapping_sub(other),
+            ChannelOp::Mul => self.wrapping_mul(other),
+            ChannelOp::Div => self
+                .checked_div(other)
+                .ok_or_else(|| anyhow!("Integer division by zero in channel operation"))?,
+        })
+    }
+}
 
 /// The `FromLua` and `ToLua` traits have a lifetime parameter which is
 /// unnecessary for the channel keys and values. We introduce this new trait
@@ -122,6 +235,8 @@ macro_rules! impl_from_to_lua {
 }
 impl_from_to_lua!(wrapped Vec3);
 impl_from_to_lua!(flat f32);
+impl_from_to_lua!(flat i32);
+impl_from_to_lua!(flat String);
 impl_from_to_lua!(flat VertexId);
 impl_from_to_lua!(flat FaceId);
 impl_from_to_lua!(flat HalfEdgeId);
@@ -137,7 +252,7 @@ pub enum ChannelKeyType { VertexId, FaceId, HalfEdgeId }
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
 #[rustfmt::skip]
 #[allow(non_camel_case_types)]
-pub enum ChannelValueType { Vec3, f32, }
+pub enum ChannelValueType { Vec3, f32, i32, String, }
 
 /// A channel represents a set of data that is associated over all the elements
 /// of a mesh. For instance, the well-known `position` channel of a mesh, is a
@@ -251,6 +366,23 @@ impl<K: ChannelKey, V: ChannelValue> Channel<K, V> {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (K, &mut V)> {
         self.inner.iter_mut()
     }
+    /// Returns the value stored for `key`. Unlike indexing, this returns
+    /// `None` instead of the default value for keys that were never written.
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.inner.get(key)
+    }
+    /// Sets the value for all the given `keys`. Unlike indexing, this
+    /// allocates an entry for every key upfront.
+    pub fn fill(&mut self, keys: impl Iterator<Item = K>, value: V) {
+        for k in keys {
+            self[k] = value.clone();
+        }
+    }
+    /// Removes all the values in this channel, so that reading any key
+    /// returns the default value again.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
 }
 
 /// This trait provides dynamic access to a `Channel`. It is mainly used to
@@ -303,6 +435,41 @@ pub trait DynChannel: Any + Debug {
         table: mlua::Table<'lua>,
     ) -> Result<()>;
 
+    /// Sets the value for all the given `keys` to `value`, which is converted
+    /// from Lua. Same as the `fill` method of the typed channel.
+    fn fill_lua<'lua>(
+        &mut self,
+        keys: Box<dyn Iterator<Item = u64> + '_>,
+        lua: &'lua mlua::Lua,
+        value: mlua::Value<'lua>,
+    ) -> Result<()>;
+
+    /// Removes all the values in this channel. Same as the `clear` method of
+    /// the typed channel.
+    fn clear_dyn(&mut self);
+
+    /// Returns the key type of this channel
+    fn key_type_dyn(&self) -> ChannelKeyType;
+
+    /// Returns the value type of this channel
+    fn value_type_dyn(&self) -> ChannelValueType;
+
+    /// Formats the values for the given `keys` for UI display, using the
+    /// [`Introspect`] trait.
+    fn introspect_dyn(&self, keys: &[slotmap::KeyData]) -> Vec<String>;
+
+    /// Applies the arithmetic operation `op` element-wise between this channel
+    /// and `other`, returning the result as a new channel. When `other` is a
+    /// channel, both channels must have the same key and value types. When
+    /// `reversed` is set, the operands are swapped, that is, the result is
+    /// `other op self`.
+    fn binary_op_dyn(
+        &self,
+        op: ChannelOp,
+        other: ChannelOperand,
+        reversed: bool,
+    ) -> Result<Rc<RefCell<dyn DynChannel>>>;
+
     /// Merges this channel with another channel. This method will panic if both
     /// channels are not of the same type.
     ///
@@ -326,7 +493,7 @@ impl<K: ChannelKey, V: ChannelValue> DynChannel for Channel<K, V> {
         'lua: 'a,
     {
         let key: K = K::cast_from_lua(key, lua)?;
-        Ok(self[key].cast_to_lua(lua))
+        Ok(self[key].clone().cast_to_lua(lua))
     }
 
     fn set_lua<'a, 'lua>(
@@ -348,8 +515,11 @@ impl<K: ChannelKey, V: ChannelValue> DynChannel for Channel<K, V> {
         keys: Box<dyn Iterator<Item = u64> + '_>,
         lua: &'lua mlua::Lua,
     ) -> mlua::Table<'lua> {
-        lua.create_sequence_from(keys.map(K::cast_from_ffi).map(|k| self[k].cast_to_lua(lua)))
-            .unwrap()
+        lua.create_sequence_from(
+            keys.map(K::cast_from_ffi)
+                .map(|k| self[k].clone().cast_to_lua(lua)),
+        )
+        .unwrap()
     }
 
     fn set_from_table<'lua>(
@@ -367,6 +537,85 @@ impl<K: ChannelKey, V: ChannelValue> DynChannel for Channel<K, V> {
         Ok(())
     }
 
+    fn fill_lua<'lua>(
+        &mut self,
+        keys: Box<dyn Iterator<Item = u64> + '_>,
+        lua: &'lua mlua::Lua,
+        value: mlua::Value<'lua>,
+    ) -> Result<()> {
+        let value: V = FromToLua::cast_from_lua(value, lua)?;
+        self.fill(keys.map(K::cast_from_ffi), value);
+        Ok(())
+    }
+
+    fn clear_dyn(&mut self) {
+        self.clear();
+    }
+
+    fn key_type_dyn(&self) -> ChannelKeyType {
+        K::key_type()
+    }
+
+    fn value_type_dyn(&self) -> ChannelValueType {
+        V::value_type()
+    }
+
+    fn introspect_dyn(&self, keys: &[slotmap::KeyData]) -> Vec<String> {
+        keys.iter()
+            .map(|k| self[K::from(*k)].introspect())
+            .collect()
+    }
+
+    fn binary_op_dyn(
+        &self,
+        op: ChannelOp,
+        other: ChannelOperand,
+        reversed: bool,
+    ) -> Result<Rc<RefCell<dyn DynChannel>>> {
+        let apply = |a: V, b: V| {
+            if reversed {
+                b.apply_op(op, a)
+            } else {
+                a.apply_op(op, b)
+            }
+        };
+        let value = match other {
+            ChannelOperand::Channel(other) => {
+                let other = other.as_any().downcast_ref::<Self>().ok_or_else(|| {
+                    anyhow!(
+                        "Can't operate a channel of {:?} {:?} with a channel of {:?} {:?}",
+                        K::key_type(),
+                        V::value_type(),
+                        other.key_type_dyn(),
+                        other.value_type_dyn(),
+                    )
+                })?;
+                let mut result = Channel {
+                    inner: slotmap::SecondaryMap::new(),
+                    default: apply(self.default.clone(), other.default.clone())
+                        .unwrap_or_else(|_| self.default.clone()),
+                };
+                for k in self.inner.keys().chain(other.inner.keys()) {
+                    result[k] = apply(self[k].clone(), other[k].clone())?;
+                }
+                return Ok(Rc::new(RefCell::new(result)));
+            }
+            ChannelOperand::Scalar(x) => V::from_scalar(x),
+            ChannelOperand::Vector(v) => V::from_vector(v)?,
+        };
+        // The op may not be defined for the defaults, like when dividing
+        // integers by zero. The default is kept as is in that case.
+        let mut result = Channel {
+            inner: slotmap::SecondaryMap::new(),
+            default: apply(self.default.clone(), value.clone())
+                .unwrap_or_else(|_| self.default.clone()),
+        };
+        for (k, v) in self.inner.iter() {
+            result[k] = apply(v.clone(), value.clone())?;
+        }
+        Ok(Rc::new(RefCell::new(result)))
+    }
+
     fn merge_with_dyn(
         &mut self,
         other: &dyn DynChannel,
@@ -378,7 +627,7 @@ impl<K: ChannelKey, V: ChannelValue> DynChannel for Channel<K, V> {
             for id in get_ids(K::key_type()).iter_cpy() {
                 let k_self = K::cast_from_ffi(id_map(K::key_type(), id).as_ffi());
                 let k_other = K::cast_from_ffi(id.as_ffi());
-                self[k_self] = other[k_other];
+                self[k_self] = other[k_other].clone();
             }
         } else {
             panic!(
@@ -420,6 +669,40 @@ impl<K: ChannelKey, V: ChannelValue> ChannelGroup<K, V> {
         }
     }
 
+    /// Creates a new channel named `new_name` with a copy of the contents of
+    /// the channel with given `id`. Returns an error if the channel doesn't
+    /// exist, a channel named `new_name` already exists, or the channel is
+    /// currently mutably borrowed.
+    pub fn duplicate_channel(
+        &mut self,
+        id: ChannelId<K, V>,
+        new_name: &str,
+    ) -> Result<ChannelId<K, V>> {
+        if self.channel_names.contains_left(new_name) {
+            bail!("The channel named {new_name} already exists in mesh");
+        }
+        let copy = self.read_channel(id)?.clone();
+        let new_id = ChannelId::new(self.channels.insert(Rc::new(RefCell::new(copy))));
+        self.channel_names.insert(new_name.into(), new_id);
+        Ok(new_id)
+    }
+
+    /// Changes the name of the channel with given `id`, keeping its data.
+    /// Returns an error if the channel doesn't exist or another channel named
+    /// `new_name` already exists.
+    pub fn rename_channel(&mut self, id: ChannelId<K, V>, new_name: &str) -> Result<()> {
+        match self.channel_names.get_by_left(new_name) {
+            Some(existing) if *existing == id => return Ok(()),
+            Some(_) => bail!("The channel named {new_name} already exists in mesh"),
+            None => {}
+        }
+        if self.channel_names.remove_by_right(&id).is_none() {
+            bail!("Channel {id:?} does not exist for this mesh");
+        }
+        self.channel_names.insert(new_name.into(), id);
+        Ok(())
+    }
+
     /// Removes a channel with given `id`. Returns error when the channel:
     /// - Doesn't exist
     /// - Is borrowed somewhere else (via a cloned Rc)
@@ -486,6 +769,12 @@ pub trait DynChannelGroup: Any + Debug + dyn_clone::DynClone {
     fn write_channel_dyn(&self, raw_id: RawChannelId) -> RefMut<dyn DynChannel>;
     /// Same as `channel_id`, but with erased types.
     fn channel_id_dyn(&self, name: &str) -> Option<RawChannelId>;
+    /// Same as `rename_channel`, but with erased types.
+    fn rename_channel_dyn(&mut self, raw_id: RawChannelId, new_name: &str) -> Result<()>;
+    /// Overwrites the contents of the channel with given `name`, creating it if
+    /// necessary, with a copy of `channel`. Fails if `channel` has different
+    /// key or value types than this group.
+    fn set_channel_dyn(&mut self, name: &str, channel: &dyn DynChannel) -> Result<()>;
     /// Returns a shared ownership borrow of the channel. This uses reference
     /// counting and allows storing the channel as a long-lived value. This can
     /// be used to hand channels over to the Lua runtime.
@@ -524,13 +813,7 @@ impl<K: ChannelKey, V: ChannelValue> DynChannelGroup for ChannelGroup<K, V> {
         let mut result = BTreeMap::new();
         for (name, id) in self.channel_names.iter() {
             let ch = self.read_channel(*id).unwrap();
-            result.insert(
-                name.into(),
-                keys.iter()
-                    .map(|k| ch[K::from(*k)])
-                    .map(|x| x.introspect())
-                    .collect(),
-            );
+            result.insert(name.into(), ch.introspect_dyn(keys));
         }
         result
     }
@@ -563,6 +846,27 @@ impl<K: ChannelKey, V: ChannelValue> DynChannelGroup for ChannelGroup<K, V> {
     fn channel_id_dyn(&self, name: &str) -> Option<RawChannelId> {
         self.channel_names.get_by_left(name).map(|x| x.raw)
     }
+    fn rename_channel_dyn(&mut self, raw_id: RawChannelId, new_name: &str) -> Result<()> {
+        self.rename_channel(ChannelId::new(raw_id), new_name)
+    }
+    fn set_channel_dyn(&mut self, name: &str, channel: &dyn DynChannel) -> Result<()> {
+        let channel = channel
+            .as_any()
+            .downcast_ref::<Channel<K, V>>()
+            .ok_or_else(|| {
+                anyhow!(
+                    "Can't store a channel of {:?} {:?} as {:?} {:?}",
+                    channel.key_type_dyn(),
+                    channel.value_type_dyn(),
+                    K::key_type(),
+                    V::value_type()
+                )
+            })?
+            .clone();
+        let id = self.ensure_channel(name);
+        *self.write_channel(id)? = channel;
+        Ok(())
+    }
 
     fn channel_names(&self) -> Box<dyn Iterator<Item = &str> + '_> {
         Box::new(self.channel_names.iter().map(|(l, _)| l.as_str()))
@@ -616,6 +920,13 @@ impl MeshChannels {
         )
     }
 
+    /// Returns the names of all the channels with the given key and value type
+    pub fn channel_names<K: ChannelKey, V: ChannelValue>(&self) -> Vec<String> {
+        self.group::<K, V>()
+            .map(|group| group.channel_names().map(|name| name.to_string()).collect())
+            .unwrap_or_default()
+    }
+
     /// Calls `ensure_channel` for the channel group with key and value type
     pub fn ensure_channel<K: ChannelKey, V: ChannelValue>(
         &mut self,
@@ -632,6 +943,24 @@ impl MeshChannels {
         self.group_or_default().create_channel(name)
     }
 
+    /// Calls `duplicate_channel` for the channel group with key and value type
+    pub fn duplicate_channel<K: ChannelKey, V: ChannelValue>(
+        &mut self,
+        ch_id: ChannelId<K, V>,
+        new_name: &str,
+    ) -> Result<ChannelId<K, V>> {
+        self.group_mut()?.duplicate_channel(ch_id, new_name)
+    }
+
+    /// Calls `rename_channel` for the channel group with key and value type
+    pub fn rename_channel<K: ChannelKey, V: ChannelValue>(
+        &mut self,
+        ch_id: ChannelId<K, V>,
+        new_name: &str,
+    ) -> Result<()> {
+        self.group_mut()?.rename_channel(ch_id, new_name)
+    }
+
     /// Calls `remove_channel` for the channel group with key and value type
     pub fn remove_channel<K: ChannelKey, V: ChannelValue>(
         &mut self,
@@ -709,10 +1038,16 @@ impl MeshChannels {
         do_match! {
             VertexId, Vec3;
             VertexId, f32;
+            VertexId, i32;
+            VertexId, String;
             FaceId, Vec3;
             FaceId, f32;
+            FaceId, i32;
+            FaceId, String;
             HalfEdgeId, Vec3;
-            HalfEdgeId, f32
+            HalfEdgeId, f32;
+            HalfEdgeId, i32;
+            HalfEdgeId, String
         }
     }
 
@@ -728,6 +1063,38 @@ impl MeshChannels {
         group.ensure_channel_dyn(name)
     }
 
+    /// Renames the channel called `old_name` in a group with dynamic key and
+    /// value types given at runtime.
+    pub fn rename_channel_dyn(
+        &mut self,
+        kty: ChannelKeyType,
+        vty: ChannelValueType,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<()> {
+        let group = self
+            .channels
+            .get_mut(&(kty, vty))
+            .ok_or_else(|| anyhow!("Channel type does not exist"))?;
+        let raw_id = group
+            .channel_id_dyn(old_name)
+            .ok_or_else(|| anyhow!("Channel {old_name} does not exist"))?;
+        group.rename_channel_dyn(raw_id, new_name)
+    }
+
+    /// Calls `set_channel_dyn` for a group with dynamic key and value types
+    /// given at runtime.
+    pub fn set_channel_dyn(
+        &mut self,
+        kty: ChannelKeyType,
+        vty: ChannelValueType,
+        name: &str,
+        channel: &dyn DynChannel,
+    ) -> Result<()> {
+        self.ensure_group_dyn(kty, vty)
+            .set_channel_dyn(name, channel)
+    }
+
     /// Calls `read_channel` for a group with dynamic key and value
     /// types given at runtime.
     pub fn dyn_read_channel(
@@ -849,6 +1216,38 @@ impl MeshChannels {
             .collect()
     }
 
+    /// Returns the names of the channels with the given key and value types,
+    /// sorted alphabetically.
+    pub fn channel_names_dyn(&self, kty: ChannelKeyType, vty: ChannelValueType) -> Vec<String> {
+        let mut names = self
+            .channels
+            .get(&(kty, vty))
+            .map(|group| group.channel_names().map(String::from).collect::<Vec<_>>())
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    /// Same as [`MeshChannels::introspect`], but for a single channel and
+    /// only for up to `count` of the given `keys`, starting at `start`. This
+    /// avoids formatting all the values of a large mesh when only a few of
+    /// them are displayed.
+    pub fn introspect_range(
+        &self,
+        kty: ChannelKeyType,
+        vty: ChannelValueType,
+        name: &str,
+        keys: &[slotmap::KeyData],
+        start: usize,
+        count: usize,
+    ) -> Result<Vec<String>> {
+        let start = start.min(keys.len());
+        let end = start.saturating_add(count).min(keys.len());
+        Ok(self
+            .dyn_read_channel_by_name(kty, vty, name)?
+            .introspect_dyn(&keys[start..end]))
+    }
+
     pub fn merge_with(
         &mut self,
         other: &Self,
@@ -985,6 +1384,26 @@ mod test {
             ]
         );
 
+        // A range of a single channel can be introspected too
+        let keys = [v1.data(), v2.data(), v3.data()];
+        assert_eq!(
+            mesh_channels
+                .introspect_range(
+                    ChannelKeyType::VertexId,
+                    ChannelValueType::f32,
+                    "size",
+                    &keys,
+                    1,
+                    5
+                )
+                .unwrap(),
+            &[" 0.500", " 1.000"]
+        );
+        assert_eq!(
+            mesh_channels.channel_names_dyn(ChannelKeyType::VertexId, ChannelValueType::Vec3),
+            &["color", "position"]
+        );
+
         // Channels can also be read and written using a type-erased API. This
         // is mainly used for interfacing with Lua and looks very clunky here.
         // When programming in Rust, using the type-safe API is preferred
@@ -1011,6 +1430,214 @@ mod test {
             mesh_channels.ensure_channel::<VertexId, Vec3>("position")
         );
     }
+
+    #[test]
+    pub fn test_duplicate_channel() {
+        let mut vertices: slotmap::SlotMap<VertexId, ()> = slotmap::SlotMap::with_key();
+        let v1 = vertices.insert(());
+
+        let mut mesh_channels = MeshChannels::default();
+        let position = mesh_channels.ensure_channel::<VertexId, Vec3>("position");
+        mesh_channels.write_channel(position).unwrap()[v1] = Vec3::X;
+
+        let snapshot = mesh_channels
+            .duplicate_channel(position, "position_snapshot")
+            .unwrap();
+        mesh_channels.write_channel(position).unwrap()[v1] = Vec3::Y;
+
+        assert_eq!(mesh_channels.read_channel(snapshot).unwrap()[v1], Vec3::X);
+        assert_eq!(mesh_channels.read_channel(position).unwrap()[v1], Vec3::Y);
+        assert!(mesh_channels
+            .duplicate_channel(position, "position_snapshot")
+            .is_err());
+    }
+
+    #[test]
+    pub fn test_rename_channel() {
+        let mut vertices: slotmap::SlotMap<VertexId, ()> = slotmap::SlotMap::with_key();
+        let v1 = vertices.insert(());
+
+        let mut mesh_channels = MeshChannels::default();
+        let size = mesh_channels.ensure_channel::<VertexId, f32>("size");
+        mesh_channels.ensure_channel::<VertexId, f32>("weight");
+        mesh_channels.write_channel(size).unwrap()[v1] = 2.0;
+
+        mesh_channels
+            .rename_channel_dyn(
+                ChannelKeyType::VertexId,
+                ChannelValueType::f32,
+                "size",
+                "radius",
+            )
+            .unwrap();
+        assert!(mesh_channels.channel_id::<VertexId, f32>("size").is_none());
+        assert_eq!(
+            mesh_channels.channel_id::<VertexId, f32>("radius"),
+            Some(size)
+        );
+        assert_eq!(mesh_channels.read_channel(size).unwrap()[v1], 2.0);
+        assert!(mesh_channels.rename_channel(size, "weight").is_err());
+    }
+
+    #[test]
+    pub fn test_fill_and_clear_channel() {
+        let mut vertices: slotmap::SlotMap<VertexId, ()> = slotmap::SlotMap::with_key();
+        let v1 = vertices.insert(());
+        let v2 = vertices.insert(());
+
+        let mut channel = Channel::<VertexId, f32>::default();
+        channel.fill(vertices.keys(), 1.5);
+        assert_eq!(channel.iter().count(), 2);
+        assert_eq!(channel[v1], 1.5);
+        assert_eq!(channel[v2], 1.5);
+
+        channel.clear();
+        assert_eq!(channel.iter().count(), 0);
+        assert_eq!(channel[v1], 0.0);
+    }
+
+    #[test]
+    pub fn test_channel_arithmetic() {
+        let mut vertices: slotmap::SlotMap<VertexId, ()> = slotmap::SlotMap::with_key();
+        let v1 = vertices.insert(());
+        let v2 = vertices.insert(());
+
+        let mut a = Channel::<VertexId, f32>::default();
+        a[v1] = 1.0;
+        a[v2] = 2.0;
+        let mut b = Channel::<VertexId, f32>::default();
+        b[v1] = 10.0;
+
+        let sum = a
+            .binary_op_dyn(ChannelOp::Add, ChannelOperand::Channel(&b), false)
+            .unwrap();
+        let sum = sum.borrow();
+        let sum = sum
+            .as_any()
+            .downcast_ref::<Channel<VertexId, f32>>()
+            .unwrap();
+        assert_eq!(sum[v1], 11.0);
+        assert_eq!(sum[v2], 2.0);
+
+        let halved = a
+            .binary_op_dyn(ChannelOp::Div, ChannelOperand::Scalar(2.0), false)
+            .unwrap();
+        let halved = halved.borrow();
+        let halved = halved
+            .as_any()
+            .downcast_ref::<Channel<VertexId, f32>>()
+            .unwrap();
+        assert_eq!(halved[v2], 1.0);
+
+        let reversed = a
+            .binary_op_dyn(ChannelOp::Sub, ChannelOperand::Scalar(5.0), true)
+            .unwrap();
+        let reversed = reversed.borrow();
+        let reversed = reversed
+            .as_any()
+            .downcast_ref::<Channel<VertexId, f32>>()
+            .unwrap();
+        assert_eq!(reversed[v1], 4.0);
+
+        let vectors = Channel::<VertexId, Vec3>::default();
+        assert!(a
+            .binary_op_dyn(ChannelOp::Add, ChannelOperand::Channel(&vectors), false)
+            .is_err());
+        assert!(a
+            .binary_op_dyn(ChannelOp::Mul, ChannelOperand::Vector(Vec3::X), false)
+            .is_err());
+    }
+
+    #[test]
+    pub fn test_integer_channel_division() {
+        let mut vertices: slotmap::SlotMap<VertexId, ()> = slotmap::SlotMap::with_key();
+        let v1 = vertices.insert(());
+        let v2 = vertices.insert(());
+
+        let mut a = Channel::<VertexId, i32>::default();
+        a[v1] = 7;
+        a[v2] = -9;
+        let mut b = Channel::<VertexId, i32>::default();
+        b[v1] = 2;
+        b[v2] = 3;
+
+        let quotient = a
+            .binary_op_dyn(ChannelOp::Div, ChannelOperand::Channel(&b), false)
+            .unwrap();
+        let quotient = quotient.borrow();
+        let quotient = quotient
+            .as_any()
+            .downcast_ref::<Channel<VertexId, i32>>()
+            .unwrap();
+        assert_eq!(quotient[v1], 3);
+        assert_eq!(quotient[v2], -3);
+
+        let halved = a
+            .binary_op_dyn(ChannelOp::Div, ChannelOperand::Scalar(2.0), false)
+            .unwrap();
+        let halved = halved.borrow();
+        let halved = halved
+            .as_any()
+            .downcast_ref::<Channel<VertexId, i32>>()
+            .unwrap();
+        assert_eq!(halved[v1], 3);
+        assert_eq!(halved[v2], -4);
+
+        // Dividing an actual value by zero is still an error
+        assert!(a
+            .binary_op_dyn(ChannelOp::Div, ChannelOperand::Scalar(0.0), false)
+            .is_err());
+        b[v2] = 0;
+        assert!(a
+            .binary_op_dyn(ChannelOp::Div, ChannelOperand::Channel(&b), false)
+            .is_err());
+    }
+
+    #[test]
+    pub fn test_string_channels() {
+        let mut faces: slotmap::SlotMap<FaceId, ()> = slotmap::SlotMap::with_key();
+        let f1 = faces.insert(());
+        let f2 = faces.insert(());
+
+        let mut mesh_channels = MeshChannels::default();
+        let material = mesh_channels.ensure_channel::<FaceId, String>("material");
+        mesh_channels.write_channel(material).unwrap()[f1] = "wood".to_string();
+        assert_eq!(mesh_channels.read_channel(material).unwrap()[f1], "wood");
+        assert_eq!(mesh_channels.read_channel(material).unwrap()[f2], "");
+
+        let copy = mesh_channels
+            .duplicate_channel(material, "material_copy")
+            .unwrap();
+        assert_eq!(mesh_channels.read_channel(copy).unwrap()[f1], "wood");
+
+        let lua = Lua::new();
+        let dyn_material = mesh_channels
+            .dyn_read_channel_by_name(ChannelKeyType::FaceId, ChannelValueType::String, "material")
+            .unwrap();
+        match dyn_material.get_lua(&lua, f1.cast_to_lua(&lua)).unwrap() {
+            mlua::Value::String(s) if s.to_str().unwrap() == "wood" => {}
+            _ => panic!("Expected the string 'wood'"),
+        }
+    }
+
+    #[test]
+    pub fn test_integer_channels() {
+        let mut faces: slotmap::SlotMap<FaceId, ()> = slotmap::SlotMap::with_key();
+        let f1 = faces.insert(());
+
+        let mut mesh_channels = MeshChannels::default();
+        let material = mesh_channels.ensure_channel::<FaceId, i32>("material");
+        mesh_channels.write_channel(material).unwrap()[f1] = 3;
+
+        let lua = Lua::new();
+        let dyn_material = mesh_channels
+            .dyn_read_channel_by_name(ChannelKeyType::FaceId, ChannelValueType::i32, "material")
+            .unwrap();
+        match dyn_material.get_lua(&lua, f1.cast_to_lua(&lua)).unwrap() {
+            mlua::Value::Integer(3) => {}
+            _ => panic!("Expected the integer 3"),
+        }
+    }
 }
 
 // ------------- Boilerplate zone ------------
@@ -1,5 +1,6 @@
 use atomic_float::AtomicF32;
 use nonmax::NonMaxU32;
+use std::ops::{Add, Mul};
 use std::sync::atomic::Ordering;
 
 use crate::prelude::*;
@@ -35,9 +36,24 @@ pub struct CompactMesh<const Subdivided: bool> {
     pub edge: Vec<u32>,
     pub face: Vec<u32>,
     pub vertex_positions: Vec<Vec3>,
+    /// The crease weight of each edge, in the [0, 1] range, or empty when the
+    /// mesh has no creases. See [`CREASE_CHANNEL`].
+    pub crease: Vec<f32>,
+    /// Vertex channels that get linearly interpolated during subdivision. The
+    /// position channel is not included, since it follows its own rules.
+    pub vertex_channels_vec3: Vec<(String, Vec<Vec3>)>,
+    pub vertex_channels_f32: Vec<(String, Vec<f32>)>,
+    /// For each face, the index of the face it was subdivided from in the
+    /// original mesh. See [`CompactMesh::to_halfedge_from_source`].
+    pub face_origin: Vec<u32>,
     pub counts: MeshCounts,
 }
 
+/// The name of the halfedge channel storing edge crease weights. During Catmull
+/// Clark subdivision, a weight of 1.0 keeps the edge perfectly sharp, while 0.0
+/// smooths it as usual. An edge is creased if any of its halfedges is.
+pub const CREASE_CHANNEL: &str = "crease";
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct MeshCounts {
     /// The number of vertices
@@ -68,6 +84,14 @@ impl MeshCounts {
             num_edges: 2 * e_0 + h_0,
         }
     }
+
+    /// Returns an error if any of the counts exceeds the [`element_limit`].
+    pub fn check_limits(&self) -> Result<()> {
+        check_element_limit(self.num_vertices, "vertices")?;
+        check_element_limit(self.num_halfedges, "halfedges")?;
+        check_element_limit(self.num_faces, "faces")?;
+        Ok(())
+    }
 }
 
 #[allow(non_upper_case_globals)]
@@ -171,6 +195,30 @@ impl<const Subdivided: bool> CompactMesh<Subdivided> {
             .map(|(v_id, _)| positions[v_id])
             .collect();
 
+        let vertex_ids = v_id_to_idx.iter().map(|(v_id, _)| v_id).collect_vec();
+        let vertex_channels_vec3 = read_vertex_channels::<Vec3>(mesh, &vertex_ids)?
+            .into_iter()
+            .filter(|(name, _)| name != "position")
+            .collect();
+        let vertex_channels_f32 = read_vertex_channels::<f32>(mesh, &vertex_ids)?;
+
+        let mut crease = vec![];
+        if let Ok(crease_ch) = mesh
+            .channels
+            .read_channel_by_name::<HalfEdgeId, f32>(CREASE_CHANNEL)
+        {
+            crease = vec![0.0; edge_id_counter as usize];
+            for (h_id, _) in h_id_to_idx.iter() {
+                let weight = crease_ch[h_id].clamp(0.0, 1.0);
+                let e = h_id_to_edge[h_id] as usize;
+                crease[e] = crease[e].max(weight);
+                // Boundary halfedges have no edge id, so read their weight here
+                if let Some(twin) = conn[h_id].twin {
+                    crease[e] = crease[e].max(crease_ch[twin].clamp(0.0, 1.0));
+                }
+            }
+        }
+
         Ok(CompactMesh {
             twin,
             next,
@@ -179,6 +227,10 @@ impl<const Subdivided: bool> CompactMesh<Subdivided> {
             edge,
             face,
             vertex_positions,
+            crease,
+            vertex_channels_vec3,
+            vertex_channels_f32,
+            face_origin: (0..num_faces as u32).collect(),
             counts: MeshCounts {
                 num_halfedges,
                 num_vertices,
@@ -192,7 +244,55 @@ impl<const Subdivided: bool> CompactMesh<Subdivided> {
 
     #[profiling::function]
     pub fn to_halfedge(&self) -> HalfEdgeMesh {
-        let mesh = HalfEdgeMesh::new();
+        self.to_halfedge_with_ids().0
+    }
+
+    /// Same as [`CompactMesh::to_halfedge`], but also carries over the
+    /// channels of `source`, the mesh this compact mesh was created from, that
+    /// can't be interpolated. Each face takes the values of the face it was
+    /// subdivided from, and the original vertices keep their values. Halfedge
+    /// channels are only defined in the result, since their values can't be
+    /// mapped to the new halfedges.
+    pub fn to_halfedge_from_source(&self, source: &HalfEdgeMesh) -> Result<HalfEdgeMesh> {
+        let (mut mesh, v_idx_to_id, f_idx_to_id) = self.to_halfedge_with_ids();
+
+        let (src_vertices, src_faces) = {
+            let conn = source.read_connectivity();
+            (
+                conn.iter_vertices().map(|(v, _)| v).collect_vec(),
+                conn.iter_faces().map(|(f, _)| f).collect_vec(),
+            )
+        };
+        let vertex_pairs = src_vertices
+            .iter()
+            .copied()
+            .zip(v_idx_to_id.iter().copied())
+            .collect_vec();
+        let face_pairs = f_idx_to_id
+            .iter()
+            .zip(self.face_origin.iter())
+            .map(|(f, origin)| (src_faces[*origin as usize], *f))
+            .collect_vec();
+
+        let src = &source.channels;
+        let dst = &mut mesh.channels;
+        copy_channels::<VertexId, i32>(src, dst, &vertex_pairs)?;
+        copy_channels::<VertexId, String>(src, dst, &vertex_pairs)?;
+        copy_channels::<FaceId, Vec3>(src, dst, &face_pairs)?;
+        copy_channels::<FaceId, f32>(src, dst, &face_pairs)?;
+        copy_channels::<FaceId, i32>(src, dst, &face_pairs)?;
+        copy_channels::<FaceId, String>(src, dst, &face_pairs)?;
+        copy_channels::<HalfEdgeId, Vec3>(src, dst, &[])?;
+        copy_channels::<HalfEdgeId, f32>(src, dst, &[])?;
+        copy_channels::<HalfEdgeId, i32>(src, dst, &[])?;
+        copy_channels::<HalfEdgeId, String>(src, dst, &[])?;
+        Ok(mesh)
+    }
+
+    /// Converts this mesh to a [`HalfEdgeMesh`]. Also returns the ids of the
+    /// new vertices and faces, in the same order as the compact indices.
+    fn to_halfedge_with_ids(&self) -> (HalfEdgeMesh, Vec<VertexId>, Vec<FaceId>) {
+        let mut mesh = HalfEdgeMesh::new();
         let mut conn = mesh.write_connectivity();
         let mut positions = mesh.write_positions();
 
@@ -242,7 +342,53 @@ impl<const Subdivided: bool> CompactMesh<Subdivided> {
 
         drop(conn);
         drop(positions);
-        mesh
+
+        if !self.crease.is_empty() {
+            let crease_ch = mesh
+                .channels
+                .ensure_channel::<HalfEdgeId, f32>(CREASE_CHANNEL);
+            let mut crease_ch = mesh
+                .channels
+                .write_channel(crease_ch)
+                .expect("Channel was just created");
+            for (h, h_id) in h_idx_to_id.iter().enumerate() {
+                let weight = self.crease[self.edge[h] as usize];
+                if weight > 0.0 {
+                    crease_ch[*h_id] = weight;
+                }
+            }
+        }
+        write_vertex_channels(&mut mesh, &self.vertex_channels_vec3, &v_idx_to_id);
+        write_vertex_channels(&mut mesh, &self.vertex_channels_f32, &v_idx_to_id);
+
+        (mesh, v_idx_to_id, f_idx_to_id)
+    }
+
+    /// Computes the values of a vertex channel after one iteration of
+    /// subdivision. Original vertices keep their value, and the new face and
+    /// edge points take the average of the vertices of their face or edge.
+    fn subdivide_vertex_channel<V>(&self, values: &[V]) -> Vec<V>
+    where
+        V: Copy + Default + Add<Output = V> + Mul<f32, Output = V>,
+    {
+        let v_d = self.counts.num_vertices;
+        let f_d = self.counts.num_faces;
+        let mut new_values = values.to_vec();
+        new_values.resize(v_d + f_d + self.counts.num_edges, V::default());
+
+        let mut face_counts = vec![0u32; f_d];
+        for h in 0..self.counts.num_halfedges {
+            let v = self.vert[h] as usize;
+            let v_end = self.vert[self.get_next(h)] as usize;
+            let f = self.get_face(h);
+            new_values[v_d + f] = new_values[v_d + f] + values[v];
+            face_counts[f] += 1;
+            new_values[v_d + f_d + self.edge[h] as usize] = (values[v] + values[v_end]) * 0.5;
+        }
+        for (f, count) in face_counts.iter().enumerate() {
+            new_values[v_d + f] = new_values[v_d + f] * (1.0 / *count as f32);
+        }
+        new_values
     }
 
     /// Generates the twin pointer for the 4 halfedges spawning from `h` during
@@ -489,9 +635,42 @@ impl<const Subdivided: bool> CompactMesh<Subdivided> {
             });
 
         // SAFETY: Same as above, Vec3 and AtomicVec3 have the same memory layout
-        let new_vertex_positions =
+        let mut new_vertex_positions =
             unsafe { transmute_vec::<AtomicVec3, Vec3>(new_vertex_positions) };
 
+        // --- Creases ---
+        let mut new_crease = vec![];
+        if !self.crease.is_empty() {
+            if catmull_clark {
+                self.apply_creases(&valences, &mut new_vertex_positions);
+            }
+            // The two halves of a split edge inherit its weight. The new edges
+            // inside each face are never creased.
+            new_crease = vec![0.0; new_counts.num_edges];
+            new_crease
+                .par_chunks_mut(2)
+                .zip(self.crease.par_iter())
+                .for_each(|(halves, weight)| halves.fill(*weight));
+        }
+
+        // Channels are independent from each other, so each one is
+        // subdivided in its own task.
+        let vertex_channels_vec3 = self
+            .vertex_channels_vec3
+            .par_iter()
+            .map(|(name, values)| (name.clone(), self.subdivide_vertex_channel(values)))
+            .collect();
+        let vertex_channels_f32 = self
+            .vertex_channels_f32
+            .par_iter()
+            .map(|(name, values)| (name.clone(), self.subdivide_vertex_channel(values)))
+            .collect();
+        // Each halfedge of the old mesh becomes a face of the new one
+        let face_origin = (0..self.counts.num_halfedges)
+            .into_par_iter()
+            .map(|h| self.face_origin[self.get_face(h)])
+            .collect();
+
         CompactMesh {
             twin: new_twin,
             // NOTE: Empty vecs represent analytically computed properties
@@ -501,20 +680,166 @@ impl<const Subdivided: bool> CompactMesh<Subdivided> {
             edge: new_edge,
             face: vec![],
             vertex_positions: new_vertex_positions,
+            crease: new_crease,
+            vertex_channels_vec3,
+            vertex_channels_f32,
+            face_origin,
             counts: new_counts,
         }
     }
 
+    /// Corrects the smooth Catmull Clark vertex and edge points stored in
+    /// `new_vertex_positions` to account for creased edges. Each point is
+    /// interpolated between its smooth position and its sharp position using
+    /// the crease weight. Vertices with two creased edges follow the crease,
+    /// and vertices with more are kept as corners. Boundary vertices are
+    /// already kept in place by the regular rules.
+    fn apply_creases(&self, valences: &[Option<NonMaxU32>], new_vertex_positions: &mut [Vec3]) {
+        let num_vertices = self.counts.num_vertices;
+        let edge_points_start = num_vertices + self.counts.num_faces;
+
+        // For each vertex: creased edge count, sum of weights, and sum of the
+        // midpoints of its creased edges.
+        let mut crease_count = vec![0u32; num_vertices];
+        let mut weight_sum = vec![0.0f32; num_vertices];
+        let mut midpoint_sum = vec![Vec3::ZERO; num_vertices];
+        let mut on_boundary = vec![false; num_vertices];
+
+        for h in 0..self.counts.num_halfedges {
+            let v = self.vert[h] as usize;
+            let v_end = self.vert[self.get_next(h)] as usize;
+            let weight = self.crease[self.edge[h] as usize];
+            let midpoint = (self.vertex_positions[v] + self.vertex_positions[v_end]) / 2.0;
+
+            if valences[h].is_none() {
+                on_boundary[v] = true;
+            }
+            // NOTE: Each interior edge is visited once from each of its
+            // endpoints, through its two halfedges.
+            if weight > 0.0 {
+                crease_count[v] += 1;
+                weight_sum[v] += weight;
+                midpoint_sum[v] += midpoint;
+
+                // Edge point, skipped for boundary edges which are already sharp
+                if let Some(twin) = self.twin[h] {
+                    if (h as u32) < twin.get() {
+                        let j = edge_points_start + self.edge[h] as usize;
+                        new_vertex_positions[j] = new_vertex_positions[j].lerp(midpoint, weight);
+                    }
+                }
+            }
+        }
+
+        for v in 0..num_vertices {
+            if on_boundary[v] || crease_count[v] < 2 {
+                continue;
+            }
+            let pos = self.vertex_positions[v];
+            let sharp = if crease_count[v] == 2 {
+                // Crease rule: (a + 6v + b) / 8, with a, b the crease neighbors
+                midpoint_sum[v] / 4.0 + pos / 2.0
+            } else {
+                pos
+            };
+            let weight = weight_sum[v] / crease_count[v] as f32;
+            new_vertex_positions[v] = new_vertex_positions[v].lerp(sharp, weight);
+        }
+    }
+
     #[profiling::function]
-    pub fn subdivide_multi(&self, iterations: usize, catmull_clark: bool) -> CompactMesh<true> {
+    pub fn subdivide_multi(
+        &self,
+        iterations: usize,
+        catmull_clark: bool,
+    ) -> Result<CompactMesh<true>> {
+        self.subdivide_multi_with_progress(iterations, catmull_clark, &Progress::new())
+    }
+
+    /// Same as [`CompactMesh::subdivide_multi`], but reports progress after
+    /// every iteration and stops with an error when the given `progress`
+    /// handle is cancelled.
+    pub fn subdivide_multi_with_progress(
+        &self,
+        iterations: usize,
+        catmull_clark: bool,
+        progress: &Progress,
+    ) -> Result<CompactMesh<true>> {
+        // Check the final size before doing any work. The counts grow
+        // exponentially, so a high number of iterations quickly becomes
+        // impossible to allocate.
+        let mut counts = self.counts;
+        for _ in 0..iterations {
+            counts = counts.subdiv();
+            counts.check_limits()?;
+        }
+
+        progress.check_cancelled()?;
         let mut mesh = self.subdivide(catmull_clark);
-        for _ in 0..(iterations - 1) {
+        progress.set_fraction(1.0 / iterations as f32);
+        for i in 1..iterations {
+            progress.check_cancelled()?;
             mesh = mesh.subdivide(catmull_clark);
+            progress.set_fraction((i + 1) as f32 / iterations as f32);
         }
-        mesh
+        Ok(mesh)
     }
 }
 
+/// Reads all the vertex channels with value type `V`, with the values sorted
+/// in the same order as `vertices`.
+fn read_vertex_channels<V: ChannelValue>(
+    mesh: &HalfEdgeMesh,
+    vertices: &[VertexId],
+) -> Result<Vec<(String, Vec<V>)>> {
+    mesh.channels
+        .channel_names::<VertexId, V>()
+        .into_iter()
+        .map(|name| {
+            let ch = mesh.channels.read_channel_by_name::<VertexId, V>(&name)?;
+            let values = vertices.iter().map(|v| ch[*v].clone()).collect();
+            Ok((name, values))
+        })
+        .collect()
+}
+
+/// Writes the given vertex channels into `mesh`. `vertices` contains the id of
+/// the vertex for each value.
+fn write_vertex_channels<V: ChannelValue>(
+    mesh: &mut HalfEdgeMesh,
+    channels: &[(String, Vec<V>)],
+    vertices: &[VertexId],
+) {
+    for (name, values) in channels {
+        let ch_id = mesh.channels.ensure_channel::<VertexId, V>(name);
+        let mut ch = mesh
+            .channels
+            .write_channel(ch_id)
+            .expect("Channel was just created");
+        for (v, value) in vertices.iter().zip(values) {
+            ch[*v] = value.clone();
+        }
+    }
+}
+
+/// Copies all the channels with key `K` and value `V` from `src` to `dst`.
+/// Each pair in `keys` maps a key in `src` to a key in `dst`.
+fn copy_channels<K: ChannelKey, V: ChannelValue>(
+    src: &MeshChannels,
+    dst: &mut MeshChannels,
+    keys: &[(K, K)],
+) -> Result<()> {
+    for name in src.channel_names::<K, V>() {
+        let src_ch = src.read_channel_by_name::<K, V>(&name)?;
+        let dst_id = dst.ensure_channel::<K, V>(&name);
+        let mut dst_ch = dst.write_channel(dst_id)?;
+        for (src_key, dst_key) in keys {
+            dst_ch[*dst_key] = src_ch[*src_key].clone();
+        }
+    }
+    Ok(())
+}
+
 /// A counterpart to `glam::Vec3` with atomics in its `x`, `y`, `z` fields.
 #[repr(C)]
 struct AtomicVec3 {
@@ -656,4 +981,117 @@ pub mod test {
             ]
         );
     }
+
+    #[test]
+    pub fn subdivide_element_limit_test() {
+        let cube = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let compact = CompactMesh::<false>::from_halfedge(&cube).unwrap();
+        // 20 iterations would create trillions of faces
+        assert!(compact.subdivide_multi(20, true).is_err());
+    }
+
+    #[test]
+    pub fn subdivide_progress_test() {
+        let cube = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let compact = CompactMesh::<false>::from_halfedge(&cube).unwrap();
+
+        let progress = Progress::new();
+        assert!(compact
+            .subdivide_multi_with_progress(2, true, &progress)
+            .is_ok());
+        assert_eq!(progress.fraction(), 1.0);
+
+        progress.reset();
+        assert_eq!(progress.fraction(), 0.0);
+        progress.cancel();
+        assert!(compact
+            .subdivide_multi_with_progress(2, true, &progress)
+            .is_err());
+        assert_eq!(progress.fraction(), 0.0);
+    }
+
+    #[test]
+    pub fn subdivide_channels_test() {
+        let mut quad = primitives::Quad::build(Vec3::ZERO, Vec3::Y, Vec3::X, Vec2::ONE);
+        let uv_ch = quad.channels.ensure_channel::<VertexId, Vec3>("uv");
+        let id_ch = quad.channels.ensure_channel::<FaceId, i32>("id");
+        {
+            let conn = quad.read_connectivity();
+            let positions = quad.read_positions();
+            let mut uv_ch = quad.channels.write_channel(uv_ch).unwrap();
+            for (v, _) in conn.iter_vertices() {
+                uv_ch[v] = positions[v] + Vec3::ONE;
+            }
+            let mut id_ch = quad.channels.write_channel(id_ch).unwrap();
+            for (f, _) in conn.iter_faces() {
+                id_ch[f] = 42;
+            }
+        }
+
+        // With linear subdivision, the interpolated uvs must follow positions
+        let result = CompactMesh::<false>::from_halfedge(&quad)
+            .unwrap()
+            .subdivide_multi(2, false)
+            .unwrap()
+            .to_halfedge_from_source(&quad)
+            .unwrap();
+        let conn = result.read_connectivity();
+        let positions = result.read_positions();
+        let uv_ch = result
+            .channels
+            .read_channel_by_name::<VertexId, Vec3>("uv")
+            .unwrap();
+        for (v, _) in conn.iter_vertices() {
+            assert!(uv_ch[v].distance(positions[v] + Vec3::ONE) < 1e-5);
+        }
+        let id_ch = result
+            .channels
+            .read_channel_by_name::<FaceId, i32>("id")
+            .unwrap();
+        assert_eq!(conn.num_faces(), 16);
+        assert!(conn.iter_faces().all(|(f, _)| id_ch[f] == 42));
+    }
+
+    #[test]
+    pub fn subdivide_crease_test() {
+        // The smallest distance from a vertex to the cube's center, measured along
+        // the axis where the vertex is farthest from it
+        let min_extent = |mesh: &HalfEdgeMesh| {
+            let positions = mesh.read_positions();
+            mesh.read_connectivity()
+                .iter_vertices()
+                .map(|(v, _)| positions[v].abs().max_element())
+                .fold(f32::INFINITY, f32::min)
+        };
+
+        let mut cube = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let smooth = CompactMesh::<false>::from_halfedge(&cube)
+            .unwrap()
+            .subdivide_multi(2, true)
+            .unwrap()
+            .to_halfedge();
+        assert!(min_extent(&smooth) < 0.49);
+
+        // With all edges fully creased, every vertex stays on the cube's surface
+        let crease_ch = cube
+            .channels
+            .ensure_channel::<HalfEdgeId, f32>(CREASE_CHANNEL);
+        {
+            let conn = cube.read_connectivity();
+            let mut crease_ch = cube.channels.write_channel(crease_ch).unwrap();
+            for (h, _) in conn.iter_halfedges() {
+                crease_ch[h] = 1.0;
+            }
+        }
+        let sharp = CompactMesh::<false>::from_halfedge(&cube)
+            .unwrap()
+            .subdivide_multi(2, true)
+            .unwrap()
+            .to_halfedge();
+        assert!((min_extent(&sharp) - 0.5).abs() < 1e-5);
+        assert!(sharp
+            .channels
+            .read_channel_by_name::<HalfEdgeId, f32>(CREASE_CHANNEL)
+            .is_ok());
+    }
 }
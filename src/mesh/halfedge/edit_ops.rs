@@ -8,6 +8,10 @@ use crate::prelude::*;
 /// Just a place where commented-out code goes to die
 pub mod deprecated;
 
+/// Convex hull computation for point clouds
+pub mod convex_hull;
+pub use convex_hull::convex_hull;
+
 /// Removes `h_l` and its twin `h_r`, merging their respective faces together.
 /// The face on the L side will be kept, and the R side removed. Both sides of
 /// the edge that will be dissolved need to be on a face. Boundary halfedges are
@@ -124,15 +128,13 @@ pub fn cut_face(
     v: VertexId,
     w: VertexId,
 ) -> Result<HalfEdgeId> {
+    // Boundary halfedges around v have no face, and are skipped
     let face = mesh
         .at_vertex(v)
         .outgoing_halfedges()?
         .iter()
-        .map(|h| mesh.at_halfedge(*h).face().try_end())
-        .collect::<Result<SVec<FaceId>, TraversalError>>()?
-        .iter()
-        .find(|f| mesh.face_vertices(**f).contains(&w))
-        .cloned()
+        .filter_map(|h| mesh.at_halfedge(*h).face().try_end().ok())
+        .find(|f| mesh.face_vertices(*f).contains(&w))
         .ok_or_else(|| anyhow!("cut_face: v and w must share a face"))?;
 
     if mesh.at_vertex(v).halfedge_to(w).try_end().is_ok() {
@@ -256,6 +258,213 @@ pub fn dissolve_vertex(mesh: &mut halfedge::MeshConnectivity, v: VertexId) -> Re
     Ok(new_face)
 }
 
+/// Removes a vertex with exactly two edges, joining them into a single edge.
+/// Unlike [`dissolve_vertex`], the faces on both sides are kept.
+fn join_edges_at_vertex(mesh: &mut MeshConnectivity, v: VertexId) -> Result<()> {
+    let outgoing = mesh.at_vertex(v).outgoing_halfedges()?;
+    let (h1, h2) = match outgoing.as_slice() {
+        [h1, h2] => (*h1, *h2),
+        _ => bail!("Vertex {:?} does not have exactly two edges", v),
+    };
+    let t1 = mesh.at_halfedge(h1).twin().try_end()?;
+    let t2 = mesh.at_halfedge(h2).twin().try_end()?;
+
+    // Going through v, t1 is followed by h2 on one side, and t2 by h1 on the
+    // other. The incoming halfedges are kept, and become twins.
+    for h_out in [h1, h2] {
+        if mesh[h_out].face.is_some() && mesh.halfedge_loop(h_out).len() <= 3 {
+            bail!(
+                "Dissolving vertex {:?} would leave a face with less than three sides",
+                v
+            );
+        }
+    }
+    for (h_in, h_out, twin) in [(t1, h2, t2), (t2, h1, t1)] {
+        let nxt = mesh.at_halfedge(h_out).next().try_end()?;
+        mesh[h_in].next = Some(nxt);
+        mesh[h_in].twin = Some(twin);
+        if let Some(f) = mesh[h_out].face {
+            if mesh[f].halfedge == Some(h_out) {
+                mesh[f].halfedge = Some(h_in);
+            }
+        }
+    }
+
+    mesh.remove_vertex(v);
+    mesh.remove_halfedge(h1);
+    mesh.remove_halfedge(h2);
+    Ok(())
+}
+
+/// Removes the given vertices, merging the faces around each of them into a
+/// single face. Vertices with exactly two edges are removed by joining their
+/// edges instead, so the faces around them are kept.
+pub fn dissolve_vertices(mesh: &mut MeshConnectivity, verts: &[VertexId]) -> Result<()> {
+    for &v in verts {
+        if mesh.at_vertex(v).outgoing_halfedges()?.len() == 2 {
+            join_edges_at_vertex(mesh, v)?;
+        } else {
+            dissolve_vertex(mesh, v)?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes an edge that sticks into a face, ending at a vertex that has no
+/// other edges. The edge is given by either of its halfedges. The loose
+/// vertex is removed too.
+fn remove_dangling_edge(mesh: &mut MeshConnectivity, h: HalfEdgeId) -> Result<()> {
+    let twin = mesh.at_halfedge(h).twin().try_end()?;
+    // h_in goes into the loose vertex, and h_out comes back from it
+    let (h_in, h_out) = if mesh[h].next == Some(twin) {
+        (h, twin)
+    } else {
+        (twin, h)
+    };
+    let (v, w) = mesh.at_halfedge(h_in).src_dst_pair()?;
+    let prv = mesh.at_halfedge(h_in).previous().try_end()?;
+    let nxt = mesh.at_halfedge(h_out).next().try_end()?;
+
+    mesh[prv].next = Some(nxt);
+    if let Some(f) = mesh[h_in].face {
+        if mesh[f].halfedge == Some(h_in) || mesh[f].halfedge == Some(h_out) {
+            mesh[f].halfedge = Some(nxt);
+        }
+    }
+    if mesh[v].halfedge == Some(h_in) {
+        mesh[v].halfedge = Some(nxt);
+    }
+
+    mesh.remove_vertex(w);
+    mesh.remove_halfedge(h_in);
+    mesh.remove_halfedge(h_out);
+    Ok(())
+}
+
+/// Removes the given edges, merging the faces on both sides of each edge into
+/// a single face. Either halfedge of an edge may be given, or both. Edges
+/// left sticking into a face by the previous removals, like the last edge
+/// around a vertex whose other edges were all dissolved, are removed along
+/// with their loose vertex. Boundary edges cannot be dissolved.
+pub fn dissolve_edges(mesh: &mut MeshConnectivity, halfedges: &[HalfEdgeId]) -> Result<()> {
+    for &h in halfedges {
+        // Skip edges that were already removed through their twin
+        if mesh.halfedge(h).is_none() {
+            continue;
+        }
+        let twin = mesh.at_halfedge(h).twin().try_end()?;
+        let f_l = mesh.at_halfedge(h).face().try_end()?;
+        let f_r = mesh.at_halfedge(twin).face().try_end()?;
+        if f_l != f_r {
+            dissolve_edge(mesh, h)?;
+        } else if mesh[h].next == Some(twin) || mesh[twin].next == Some(h) {
+            remove_dangling_edge(mesh, h)?;
+        } else {
+            bail!(
+                "Edge {:?} has the same face on both sides. Cannot dissolve",
+                h
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Removes the edges among `halfedges` that have no face on either side, and
+/// the vertices that are left without any edges.
+fn remove_loose_edges(mesh: &mut MeshConnectivity, halfedges: &[HalfEdgeId]) -> Result<()> {
+    for &h in halfedges {
+        // Skip edges that were already removed through their twin
+        if mesh.halfedge(h).is_none() {
+            continue;
+        }
+        let t = mesh.at_halfedge(h).twin().try_end()?;
+        if mesh[h].face.is_some() || mesh[t].face.is_some() {
+            continue;
+        }
+        let (v, w) = mesh.at_halfedge(h).src_dst_pair()?;
+        let h_prv = mesh.at_halfedge(h).previous().try_end()?;
+        let t_prv = mesh.at_halfedge(t).previous().try_end()?;
+        let h_nxt = mesh.at_halfedge(h).next().try_end()?;
+        let t_nxt = mesh.at_halfedge(t).next().try_end()?;
+
+        // When `t` is followed by `h`, the edge is the only one at `v`, and
+        // the vertex goes away with it. Same for `h`, `t` and `w`.
+        if t_nxt == h {
+            mesh.remove_vertex(v);
+        } else {
+            mesh[h_prv].next = Some(t_nxt);
+            if mesh[v].halfedge == Some(h) {
+                mesh[v].halfedge = Some(t_nxt);
+            }
+        }
+        if h_nxt == t {
+            mesh.remove_vertex(w);
+        } else {
+            mesh[t_prv].next = Some(h_nxt);
+            if mesh[w].halfedge == Some(t) {
+                mesh[w].halfedge = Some(h_nxt);
+            }
+        }
+
+        mesh.remove_halfedge(h);
+        mesh.remove_halfedge(t);
+    }
+    Ok(())
+}
+
+/// Removes the given faces, leaving a hole in their place. When
+/// `keep_boundary` is set, only the faces are removed, and their edges are
+/// kept as boundary or loose edges. Otherwise, the edges and vertices that
+/// are no longer part of any face are removed too.
+pub fn delete_faces(
+    mesh: &mut MeshConnectivity,
+    faces: &[FaceId],
+    keep_boundary: bool,
+) -> Result<()> {
+    let mut halfedges = vec![];
+    for &f in faces {
+        if mesh.face(f).is_none() {
+            continue;
+        }
+        for h in mesh.face_edges(f) {
+            mesh[h].face = None;
+            halfedges.push(h);
+        }
+        mesh.remove_face(f);
+    }
+    if !keep_boundary {
+        remove_loose_edges(mesh, &halfedges)?;
+    }
+    Ok(())
+}
+
+/// Removes the given vertices, along with all the edges and faces around
+/// them. Edges and vertices that are no longer part of any face are removed
+/// too.
+pub fn delete_vertices(mesh: &mut MeshConnectivity, verts: &[VertexId]) -> Result<()> {
+    let mut faces = vec![];
+    let mut halfedges = vec![];
+    for &v in verts {
+        if !mesh.vertex_exists(v) {
+            continue;
+        }
+        for h in mesh.at_vertex(v).outgoing_halfedges()? {
+            faces.extend(mesh[h].face);
+            halfedges.push(h);
+        }
+    }
+    delete_faces(mesh, &faces, false)?;
+    // Edges around the vertices that had no faces to begin with
+    remove_loose_edges(mesh, &halfedges)?;
+    // Vertices that had no edges at all are still there
+    for &v in verts {
+        if mesh.vertex_exists(v) {
+            mesh.remove_vertex(v);
+        }
+    }
+    Ok(())
+}
+
 /// Chamfers a vertex. That is, for each outgoing edge of the vertex, a new
 /// vertex will be created. All the new vertices will be joined in a new face,
 /// and the original vertex will get removed.
@@ -460,12 +669,18 @@ fn bevel_edges_connectivity(
     Ok(edges_to_bevel)
 }
 
-/// Bevels the given vertices by a given distance amount
+/// Bevels the given edges by a given distance amount. The bevel is made of
+/// `segments` strips of faces, shaped by `profile`. See [`bevel_profile`].
+///
+/// Only the strips along the beveled edges get subdivided. Where three or
+/// more beveled edges meet, the corner is left as a single n-gon.
 pub fn bevel_edges(
     mesh: &mut MeshConnectivity,
     positions: &mut Positions,
     halfedges: &[HalfEdgeId],
     amount: f32,
+    segments: u32,
+    profile: f32,
 ) -> Result<()> {
     let beveled_edges = bevel_edges_connectivity(mesh, positions, halfedges)?;
 
@@ -477,7 +692,7 @@ pub fn bevel_edges(
     // depending on their location of the halfedge (head, tail resp.). The final
     // move direction of a vertice is the sum of all its pulls.
     let mut move_ops = HashMap::<VertexId, HashSet<Vec3Ord>>::new();
-    for h in beveled_edges {
+    for &h in &beveled_edges {
         mesh.add_debug_halfedge(h, DebugMark::green("bvl"));
 
         let (v, w) = mesh.at_halfedge(h).src_dst_pair()?;
@@ -493,8 +708,11 @@ pub fn bevel_edges(
         wdir.insert(w_to_pos.to_ord());
     }
 
+    // Before moving, every vertex still sits at the corner it was split from
+    let mut corners = HashMap::new();
     for (v, v_pulls) in move_ops {
         let v_pos = positions[v];
+        corners.insert(v, v_pos);
         for v_pull in v_pulls {
             let pull_to = v_pull.to_vec();
             let dir = (pull_to - v_pos).normalize();
@@ -502,9 +720,96 @@ pub fn bevel_edges(
         }
     }
 
+    if segments <= 1 {
+        return Ok(());
+    }
+
+    // --- Subdivide the bevel strips ---
+
+    // The twins of the beveled edges run along the sides of the new faces.
+    // Consecutive quads along a beveled edge form a strip, which gets loop cut
+    // starting from the rails on one of its sides.
+    let rails = beveled_edges
+        .iter()
+        .map(|h| mesh.at_halfedge(*h).twin().try_end())
+        .collect::<Result<BTreeSet<_>, _>>()?;
+    let is_strip_rail = |mesh: &MeshConnectivity, h: HalfEdgeId| -> Result<bool> {
+        Ok(rails.contains(&h) && mesh.face_edges(mesh.at_halfedge(h).face().try_end()?).len() == 4)
+    };
+
+    let mut strips = vec![];
+    let mut visited = HashSet::new();
+    for &rail in &rails {
+        let face = mesh.at_halfedge(rail).face().try_end()?;
+        if visited.contains(&face) || !is_strip_rail(mesh, rail)? {
+            continue;
+        }
+        // Walk back to the start of the strip, unless it's closed
+        let mut start = rail;
+        loop {
+            let prev = mesh
+                .at_halfedge(start)
+                .previous()
+                .twin()
+                .previous()
+                .try_end()?;
+            if prev == rail || !is_strip_rail(mesh, prev)? {
+                break;
+            }
+            start = prev;
+        }
+        let mut strip = vec![];
+        let mut h = start;
+        loop {
+            strip.push(h);
+            visited.insert(mesh.at_halfedge(h).face().try_end()?);
+            let next = mesh.at_halfedge(h).next().twin().next().try_end()?;
+            if next == start
+                || !is_strip_rail(mesh, next)?
+                || visited.contains(&mesh.at_halfedge(next).face().try_end()?)
+            {
+                break;
+            }
+            h = next;
+        }
+        strips.push(strip);
+    }
+
+    for strip in strips {
+        let (_, rungs) = loop_cut_with_rungs(mesh, positions, &strip, segments - 1)?;
+        for rung in rungs {
+            let (a, b) = (rung[0], rung[rung.len() - 1]);
+            if let Some(corner) = corners.get(&a) {
+                let (a_pos, b_pos) = (positions[a], positions[b]);
+                for (i, v) in rung.iter().enumerate().take(rung.len() - 1).skip(1) {
+                    let t = i as f32 / segments as f32;
+                    positions[*v] = bevel_profile(*corner, a_pos, b_pos, t, profile);
+                }
+            }
+        }
+    }
+
     Ok(())
 }
 
+/// Returns the point at `t` along the profile of a bevel that goes from `a` to
+/// `b`, where `corner` is the position of the edge before beveling. The
+/// profile is a superellipse inscribed in the parallelogram formed by the
+/// three points and the fourth corner opposite to `corner`.
+///
+/// A `profile` of 0.5 gives a round bevel and 0.25 a straight chamfer. Higher
+/// values bring the profile closer to the original corner, and lower values
+/// make it concave.
+fn bevel_profile(corner: Vec3, a: Vec3, b: Vec3, t: f32, profile: f32) -> Vec3 {
+    let exponent = 2.0f32.powf(4.0 * profile.clamp(0.0, 1.0) - 1.0);
+    let angle = (1.0 - t) * std::f32::consts::FRAC_PI_2;
+    let x = angle.cos().max(0.0).powf(2.0 / exponent);
+    let y = angle.sin().max(0.0).powf(2.0 / exponent);
+    let (u, v) = (a - corner, b - corner);
+    let center = corner + u + v;
+    center - u * x - v * y
+}
+
 /// Extrudes the given set of faces. Faces that are connected by at least one
 /// edge will be connected after the extrude.
 pub fn extrude_faces(
@@ -571,3 +876,1634 @@ pub fn extrude_faces(
 
     Ok(())
 }
+
+/// Insets each one of the given faces individually. A smaller copy of each
+/// face is created, moved towards the face's centroid by `amount`, and
+/// connected to the original boundary with a ring of quads. The amount is
+/// clamped to the inradius of the face so the inner face never gets inverted.
+/// Returns the inner faces.
+pub fn inset_faces(
+    mesh: &mut MeshConnectivity,
+    positions: &mut Positions,
+    faces: &[FaceId],
+    amount: f32,
+) -> Result<Vec<FaceId>> {
+    // Don't let the inner vertices collapse all the way into the centroid
+    const MAX_INSET_FACTOR: f32 = 0.99;
+
+    let mut inner_faces = vec![];
+    for &face in faces {
+        let outer = mesh.at_face(face).halfedges()?;
+        let n = outer.len();
+        if n < 3 {
+            bail!("Attempted to inset a face with only two vertices.");
+        }
+        let outer_verts = outer
+            .iter()
+            .map(|h| mesh.at_halfedge(*h).src_vertex().try_end())
+            .collect::<Result<SVec<_>, _>>()?;
+
+        // The inradius is approximated as the distance from the centroid to
+        // the closest edge.
+        let centroid = mesh.face_vertex_average(positions, face);
+        let inradius = (0..n)
+            .map(|i| {
+                let a = positions[outer_verts[i]];
+                let b = positions[outer_verts[(i + 1) % n]];
+                let ab = b - a;
+                let t = ((centroid - a).dot(ab) / ab.length_squared().max(f32::EPSILON))
+                    .clamp(0.0, 1.0);
+                centroid.distance(a + ab * t)
+            })
+            .fold(f32::INFINITY, f32::min);
+        let factor = (amount / inradius.max(f32::EPSILON)).clamp(0.0, MAX_INSET_FACTOR);
+
+        let inner_verts = outer_verts
+            .iter()
+            .map(|v| {
+                let pos = positions[*v].lerp(centroid, factor);
+                mesh.alloc_vertex(positions, pos, None)
+            })
+            .collect::<SVec<_>>();
+
+        // Inner face, going in the same direction as the original one
+        let inner_face = mesh.alloc_face(None);
+        let inner = (0..n)
+            .map(|i| {
+                mesh.alloc_halfedge(HalfEdge {
+                    twin: None,
+                    next: None,
+                    vertex: Some(inner_verts[i]),
+                    face: Some(inner_face),
+                })
+            })
+            .collect::<SVec<_>>();
+        for i in 0..n {
+            mesh[inner[i]].next = Some(inner[(i + 1) % n]);
+            mesh[inner_verts[i]].halfedge = Some(inner[i]);
+        }
+        mesh[inner_face].halfedge = Some(inner[0]);
+
+        // The ring of quads. The i-th quad goes through the outer vertices i,
+        // i + 1, and then back through the inner vertices i + 1, i. The
+        // original face is reused as the first quad.
+        let mut sides_in: SVec<HalfEdgeId> = SVec::new();
+        let mut sides_out: SVec<HalfEdgeId> = SVec::new();
+        for i in 0..n {
+            let j = (i + 1) % n;
+            let quad = if i == 0 { face } else { mesh.alloc_face(None) };
+            let side_in = mesh.alloc_halfedge(HalfEdge {
+                twin: None,
+                next: None,
+                vertex: Some(outer_verts[j]),
+                face: Some(quad),
+            });
+            let inner_twin = mesh.alloc_halfedge(HalfEdge {
+                twin: Some(inner[i]),
+                next: None,
+                vertex: Some(inner_verts[j]),
+                face: Some(quad),
+            });
+            let side_out = mesh.alloc_halfedge(HalfEdge {
+                twin: None,
+                next: Some(outer[i]),
+                vertex: Some(inner_verts[i]),
+                face: Some(quad),
+            });
+            mesh[inner[i]].twin = Some(inner_twin);
+            mesh[outer[i]].next = Some(side_in);
+            mesh[outer[i]].face = Some(quad);
+            mesh[side_in].next = Some(inner_twin);
+            mesh[inner_twin].next = Some(side_out);
+            mesh[quad].halfedge = Some(outer[i]);
+            sides_in.push(side_in);
+            sides_out.push(side_out);
+        }
+        // The side going into the inner face at vertex j is the twin of the
+        // side coming out of it in the next quad.
+        for i in 0..n {
+            let j = (i + 1) % n;
+            mesh[sides_in[i]].twin = Some(sides_out[j]);
+            mesh[sides_out[j]].twin = Some(sides_in[i]);
+        }
+
+        inner_faces.push(inner_face);
+    }
+
+    Ok(inner_faces)
+}
+
+/// Pokes each one of the given faces, adding a vertex at its centroid and
+/// replacing the face by a fan of triangles around it. The new vertex is moved
+/// along the face normal by `offset`. Returns the new center vertices.
+pub fn poke_faces(
+    mesh: &mut MeshConnectivity,
+    positions: &mut Positions,
+    faces: &[FaceId],
+    offset: f32,
+) -> Result<Vec<VertexId>> {
+    let mut centers = vec![];
+    for &face in faces {
+        let outer = mesh.at_face(face).halfedges()?;
+        let n = outer.len();
+        let outer_verts = outer
+            .iter()
+            .map(|h| mesh.at_halfedge(*h).src_vertex().try_end())
+            .collect::<Result<SVec<_>, _>>()?;
+        let normal = mesh
+            .face_normal(positions, face)
+            .ok_or_else(|| anyhow!("Attempted to poke a face with only two vertices."))?;
+        let center_pos = mesh.face_vertex_average(positions, face) + normal * offset;
+        let center = mesh.alloc_vertex(positions, center_pos, None);
+
+        // The i-th triangle goes through the vertices i, i + 1 and the center.
+        // The original face is reused as the first triangle.
+        let mut to_center: SVec<HalfEdgeId> = SVec::new();
+        let mut from_center: SVec<HalfEdgeId> = SVec::new();
+        for i in 0..n {
+            let tri = if i == 0 { face } else { mesh.alloc_face(None) };
+            let from = mesh.alloc_halfedge(HalfEdge {
+                twin: None,
+                next: Some(outer[i]),
+                vertex: Some(center),
+                face: Some(tri),
+            });
+            let to = mesh.alloc_halfedge(HalfEdge {
+                twin: None,
+                next: Some(from),
+                vertex: Some(outer_verts[(i + 1) % n]),
+                face: Some(tri),
+            });
+            mesh[outer[i]].next = Some(to);
+            mesh[outer[i]].face = Some(tri);
+            mesh[tri].halfedge = Some(outer[i]);
+            to_center.push(to);
+            from_center.push(from);
+        }
+        for i in 0..n {
+            let j = (i + 1) % n;
+            mesh[to_center[i]].twin = Some(from_center[j]);
+            mesh[from_center[j]].twin = Some(to_center[i]);
+        }
+        mesh[center].halfedge = Some(from_center[0]);
+
+        centers.push(center);
+    }
+
+    Ok(centers)
+}
+
+/// Boundary vertices of different copies closer than this distance get welded
+/// together by [`array`].
+const ARRAY_WELD_DISTANCE: f32 = 1e-4;
+
+/// Makes `count` copies of the mesh, each one translated by `offset` with
+/// respect to the previous one, and merges them into a single mesh. When
+/// `merge_adjacent` is set, coincident boundary vertices of the copies are
+/// welded together so that touching copies become a single surface. Channel
+/// data is only preserved when not merging.
+pub fn array(
+    base: &HalfEdgeMesh,
+    count: u32,
+    offset: Vec3,
+    merge_adjacent: bool,
+) -> Result<HalfEdgeMesh> {
+    let points = (0..count).map(|i| offset * i as f32).collect_vec();
+    if !merge_adjacent {
+        return instance_on_points(base, &points, None);
+    }
+
+    let mesh = base.read_connectivity();
+    let positions = base.read_positions();
+    check_element_limit(
+        mesh.num_vertices().saturating_mul(count as usize),
+        "vertices",
+    )?;
+
+    let mut boundary = HashSet::new();
+    for (h, _) in mesh.iter_halfedges() {
+        if mesh.at_halfedge(h).is_boundary()? {
+            boundary.insert(mesh.at_halfedge(h).src_vertex().try_end()?);
+        }
+    }
+
+    let mut new_positions = vec![];
+    let mut welded = HashMap::<Vec3Ord, u32>::new();
+    let mut polygons = vec![];
+    for point in points {
+        let mut indices = HashMap::new();
+        for (v, _) in mesh.iter_vertices() {
+            let pos = positions[v] + point;
+            let mut push_vertex = || {
+                new_positions.push(pos);
+                new_positions.len() as u32 - 1
+            };
+            let index = if boundary.contains(&v) {
+                *welded
+                    .entry((pos / ARRAY_WELD_DISTANCE).round().to_ord())
+                    .or_insert_with(push_vertex)
+            } else {
+                push_vertex()
+            };
+            indices.insert(v, index);
+        }
+        for (face, _) in mesh.iter_faces() {
+            polygons.push(
+                mesh.face_vertices(face)
+                    .iter()
+                    .map(|v| indices[v])
+                    .collect_vec(),
+            );
+        }
+    }
+
+    HalfEdgeMesh::build_from_polygons(&new_positions, &polygons)
+}
+
+/// Reverses the winding of all the faces in a connected component, given as
+/// the full list of its faces. The boundary halfedges of the component are
+/// reversed too, so twins still point in opposite directions.
+fn flip_component(mesh: &mut MeshConnectivity, component: &[FaceId]) -> Result<()> {
+    let mut halfedges = HashSet::new();
+    for &face in component {
+        for h in mesh.at_face(face).halfedges()? {
+            halfedges.insert(h);
+            halfedges.insert(mesh.at_halfedge(h).twin().try_end()?);
+        }
+    }
+
+    let mut new_next = vec![];
+    let mut new_vertex = vec![];
+    let mut vertices = HashSet::new();
+    for &h in &halfedges {
+        let next = mesh.at_halfedge(h).next().try_end()?;
+        let (src, dst) = mesh.at_halfedge(h).src_dst_pair()?;
+        new_next.push((next, h));
+        new_vertex.push((h, dst));
+        vertices.insert(src);
+    }
+    for (h, next) in new_next {
+        mesh[h].next = Some(next);
+    }
+    for (h, v) in new_vertex {
+        mesh[h].vertex = Some(v);
+    }
+    // Each vertex's outgoing halfedge is now incoming, so its twin is the new
+    // outgoing halfedge.
+    for v in vertices {
+        if let Some(h) = mesh[v].halfedge {
+            mesh[v].halfedge = mesh[h].twin;
+        }
+    }
+    Ok(())
+}
+
+/// Reverses the winding of the given faces, flipping their normals. The
+/// winding must be consistent across shared edges, so the whole connected
+/// component containing each face gets flipped.
+pub fn flip_faces(mesh: &mut MeshConnectivity, faces: &[FaceId]) -> Result<()> {
+    let mut flipped = HashSet::new();
+    for &face in faces {
+        if flipped.contains(&face) {
+            continue;
+        }
+        let component = selection::select_linked_faces(mesh, &[face])?;
+        flipped.extend(component.iter_cpy());
+        flip_component(mesh, &component)?;
+    }
+    Ok(())
+}
+
+/// Makes the normals of every connected component of the mesh point outwards.
+/// The winding is already consistent inside a component, but separate
+/// components, e.g. after merging meshes, may be inside-out. Components with a
+/// negative signed volume get flipped. For open surfaces, the volume is
+/// measured as if the holes were closed by a fan around the origin.
+pub fn recompute_normals(mesh: &mut MeshConnectivity, positions: &Positions) -> Result<()> {
+    let mut visited = HashSet::new();
+    let mut components = vec![];
+    for (face, _) in mesh.iter_faces() {
+        if !visited.contains(&face) {
+            let component = selection::select_linked_faces(mesh, &[face])?;
+            visited.extend(component.iter_cpy());
+            components.push(component);
+        }
+    }
+
+    for component in components {
+        let mut volume = 0.0;
+        for &face in &component {
+            let verts = mesh.face_vertices(face);
+            for i in 1..verts.len().saturating_sub(1) {
+                let (a, b, c) = (
+                    positions[verts[0]],
+                    positions[verts[i]],
+                    positions[verts[i + 1]],
+                );
+                volume += a.dot((b - a).cross(c - a)) / 6.0;
+            }
+        }
+        if volume < 0.0 {
+            flip_component(mesh, &component)?;
+        }
+    }
+    Ok(())
+}
+
+/// Gives thickness to a surface mesh. An inner shell is created by moving a
+/// copy of each vertex inwards along its normal by `thickness`, with its faces
+/// flipped. On open meshes, each boundary loop is stitched to the inner shell
+/// with a wall of quads, so the result is always a closed solid. Closed meshes
+/// just get the inner shell, making them hollow.
+pub fn solidify(
+    mesh: &MeshConnectivity,
+    positions: &Positions,
+    thickness: f32,
+) -> Result<HalfEdgeMesh> {
+    let mut indices = HashMap::new();
+    let mut outer_positions = vec![];
+    let mut normals = vec![];
+    for (v, _) in mesh.iter_vertices() {
+        indices.insert(v, outer_positions.len() as u32);
+        outer_positions.push(positions[v]);
+        let mut normal = Vec3::ZERO;
+        for face in mesh.at_vertex(v).adjacent_faces()? {
+            normal += mesh.face_normal(positions, face).unwrap_or(Vec3::ZERO);
+        }
+        normals.push(normal.normalize_or_zero());
+    }
+    let num_verts = outer_positions.len() as u32;
+    check_element_limit(num_verts as usize * 2, "vertices")?;
+
+    let mut new_positions = outer_positions.clone();
+    new_positions.extend(
+        outer_positions
+            .iter()
+            .zip(normals.iter())
+            .map(|(pos, normal)| *pos - *normal * thickness),
+    );
+
+    let mut polygons = vec![];
+    for (face, _) in mesh.iter_faces() {
+        let outer = mesh
+            .face_vertices(face)
+            .iter()
+            .map(|v| indices[v])
+            .collect_vec();
+        let inner = outer.iter().rev().map(|i| i + num_verts).collect_vec();
+        polygons.push(outer);
+        polygons.push(inner);
+    }
+    for (h, _) in mesh.iter_halfedges() {
+        if mesh.at_halfedge(h).is_boundary()? {
+            let (src, dst) = mesh.at_halfedge(h).src_dst_pair()?;
+            let (src, dst) = (indices[&src], indices[&dst]);
+            polygons.push(vec![src, dst, dst + num_verts, src + num_verts]);
+        }
+    }
+
+    HalfEdgeMesh::build_from_polygons(&new_positions, &polygons)
+}
+
+/// Vertices closer than this distance to the mirror plane get welded with
+/// their reflection by [`mirror`].
+const MIRROR_WELD_DISTANCE: f32 = 1e-4;
+
+/// Returns a new mesh with the original geometry plus a copy reflected across
+/// the plane defined by `plane_origin` and `plane_normal`. The reflected faces
+/// are flipped so their normals keep pointing outwards. When `weld` is set,
+/// vertices on the mirror plane are shared by both halves instead of
+/// duplicated, so the halves end up connected.
+pub fn mirror(
+    mesh: &MeshConnectivity,
+    positions: &Positions,
+    plane_origin: Vec3,
+    plane_normal: Vec3,
+    weld: bool,
+) -> Result<HalfEdgeMesh> {
+    let normal = plane_normal.normalize_or_zero();
+    if normal == Vec3::ZERO {
+        bail!("The mirror plane normal can't be zero");
+    }
+    check_element_limit(mesh.num_vertices().saturating_mul(2), "vertices")?;
+
+    let mut indices = HashMap::new();
+    let mut new_positions = vec![];
+    for (v, _) in mesh.iter_vertices() {
+        indices.insert(v, new_positions.len() as u32);
+        new_positions.push(positions[v]);
+    }
+
+    // Maps the index of each original vertex to the index of its reflection
+    let mut mirrored = vec![];
+    for i in 0..new_positions.len() {
+        let pos = new_positions[i];
+        let distance = (pos - plane_origin).dot(normal);
+        if weld && distance.abs() < MIRROR_WELD_DISTANCE {
+            mirrored.push(i as u32);
+        } else {
+            mirrored.push(new_positions.len() as u32);
+            new_positions.push(pos - 2.0 * distance * normal);
+        }
+    }
+
+    let mut polygons = vec![];
+    for (face, _) in mesh.iter_faces() {
+        let polygon = mesh
+            .face_vertices(face)
+            .iter()
+            .map(|v| indices[v])
+            .collect_vec();
+        let reflected = polygon
+            .iter()
+            .rev()
+            .map(|i| mirrored[*i as usize])
+            .collect_vec();
+        // A face lying on the mirror plane would be welded onto itself
+        if reflected.iter().any(|i| !polygon.contains(i)) {
+            polygons.push(reflected);
+        }
+        polygons.push(polygon);
+    }
+
+    HalfEdgeMesh::build_from_polygons(&new_positions, &polygons)
+}
+
+/// Makes the mesh symmetric across the coordinate plane perpendicular to
+/// `axis`. The geometry on one side of the plane is discarded and replaced by
+/// a welded reflection of the other side. When `positive_to_negative` is set,
+/// the positive side is kept and copied over to the negative side.
+///
+/// Faces that straddle the plane are kept, but their vertices on the discarded
+/// side are clamped onto the plane so the two halves can be welded together.
+pub fn symmetrize(
+    mesh: &MeshConnectivity,
+    positions: &Positions,
+    axis: selection::SymmetryAxis,
+    positive_to_negative: bool,
+) -> Result<HalfEdgeMesh> {
+    let normal = match axis {
+        selection::SymmetryAxis::X => Vec3::X,
+        selection::SymmetryAxis::Y => Vec3::Y,
+        selection::SymmetryAxis::Z => Vec3::Z,
+    };
+    let sign = if positive_to_negative { 1.0 } else { -1.0 };
+    // Signed distance to the plane, positive on the side that is kept
+    let distance = |v: VertexId| positions[v].dot(normal) * sign;
+
+    let mut indices = HashMap::new();
+    let mut kept_positions = vec![];
+    let mut polygons = vec![];
+    for (face, _) in mesh.iter_faces() {
+        let verts = mesh.face_vertices(face);
+        if verts.iter().all(|v| distance(*v) <= MIRROR_WELD_DISTANCE) {
+            continue;
+        }
+        let polygon = verts
+            .iter()
+            .map(|v| {
+                *indices.entry(*v).or_insert_with(|| {
+                    let mut pos = positions[*v];
+                    if distance(*v) < MIRROR_WELD_DISTANCE {
+                        pos -= pos.dot(normal) * normal;
+                    }
+                    kept_positions.push(pos);
+                    kept_positions.len() as u32 - 1
+                })
+            })
+            .collect_vec();
+        polygons.push(polygon);
+    }
+
+    let kept = HalfEdgeMesh::build_from_polygons(&kept_positions, &polygons)?;
+    let (conn, positions) = (kept.read_connectivity(), kept.read_positions());
+    mirror(&conn, &positions, Vec3::ZERO, normal, true)
+}
+
+/// Returns the polygons of the surface swept by a profile mesh. `rings` maps
+/// each vertex of the profile to the indices of its copies along the sweep,
+/// one per step. Each boundary edge of the profile gets swept into a strip of
+/// `segments` faces, with coincident copies of a vertex collapsed into one.
+///
+/// When `closed` is set, the last step connects back to the first one.
+/// Otherwise, the profile faces are used to cap both ends of the sweep. The
+/// result is oriented so the profile faces at the start keep their winding,
+/// or the opposite one when `flip` is set.
+fn swept_polygons(
+    mesh: &MeshConnectivity,
+    rings: &HashMap<VertexId, Vec<u32>>,
+    segments: usize,
+    closed: bool,
+    flip: bool,
+) -> Result<Vec<Vec<u32>>> {
+    let num_rings = if closed { segments } else { segments + 1 };
+
+    let mut boundary = vec![];
+    for (h, _) in mesh.iter_halfedges() {
+        if mesh.at_halfedge(h).is_boundary()? {
+            boundary.push(mesh.at_halfedge(h).src_dst_pair()?);
+        }
+    }
+    if boundary.is_empty() {
+        bail!("The profile mesh has no boundary edges");
+    }
+
+    // Each boundary edge from `a` to `b` is swept into a strip of quads. The
+    // edge runs opposite to the profile face next to it, so the strips and the
+    // profile faces are consistently oriented.
+    let mut polygons = vec![];
+    for (a, b) in boundary {
+        for i in 0..segments {
+            let j = (i + 1) % num_rings;
+            let mut polygon = vec![rings[&a][i], rings[&b][i], rings[&b][j], rings[&a][j]];
+            polygon.dedup();
+            if polygon.len() > 1 && polygon.first() == polygon.last() {
+                polygon.pop();
+            }
+            if polygon.len() >= 3 {
+                polygons.push(polygon);
+            }
+        }
+    }
+    if !closed {
+        for (face, _) in mesh.iter_faces() {
+            let verts = mesh.face_vertices(face);
+            polygons.push(verts.iter().map(|v| rings[v][0]).collect_vec());
+            polygons.push(
+                verts
+                    .iter()
+                    .rev()
+                    .map(|v| rings[v][num_rings - 1])
+                    .collect_vec(),
+            );
+        }
+    }
+
+    if flip {
+        for polygon in &mut polygons {
+            polygon.reverse();
+        }
+    }
+    Ok(polygons)
+}
+
+/// Vertices of the profile closer than this distance to the axis are not
+/// duplicated by [`revolve`], since all their copies would be coincident.
+const REVOLVE_AXIS_DISTANCE: f32 = 1e-4;
+
+/// Sweeps the boundary edges of `profile` around the axis defined by
+/// `axis_origin` and `axis_dir`, producing a surface of revolution made of
+/// `segments` strips of quads. Vertices of the profile lying on the axis are
+/// shared by all the strips, which turns the quads touching them into
+/// triangles.
+///
+/// When `angle_degrees` is a full turn, the last strip is welded to the first
+/// one. Otherwise, the faces of the profile are kept at both ends to close the
+/// swept volume.
+pub fn revolve(
+    profile: &HalfEdgeMesh,
+    axis_origin: Vec3,
+    axis_dir: Vec3,
+    angle_degrees: f32,
+    segments: u32,
+) -> Result<HalfEdgeMesh> {
+    let axis = axis_dir.normalize_or_zero();
+    if axis == Vec3::ZERO {
+        bail!("The revolve axis can't be zero");
+    }
+    if segments == 0 {
+        bail!("Revolve needs at least one segment");
+    }
+    if angle_degrees == 0.0 {
+        bail!("The revolve angle can't be zero");
+    }
+    let full_turn = angle_degrees.abs() >= 360.0 - 1e-3;
+    let angle = if full_turn {
+        360.0f32.copysign(angle_degrees)
+    } else {
+        angle_degrees
+    };
+    // On full turns, the ring after the last one is the first one
+    let num_rings = if full_turn { segments } else { segments + 1 };
+    let num_rings = num_rings as usize;
+
+    let mesh = profile.read_connectivity();
+    let positions = profile.read_positions();
+    check_element_limit(mesh.num_vertices().saturating_mul(num_rings), "vertices")?;
+
+    let mut new_positions = vec![];
+    let mut rings = HashMap::new();
+    for (v, _) in mesh.iter_vertices() {
+        let pos = positions[v] - axis_origin;
+        let ring = if (pos - axis * pos.dot(axis)).length() < REVOLVE_AXIS_DISTANCE {
+            new_positions.push(positions[v]);
+            vec![new_positions.len() as u32 - 1; num_rings]
+        } else {
+            (0..num_rings)
+                .map(|i| {
+                    let rotation = Quat::from_axis_angle(
+                        axis,
+                        (angle * i as f32 / segments as f32).to_radians(),
+                    );
+                    new_positions.push(axis_origin + rotation * pos);
+                    new_positions.len() as u32 - 1
+                })
+                .collect_vec()
+        };
+        rings.insert(v, ring);
+    }
+
+    // The profile faces at the start must point against the direction of the
+    // sweep for the result to face outwards.
+    let flip = match mesh.iter_faces().next() {
+        Some((face, _)) => {
+            let centroid = mesh.face_vertex_average(&positions, face);
+            let sweep_dir = axis.cross(centroid - axis_origin) * angle.signum();
+            let normal = mesh.face_normal(&positions, face).unwrap_or(Vec3::ZERO);
+            normal.dot(sweep_dir) > 0.0
+        }
+        None => false,
+    };
+    let polygons = swept_polygons(&mesh, &rings, segments as usize, full_turn, flip)?;
+
+    HalfEdgeMesh::build_from_polygons(&new_positions, &polygons)
+}
+
+/// Extrudes `profile` along the polyline `path`. The profile is defined in its
+/// own XY plane, and a copy of it is placed at every point of the path with
+/// the local Z axis pointing along the path. Each boundary edge of the profile
+/// is swept into a strip of quads, and the profile faces cap both ends.
+///
+/// The cross-sections are oriented with a parallel-transport frame, so they
+/// don't twist around the path. At the corners of the path, the cross-section
+/// is aligned with the average of the directions of the two segments.
+pub fn sweep(profile: &HalfEdgeMesh, path: &[Vec3]) -> Result<HalfEdgeMesh> {
+    // Repeated points would leave some segments without a direction
+    let mut points: Vec<Vec3> = vec![];
+    for &p in path {
+        if let Some(last) = points.last() {
+            if last.distance(p) <= 1e-6 {
+                continue;
+            }
+        }
+        points.push(p);
+    }
+    if points.len() < 2 {
+        bail!("The sweep path needs at least two distinct points");
+    }
+
+    let mesh = profile.read_connectivity();
+    let positions = profile.read_positions();
+    check_element_limit(mesh.num_vertices().saturating_mul(points.len()), "vertices")?;
+
+    let directions = points
+        .iter()
+        .tuple_windows()
+        .map(|(a, b)| (*b - *a).normalize())
+        .collect_vec();
+    let tangents = (0..points.len())
+        .map(|i| {
+            let incoming = directions[i.saturating_sub(1)];
+            let outgoing = directions[i.min(directions.len() - 1)];
+            // A path that turns back on itself has no average direction
+            let average = (incoming + outgoing).normalize_or_zero();
+            if average == Vec3::ZERO {
+                outgoing
+            } else {
+                average
+            }
+        })
+        .collect_vec();
+
+    // Each frame is the previous one, rotated by the smallest rotation that
+    // takes the previous tangent to the current one.
+    let mut frames = vec![Quat::from_rotation_arc(Vec3::Z, tangents[0])];
+    for (prev, next) in tangents.iter().tuple_windows() {
+        let frame = Quat::from_rotation_arc(*prev, *next) * *frames.last().unwrap();
+        frames.push(frame.normalize());
+    }
+
+    let mut new_positions = vec![];
+    let mut rings = HashMap::new();
+    for (v, _) in mesh.iter_vertices() {
+        let ring = points
+            .iter()
+            .zip(&frames)
+            .map(|(point, frame)| {
+                new_positions.push(*point + *frame * positions[v]);
+                new_positions.len() as u32 - 1
+            })
+            .collect_vec();
+        rings.insert(v, ring);
+    }
+
+    // The profile faces at the start must point against the direction of the
+    // sweep, which is the profile's Z axis, for the result to face outwards.
+    let flip = match mesh.iter_faces().next() {
+        Some((face, _)) => {
+            let normal = mesh.face_normal(&positions, face).unwrap_or(Vec3::ZERO);
+            normal.dot(Vec3::Z) > 0.0
+        }
+        None => false,
+    };
+    let polygons = swept_polygons(&mesh, &rings, points.len() - 1, false, flip)?;
+
+    HalfEdgeMesh::build_from_polygons(&new_positions, &polygons)
+}
+
+/// Returns the boundary loop that contains the given halfedges, ordered by
+/// following the `next` pointers. Halfedges that are on a face are replaced by
+/// their twin, so the loop can be selected from either side.
+fn find_boundary_loop(
+    mesh: &MeshConnectivity,
+    halfedges: &[HalfEdgeId],
+) -> Result<SVec<HalfEdgeId>> {
+    let mut boundary = vec![];
+    for &h in halfedges {
+        if mesh.at_halfedge(h).is_boundary()? {
+            boundary.push(h);
+        } else {
+            let twin = mesh.at_halfedge(h).twin().try_end()?;
+            if !mesh.at_halfedge(twin).is_boundary()? {
+                bail!("Halfedge {:?} is not on a boundary", h);
+            }
+            boundary.push(twin);
+        }
+    }
+    let first = *boundary
+        .first()
+        .ok_or_else(|| anyhow!("Cannot bridge an empty edge loop"))?;
+    let edge_loop = mesh.halfedge_loop(first);
+    if let Some(h) = boundary.iter().find(|h| !edge_loop.contains(h)) {
+        bail!("Halfedge {:?} is not part of the same boundary loop", h);
+    }
+    Ok(edge_loop)
+}
+
+/// Connects two boundary loops with a band of quads. Both loops must have the
+/// same number of edges. The vertex correspondence between the loops is chosen
+/// to minimize the distance between matched vertices, and loops are matched in
+/// opposite directions so the new faces keep a consistent winding. Returns the
+/// new faces.
+pub fn bridge_loops(
+    mesh: &mut MeshConnectivity,
+    positions: &Positions,
+    loop_a: &[HalfEdgeId],
+    loop_b: &[HalfEdgeId],
+) -> Result<Vec<FaceId>> {
+    let loop_a = find_boundary_loop(mesh, loop_a)?;
+    let loop_b = find_boundary_loop(mesh, loop_b)?;
+    if loop_a.len() != loop_b.len() {
+        bail!(
+            "Cannot bridge edge loops of different lengths ({} and {} edges)",
+            loop_a.len(),
+            loop_b.len()
+        );
+    }
+    if loop_a.contains(&loop_b[0]) {
+        bail!("Cannot bridge an edge loop with itself");
+    }
+    let n = loop_a.len();
+
+    let src = |h: HalfEdgeId| mesh.at_halfedge(h).src_vertex().try_end();
+    let verts_a = loop_a
+        .iter()
+        .map(|h| src(*h))
+        .collect::<Result<SVec<_>, _>>()?;
+    let verts_b = loop_b
+        .iter()
+        .map(|h| src(*h))
+        .collect::<Result<SVec<_>, _>>()?;
+
+    // Vertex i in loop a is matched with vertex (shift - i) in loop b. Find
+    // the shift that minimizes the distance between matched vertices.
+    let matched = |shift: usize, i: usize| (shift + n - i % n) % n;
+    let shift = (0..n)
+        .map(|shift| {
+            let cost: f32 = (0..n)
+                .map(|i| {
+                    positions[verts_a[i]].distance_squared(positions[verts_b[matched(shift, i)]])
+                })
+                .sum();
+            (shift, cost)
+        })
+        .min_by(|(_, c1), (_, c2)| c1.partial_cmp(c2).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(shift, _)| shift)
+        .unwrap_or(0);
+
+    // The i-th quad is formed by the edge from a[i] to a[i + 1], the edge
+    // from b[shift - i - 1] to b[shift - i], and two new edges connecting them.
+    let mut faces = vec![];
+    let mut sides_to_b: SVec<HalfEdgeId> = SVec::new();
+    let mut sides_to_a: SVec<HalfEdgeId> = SVec::new();
+    for i in 0..n {
+        let h_a = loop_a[i];
+        let h_b = loop_b[matched(shift, i + 1)];
+        let face = mesh.alloc_face(Some(h_a));
+        let to_b = mesh.alloc_halfedge(HalfEdge {
+            twin: None,
+            next: Some(h_b),
+            vertex: Some(verts_a[(i + 1) % n]),
+            face: Some(face),
+        });
+        let to_a = mesh.alloc_halfedge(HalfEdge {
+            twin: None,
+            next: Some(h_a),
+            vertex: Some(verts_b[matched(shift, i)]),
+            face: Some(face),
+        });
+        mesh[h_a].next = Some(to_b);
+        mesh[h_a].face = Some(face);
+        mesh[h_b].next = Some(to_a);
+        mesh[h_b].face = Some(face);
+        sides_to_b.push(to_b);
+        sides_to_a.push(to_a);
+        faces.push(face);
+    }
+    for i in 0..n {
+        let j = (i + 1) % n;
+        mesh[sides_to_b[i]].twin = Some(sides_to_a[j]);
+        mesh[sides_to_a[j]].twin = Some(sides_to_b[i]);
+    }
+
+    Ok(faces)
+}
+
+/// Controls the random variation of the instances generated by
+/// [`instance_on_points_with_variation`]. The random values for each instance
+/// are derived from the seed and the instance index, so results are
+/// reproducible.
+#[derive(Clone, Debug)]
+pub struct InstanceVariation {
+    pub seed: u64,
+    /// The minimum uniform scale applied to an instance
+    pub min_scale: f32,
+    /// The maximum uniform scale applied to an instance
+    pub max_scale: f32,
+    /// When set, instances are rotated by a random angle about this axis
+    pub rotation_axis: Option<Vec3>,
+}
+
+impl Default for InstanceVariation {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            min_scale: 1.0,
+            max_scale: 1.0,
+            rotation_axis: None,
+        }
+    }
+}
+
+/// Returns a pseudo-random number in the [0, 1) range, derived by hashing the
+/// `seed`, `index` and `stream` together. Different streams give uncorrelated
+/// values for the same seed and index.
+fn hash_unit_f32(seed: u64, index: u64, stream: u64) -> f32 {
+    // SplitMix64 finalizer
+    let mut x = seed
+        .wrapping_add(index.wrapping_mul(0x9E3779B97F4A7C15))
+        .wrapping_add(stream.wrapping_mul(0xBF58476D1CE4E5B9));
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+    x ^= x >> 31;
+    (x >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Copies the `base` mesh at each one of the `points`, and merges all the
+/// copies into a single mesh. When `orientations` are given, each copy is
+/// rotated by the orientation at the same index as its point. Channel data is
+/// remapped for each instance, so every copy keeps its own values.
+pub fn instance_on_points(
+    base: &HalfEdgeMesh,
+    points: &[Vec3],
+    orientations: Option<&[Quat]>,
+) -> Result<HalfEdgeMesh> {
+    instance_on_points_with_variation(base, points, orientations, &InstanceVariation::default())
+}
+
+/// Same as [`instance_on_points`], but each instance gets a random scale and
+/// rotation, as described by `variation`. The random rotation is applied
+/// before the instance's orientation.
+pub fn instance_on_points_with_variation(
+    base: &HalfEdgeMesh,
+    points: &[Vec3],
+    orientations: Option<&[Quat]>,
+    variation: &InstanceVariation,
+) -> Result<HalfEdgeMesh> {
+    {
+        let conn = base.read_connectivity();
+        let instances = points.len();
+        check_element_limit(conn.num_vertices().saturating_mul(instances), "vertices")?;
+        check_element_limit(conn.num_faces().saturating_mul(instances), "faces")?;
+    }
+
+    let mut result = HalfEdgeMesh::new();
+    for (i, point) in points.iter_cpy().enumerate() {
+        let orientation = orientations
+            .and_then(|o| o.get(i).copied())
+            .unwrap_or(Quat::IDENTITY);
+        let scale = variation.min_scale
+            + (variation.max_scale - variation.min_scale)
+                * hash_unit_f32(variation.seed, i as u64, 0);
+        let random_rotation = match variation.rotation_axis {
+            Some(axis) if axis.length_squared() > 0.0 => Quat::from_axis_angle(
+                axis.normalize(),
+                hash_unit_f32(variation.seed, i as u64, 1) * std::f32::consts::TAU,
+            ),
+            _ => Quat::IDENTITY,
+        };
+        let rotation = orientation * random_rotation;
+
+        let instance = base.clone();
+        for (_, pos) in instance.write_positions().iter_mut() {
+            *pos = rotation * (*pos * scale) + point;
+        }
+        result.merge_with(&instance);
+    }
+    Ok(result)
+}
+
+/// Relaxes the mesh under the effect of gravity, producing draped or hanging
+/// shapes. Edges are treated as springs that try to keep their original
+/// length, and the vertices that are not `pinned` fall downwards. Uses a
+/// simple Verlet integration scheme, where each iteration is a single time
+/// step. The `stiffness`, in the [0, 1] range, controls how strongly edges
+/// resist stretching. Only the positions are modified. Reports progress after
+/// every iteration, and stops with an error when `progress` is cancelled.
+pub fn relax_gravity(
+    mesh: &MeshConnectivity,
+    positions: &mut Positions,
+    pinned: &[VertexId],
+    iterations: usize,
+    stiffness: f32,
+    progress: &Progress,
+) -> Result<()> {
+    const GRAVITY: Vec3 = glam::const_vec3!([0.0, -9.81, 0.0]);
+    const TIME_STEP: f32 = 1.0 / 60.0;
+    const DAMPING: f32 = 0.98;
+
+    let stiffness = stiffness.clamp(0.0, 1.0);
+    let pinned: HashSet<VertexId> = pinned.iter().cloned().collect();
+
+    // Collect each edge once, along with its rest length
+    let mut visited = HashSet::new();
+    let mut edges = vec![];
+    for (h, halfedge) in mesh.iter_halfedges() {
+        if halfedge
+            .twin
+            .map(|tw| visited.contains(&tw))
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        visited.insert(h);
+        let (src, dst) = mesh.at_halfedge(h).src_dst_pair()?;
+        edges.push((src, dst, positions[src].distance(positions[dst])));
+    }
+
+    let mut previous = positions.clone();
+    for i in 0..iterations {
+        progress.check_cancelled()?;
+        // Integration step
+        for (v, _) in mesh.iter_vertices() {
+            if pinned.contains(&v) {
+                continue;
+            }
+            let velocity = (positions[v] - previous[v]) * DAMPING;
+            previous[v] = positions[v];
+            positions[v] += velocity + GRAVITY * TIME_STEP * TIME_STEP;
+        }
+
+        // Constraint step
+        for &(src, dst, rest_length) in &edges {
+            let delta = positions[dst] - positions[src];
+            let length = delta.length();
+            if length <= f32::EPSILON {
+                continue;
+            }
+            let correction = delta * ((length - rest_length) / length) * stiffness;
+            match (pinned.contains(&src), pinned.contains(&dst)) {
+                (true, true) => {}
+                (true, false) => positions[dst] -= correction,
+                (false, true) => positions[src] += correction,
+                (false, false) => {
+                    positions[src] += correction * 0.5;
+                    positions[dst] -= correction * 0.5;
+                }
+            }
+        }
+        progress.set_fraction((i + 1) as f32 / iterations as f32);
+    }
+
+    Ok(())
+}
+
+/// Smooths the given vertices by repeatedly moving each of them towards the
+/// average position of its neighbors. The `factor`, in the [0, 1] range,
+/// controls how far vertices move on each iteration. When `pin_boundary` is
+/// set, vertices on a mesh boundary are left in place. Vertices that are not
+/// in `verts` are never moved. Reports progress after every iteration, and
+/// stops with an error when `progress` is cancelled.
+pub fn laplacian_smooth(
+    mesh: &MeshConnectivity,
+    positions: &mut Positions,
+    verts: &[VertexId],
+    iterations: u32,
+    factor: f32,
+    pin_boundary: bool,
+    progress: &Progress,
+) -> Result<()> {
+    let factor = factor.clamp(0.0, 1.0);
+
+    let mut neighbors = vec![];
+    for &v in verts {
+        let outgoing = mesh.at_vertex(v).outgoing_halfedges()?;
+        if pin_boundary {
+            let mut on_boundary = false;
+            for &h in &outgoing {
+                let twin = mesh.at_halfedge(h).twin().try_end()?;
+                on_boundary |=
+                    mesh.at_halfedge(h).is_boundary()? || mesh.at_halfedge(twin).is_boundary()?;
+            }
+            if on_boundary {
+                continue;
+            }
+        }
+        let ring = outgoing
+            .iter()
+            .map(|h| mesh.at_halfedge(*h).dst_vertex().try_end())
+            .collect::<Result<SVec<_>, _>>()?;
+        if !ring.is_empty() {
+            neighbors.push((v, ring));
+        }
+    }
+
+    for i in 0..iterations {
+        progress.check_cancelled()?;
+        // Compute all the new positions before writing any, so the result
+        // doesn't depend on the order of the vertices.
+        let new_positions = neighbors
+            .iter()
+            .map(|(v, ring)| {
+                let average =
+                    ring.iter().fold(Vec3::ZERO, |acc, w| acc + positions[*w]) / ring.len() as f32;
+                positions[*v].lerp(average, factor)
+            })
+            .collect_vec();
+        for ((v, _), pos) in neighbors.iter().zip(new_positions) {
+            positions[*v] = pos;
+        }
+        progress.set_fraction((i + 1) as f32 / iterations as f32);
+    }
+
+    Ok(())
+}
+
+/// Displaces each of the given vertices by a random offset, at most `amount`
+/// long. The offsets are derived from the `seed` and the vertex ids, so the
+/// same seed always displaces the same vertex in the same way.
+pub fn jitter_vertices(
+    mesh: &MeshConnectivity,
+    positions: &mut Positions,
+    verts: &[VertexId],
+    amount: f32,
+    seed: u64,
+) -> Result<()> {
+    for &v in verts {
+        if !mesh.vertex_exists(v) {
+            bail!("Vertex {v:?} does not exist for this mesh");
+        }
+        // A uniformly distributed point inside the ball of radius `amount`
+        let index = slotmap::Key::data(&v).as_ffi();
+        let z = hash_unit_f32(seed, index, 0) * 2.0 - 1.0;
+        let angle = hash_unit_f32(seed, index, 1) * std::f32::consts::TAU;
+        let radius = amount * hash_unit_f32(seed, index, 2).cbrt();
+        let r_xy = (1.0 - z * z).sqrt();
+        positions[v] += radius * Vec3::new(r_xy * angle.cos(), r_xy * angle.sin(), z);
+    }
+    Ok(())
+}
+
+/// Applies the affine transform `matrix` to the positions of the given
+/// vertices. The rest of the mesh is left untouched.
+pub fn transform(
+    mesh: &MeshConnectivity,
+    positions: &mut Positions,
+    verts: &[VertexId],
+    matrix: Mat4,
+) -> Result<()> {
+    for &v in verts {
+        if !mesh.vertex_exists(v) {
+            bail!("Vertex {v:?} does not exist for this mesh");
+        }
+        positions[v] = matrix.transform_point3(positions[v]);
+    }
+    Ok(())
+}
+
+/// Inserts `cuts` new edge loops parallel to `edge_loop`, evenly spaced across
+/// the strip of quads on its left side. The edges of the strip going across
+/// it get divided, and new vertices are placed by linear interpolation.
+///
+/// The `edge_loop` must be ordered along the loop, as returned by
+/// [`selection::edge_loop`](super::selection::edge_loop), and all the faces on
+/// its left side must be quads. Returns the halfedges of the new loops.
+pub fn loop_cut(
+    mesh: &mut MeshConnectivity,
+    positions: &mut Positions,
+    edge_loop: &[HalfEdgeId],
+    cuts: u32,
+) -> Result<Vec<HalfEdgeId>> {
+    let (new_halfedges, _) = loop_cut_with_rungs(mesh, positions, edge_loop, cuts)?;
+    Ok(new_halfedges)
+}
+
+/// Same as [`loop_cut`], but also returns the vertices along each of the edges
+/// going across the strip, including the endpoints. The vertices are ordered
+/// from the side of the `edge_loop` outwards.
+fn loop_cut_with_rungs(
+    mesh: &mut MeshConnectivity,
+    positions: &mut Positions,
+    edge_loop: &[HalfEdgeId],
+    cuts: u32,
+) -> Result<(Vec<HalfEdgeId>, Vec<SVec<VertexId>>)> {
+    if edge_loop.is_empty() || cuts == 0 {
+        return Ok((vec![], vec![]));
+    }
+
+    let mut faces = HashSet::new();
+    for (&h, &h_next) in edge_loop.iter().tuple_windows() {
+        if mesh.at_halfedge(h).dst_vertex().try_end()?
+            != mesh.at_halfedge(h_next).vertex().try_end()?
+        {
+            bail!("The halfedges for a loop cut must form a connected loop");
+        }
+    }
+    for &h in edge_loop {
+        let face = mesh
+            .at_halfedge(h)
+            .face()
+            .try_end()
+            .map_err(|_| anyhow!("Cannot loop cut next to a boundary edge: {:?}", h))?;
+        if mesh.face_edges(face).len() != 4 {
+            bail!(
+                "Loop cut only works on quads, but face {:?} is not a quad",
+                face
+            );
+        }
+        if !faces.insert(face) {
+            bail!("The loop cut crosses face {:?} twice", face);
+        }
+    }
+    check_element_limit(
+        mesh.num_vertices() + (edge_loop.len() + 1) * cuts as usize,
+        "vertices",
+    )?;
+
+    // The edges across the strip, pointing away from the loop. There's one at
+    // the start of each halfedge of the loop, and an extra one at the end if
+    // the loop is open.
+    let first = edge_loop[0];
+    let last = edge_loop[edge_loop.len() - 1];
+    let mut rungs = edge_loop
+        .iter()
+        .map(|h| mesh.at_halfedge(*h).previous().twin().try_end())
+        .collect::<Result<Vec<_>, _>>()?;
+    let closed = mesh.at_halfedge(last).dst_vertex().try_end()?
+        == mesh.at_halfedge(first).vertex().try_end()?;
+    if !closed {
+        rungs.push(mesh.at_halfedge(last).next().try_end()?);
+    }
+
+    // Divide each rung into `cuts + 1` equal parts. Dividing an edge keeps
+    // its halfedge on the far side, so the rung is divided from the loop
+    // outwards.
+    let mut rung_vertices = vec![];
+    for &rung in &rungs {
+        let (src, dst) = mesh.at_halfedge(rung).src_dst_pair()?;
+        let mut vertices = SVec::new();
+        vertices.push(src);
+        for i in 0..cuts {
+            vertices.push(divide_edge(
+                mesh,
+                positions,
+                rung,
+                1.0 / (cuts + 1 - i) as f32,
+            )?);
+        }
+        vertices.push(dst);
+        rung_vertices.push(vertices);
+    }
+
+    // Join the matching vertices on both rungs of every face of the strip
+    let mut new_halfedges = vec![];
+    for i in 0..edge_loop.len() {
+        let next = (i + 1) % rungs.len();
+        let inner = 1..=cuts as usize;
+        for (&v, &w) in rung_vertices[i][inner.clone()]
+            .iter()
+            .zip(rung_vertices[next][inner].iter())
+        {
+            new_halfedges.push(cut_face(mesh, v, w)?);
+        }
+    }
+    Ok((new_halfedges, rung_vertices))
+}
+
+/// Closes the holes in the mesh. Every boundary loop with at most `max_edges`
+/// edges gets filled with a new n-gon face. Returns the newly created faces.
+pub fn fill_holes(mesh: &mut MeshConnectivity, max_edges: usize) -> Result<Vec<FaceId>> {
+    let boundary_loops = mesh.boundary_loops()?;
+    let mut new_faces = vec![];
+    for boundary_loop in boundary_loops {
+        if boundary_loop.len() < 3 || boundary_loop.len() > max_edges {
+            continue;
+        }
+        let face = mesh.alloc_face(Some(boundary_loop[0]));
+        for &h in &boundary_loop {
+            mesh[h].face = Some(face);
+        }
+        new_faces.push(face);
+    }
+
+    Ok(new_faces)
+}
+
+/// Returns the endpoint positions for each of the edges containing the given
+/// halfedges. When both halfedges of an edge are present, the edge is only
+/// returned once.
+pub fn extract_edges(
+    mesh: &MeshConnectivity,
+    positions: &Positions,
+    halfedges: &[HalfEdgeId],
+) -> Result<Vec<(Vec3, Vec3)>> {
+    let mut visited = HashSet::new();
+    let mut edges = vec![];
+    for &h in halfedges {
+        if !visited.insert(h) {
+            continue;
+        }
+        if let Some(twin) = mesh[h].twin {
+            if !visited.insert(twin) {
+                continue;
+            }
+        }
+        let (src, dst) = mesh.at_halfedge(h).src_dst_pair()?;
+        edges.push((positions[src], positions[dst]));
+    }
+    Ok(edges)
+}
+
+/// Builds the dual of a mesh. The dual has a vertex at the centroid of each
+/// face of the original mesh, and a face for each of its vertices, connecting
+/// the centroids of the faces around that vertex. Vertices on the boundary of
+/// the original mesh do not produce a face. The dual of a cube is an
+/// octahedron.
+pub fn dual(mesh: &MeshConnectivity, positions: &Positions) -> Result<HalfEdgeMesh> {
+    let mut face_indices = HashMap::new();
+    let mut dual_positions = vec![];
+    for (face, _) in mesh.iter_faces() {
+        face_indices.insert(face, dual_positions.len() as u32);
+        dual_positions.push(mesh.face_vertex_average(positions, face));
+    }
+
+    let mut polygons = vec![];
+    'vertices: for (v, _) in mesh.iter_vertices() {
+        let mut polygon = vec![];
+        for h in mesh.at_vertex(v).outgoing_halfedges()? {
+            match mesh.at_halfedge(h).face_or_boundary()? {
+                Some(face) => polygon.push(face_indices[&face]),
+                None => continue 'vertices,
+            }
+        }
+        if polygon.len() < 3 {
+            continue;
+        }
+        // Cycling the outgoing halfedges visits the faces in clockwise order,
+        // but the new face needs to be counter-clockwise.
+        polygon.reverse();
+        polygons.push(polygon);
+    }
+
+    HalfEdgeMesh::build_from_polygons(&dual_positions, &polygons)
+}
+
+/// Appends a square prism going from `a` to `b` to the given `positions` and
+/// `polygons` buffers. The cross section is a square of side `2 * half_size`.
+fn push_prism(
+    positions: &mut Vec<Vec3>,
+    polygons: &mut Vec<[u32; 4]>,
+    a: Vec3,
+    b: Vec3,
+    half_size: f32,
+) {
+    let dir = (b - a).normalize_or_zero();
+    let helper = if dir.x.abs() < 0.9 { Vec3::X } else { Vec3::Y };
+    let u = dir.cross(helper).normalize();
+    let w = dir.cross(u);
+
+    let base = positions.len() as u32;
+    let corners = [u + w, -u + w, -u - w, u - w];
+    for endpoint in [a, b] {
+        for corner in corners {
+            positions.push(endpoint + corner * half_size);
+        }
+    }
+
+    polygons.push([base + 3, base + 2, base + 1, base]);
+    polygons.push([base + 4, base + 5, base + 6, base + 7]);
+    for i in 0..4 {
+        let j = (i + 1) % 4;
+        polygons.push([base + i, base + j, base + 4 + j, base + 4 + i]);
+    }
+}
+
+/// Turns the mesh into a lattice of beams along its edges. Each edge is
+/// replaced by a square prism with the given `thickness`, and each vertex by a
+/// slightly larger cube acting as a joint.
+pub fn wireframe_mesh(
+    mesh: &MeshConnectivity,
+    positions: &Positions,
+    thickness: f32,
+) -> Result<HalfEdgeMesh> {
+    let num_edges = mesh.num_halfedges() / 2;
+    check_element_limit(
+        (num_edges + mesh.num_vertices()).saturating_mul(8),
+        "vertices",
+    )?;
+
+    let mut new_positions = vec![];
+    let mut polygons = vec![];
+
+    let mut visited = HashSet::new();
+    for (h, halfedge) in mesh.iter_halfedges() {
+        if halfedge
+            .twin
+            .map(|tw| visited.contains(&tw))
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        visited.insert(h);
+        let (src, dst) = mesh.at_halfedge(h).src_dst_pair()?;
+        push_prism(
+            &mut new_positions,
+            &mut polygons,
+            positions[src],
+            positions[dst],
+            thickness * 0.5,
+        );
+    }
+
+    for (v, _) in mesh.iter_vertices() {
+        let joint_size = thickness * 0.75;
+        let pos = positions[v];
+        push_prism(
+            &mut new_positions,
+            &mut polygons,
+            pos - Vec3::Y * joint_size,
+            pos + Vec3::Y * joint_size,
+            joint_size,
+        );
+    }
+
+    HalfEdgeMesh::build_from_polygons(&new_positions, &polygons)
+}
+
+/// The name of the vertex channel written by [`compute_curvature`]
+pub const CURVATURE_CHANNEL: &str = "curvature";
+
+/// Returns the cotangent of the angle between `u` and `v`. The result is
+/// clamped so that degenerate (zero-area) triangles don't produce infinities.
+fn cotangent(u: Vec3, v: Vec3) -> f32 {
+    const MAX_COTANGENT: f32 = 1e4;
+    let sin = u.cross(v).length().max(f32::EPSILON);
+    (u.dot(v) / sin).clamp(-MAX_COTANGENT, MAX_COTANGENT)
+}
+
+/// Computes an estimate of the mean curvature at each vertex, using the
+/// cotangent Laplacian, and writes it into the `curvature` vertex channel.
+/// Faces with more than three sides are fan-triangulated. Curvature is
+/// positive on convex regions, negative on concave ones. Returns the id of
+/// the written channel.
+pub fn compute_curvature(mesh: &mut HalfEdgeMesh) -> Result<ChannelId<VertexId, f32>> {
+    let ch_id = mesh
+        .channels
+        .ensure_channel::<VertexId, f32>(CURVATURE_CHANNEL);
+
+    let conn = mesh.read_connectivity();
+    let positions = mesh.read_positions();
+
+    let mut laplacian: HashMap<VertexId, Vec3> = HashMap::new();
+    let mut area: HashMap<VertexId, f32> = HashMap::new();
+    let mut normal: HashMap<VertexId, Vec3> = HashMap::new();
+
+    for (face, _) in conn.iter_faces() {
+        let vertices = conn.face_vertices(face);
+        let face_normal = conn.face_normal(&positions, face).unwrap_or(Vec3::ZERO);
+        for i in 1..vertices.len().saturating_sub(1) {
+            let tri = [vertices[0], vertices[i], vertices[i + 1]];
+            let p = tri.map(|v| positions[v]);
+            let tri_area = (p[1] - p[0]).cross(p[2] - p[0]).length() * 0.5;
+            for corner in 0..3 {
+                let (a, b, c) = (corner, (corner + 1) % 3, (corner + 2) % 3);
+                // The weight of the edge opposite to this corner
+                let w = cotangent(p[b] - p[a], p[c] - p[a]);
+                *laplacian.entry(tri[b]).or_default() += w * (p[c] - p[b]);
+                *laplacian.entry(tri[c]).or_default() += w * (p[b] - p[c]);
+                *area.entry(tri[a]).or_default() += tri_area / 3.0;
+                *normal.entry(tri[a]).or_default() += face_normal;
+            }
+        }
+    }
+
+    let mut curvature = mesh.channels.write_channel(ch_id)?;
+    for (v, _) in conn.iter_vertices() {
+        let lap = laplacian.get(&v).copied().unwrap_or(Vec3::ZERO);
+        let area = area.get(&v).copied().unwrap_or(0.0).max(f32::EPSILON);
+        let normal = normal
+            .get(&v)
+            .copied()
+            .unwrap_or(Vec3::ZERO)
+            .normalize_or_zero();
+        // The Laplacian of the positions is -2 * H * n. It points inwards on
+        // convex regions, so flip it to make the curvature positive there.
+        curvature[v] = -lap.dot(normal) / (4.0 * area);
+    }
+
+    Ok(ch_id)
+}
+
+/// Linearly subdivides the given faces, `iterations` times. Each selected face
+/// is split into quads by adding a vertex at its center and at the midpoint of
+/// each of its edges. Unselected faces that share an edge with a subdivided
+/// face get the new edge midpoints too, and are stitched with a fan of
+/// triangles around their center so that no T-junctions are left. Reports
+/// progress after every iteration, and stops with an error when `progress` is
+/// cancelled.
+pub fn subdivide_selection(
+    mesh: &MeshConnectivity,
+    positions: &Positions,
+    faces: &[FaceId],
+    iterations: u32,
+    progress: &Progress,
+) -> Result<HalfEdgeMesh> {
+    let mut indices = HashMap::new();
+    let mut new_positions = vec![];
+    for (v, _) in mesh.iter_vertices() {
+        indices.insert(v, new_positions.len() as u32);
+        new_positions.push(positions[v]);
+    }
+    let selected: HashSet<FaceId> = faces.iter().copied().collect();
+    let mut polygons = vec![];
+    let mut is_selected = vec![];
+    for (face, _) in mesh.iter_faces() {
+        polygons.push(
+            mesh.face_vertices(face)
+                .iter()
+                .map(|v| indices[v])
+                .collect_vec(),
+        );
+        is_selected.push(selected.contains(&face));
+    }
+
+    for i in 0..iterations {
+        progress.check_cancelled()?;
+        let center = |polygon: &[u32], positions: &[Vec3]| {
+            polygon.iter().map(|i| positions[*i as usize]).sum::<Vec3>() / polygon.len() as f32
+        };
+
+        // Midpoints for all the edges of the selected faces, shared between
+        // both sides of the edge.
+        let mut midpoints = HashMap::<(u32, u32), u32>::new();
+        for (polygon, _) in polygons.iter().zip(&is_selected).filter(|(_, s)| **s) {
+            for (&a, &b) in polygon.iter().circular_tuple_windows() {
+                let key = (a.min(b), a.max(b));
+                midpoints.entry(key).or_insert_with(|| {
+                    new_positions
+                        .push((new_positions[a as usize] + new_positions[b as usize]) * 0.5);
+                    new_positions.len() as u32 - 1
+                });
+            }
+        }
+        let midpoint = |a: u32, b: u32| midpoints.get(&(a.min(b), a.max(b))).copied();
+
+        let mut next_polygons = vec![];
+        let mut next_selected = vec![];
+        for (polygon, &sel) in polygons.iter().zip(&is_selected) {
+            if sel {
+                new_positions.push(center(polygon, &new_positions));
+                let c = new_positions.len() as u32 - 1;
+                let n = polygon.len();
+                for i in 0..n {
+                    let prev = polygon[(i + n - 1) % n];
+                    let (v, next) = (polygon[i], polygon[(i + 1) % n]);
+                    next_polygons.push(vec![
+                        v,
+                        midpoint(v, next).unwrap(),
+                        c,
+                        midpoint(prev, v).unwrap(),
+                    ]);
+                    next_selected.push(true);
+                }
+            } else {
+                // Insert the midpoints of any edges shared with selected faces
+                let mut stitched = vec![];
+                for (&a, &b) in polygon.iter().circular_tuple_windows() {
+                    stitched.push(a);
+                    stitched.extend(midpoint(a, b));
+                }
+                if stitched.len() == polygon.len() {
+                    next_polygons.push(polygon.clone());
+                    next_selected.push(false);
+                } else {
+                    new_positions.push(center(polygon, &new_positions));
+                    let c = new_positions.len() as u32 - 1;
+                    for (&a, &b) in stitched.iter().circular_tuple_windows() {
+                        next_polygons.push(vec![a, b, c]);
+                        next_selected.push(false);
+                    }
+                }
+            }
+        }
+        check_element_limit(new_positions.len(), "vertices")?;
+        check_element_limit(next_polygons.len(), "faces")?;
+        polygons = next_polygons;
+        is_selected = next_selected;
+        progress.set_fraction((i + 1) as f32 / iterations as f32);
+    }
+
+    HalfEdgeMesh::build_from_polygons(&new_positions, &polygons)
+}
+
+/// How the normals of the faces around a vertex are weighted when computing
+/// its normal in [`compute_vertex_normals`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalWeighting {
+    /// All faces contribute equally
+    Uniform,
+    /// Faces contribute proportionally to their area
+    Area,
+    /// Faces contribute proportionally to the angle of their corner at the
+    /// vertex. This is the default, since it doesn't depend on how the
+    /// surface is tessellated.
+    Angle,
+}
+
+impl Default for NormalWeighting {
+    fn default() -> Self {
+        NormalWeighting::Angle
+    }
+}
+
+/// Computes a normal for each vertex, as the weighted average of the normals
+/// of its adjacent faces, and writes it into the `normal` vertex channel.
+/// Boundary vertices only average the faces they have, so their normal is
+/// always defined. Vertices without any faces get a zero normal. Returns the
+/// id of the written channel.
+pub fn compute_vertex_normals(
+    mesh: &mut HalfEdgeMesh,
+    weighting: NormalWeighting,
+) -> Result<ChannelId<VertexId, Vec3>> {
+    let ch_id = mesh
+        .channels
+        .ensure_channel::<VertexId, Vec3>(super::wavefront_obj::NORMAL_CHANNEL);
+
+    let conn = mesh.read_connectivity();
+    let positions = mesh.read_positions();
+
+    let mut normals: HashMap<VertexId, Vec3> = HashMap::new();
+    for (face, _) in conn.iter_faces() {
+        let vertices = conn.face_vertices(face);
+        let area_normal = conn.face_area_normal(&positions, face);
+        let unit_normal = area_normal.normalize_or_zero();
+
+        for (&prev, &v, &next) in vertices.iter().circular_tuple_windows() {
+            let contribution = match weighting {
+                NormalWeighting::Uniform => unit_normal,
+                NormalWeighting::Area => area_normal * 0.5,
+                NormalWeighting::Angle => {
+                    let angle = (positions[prev] - positions[v])
+                        .angle_between(positions[next] - positions[v]);
+                    if angle.is_finite() {
+                        unit_normal * angle
+                    } else {
+                        Vec3::ZERO
+                    }
+                }
+            };
+            *normals.entry(v).or_default() += contribution;
+        }
+    }
+
+    let mut normal_ch = mesh.channels.write_channel(ch_id)?;
+    for (v, _) in conn.iter_vertices() {
+        normal_ch[v] = normals
+            .get(&v)
+            .copied()
+            .unwrap_or(Vec3::ZERO)
+            .normalize_or_zero();
+    }
+
+    Ok(ch_id)
+}
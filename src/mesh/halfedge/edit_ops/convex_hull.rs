@@ -0,0 +1,93 @@
+use crate::prelude::*;
+
+/// Returns the index of the point in `points` that maximizes `f`
+fn farthest_by(points: &[Vec3], f: impl Fn(Vec3) -> f32) -> (usize, f32) {
+    points
+        .iter()
+        .map(|p| f(*p))
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |best, (i, d)| {
+            if d > best.1 {
+                (i, d)
+            } else {
+                best
+            }
+        })
+}
+
+/// Computes the convex hull of a set of points, as a closed triangle mesh with
+/// outward-facing normals. Points that are not on the hull are discarded.
+///
+/// Returns an error if the points don't span a volume, i.e. when there are
+/// fewer than four points or they are all coplanar.
+pub fn convex_hull(points: &[Vec3]) -> Result<HalfEdgeMesh> {
+    if points.len() < 4 {
+        bail!(
+            "The convex hull needs at least 4 points, but got {}",
+            points.len()
+        );
+    }
+    check_element_limit(points.len(), "vertices")?;
+
+    // Tolerance relative to the size of the point cloud
+    let (min, max) = points.iter().fold(
+        (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+        |(min, max), p| (min.min(*p), max.max(*p)),
+    );
+    let eps = (max - min).length() * 1e-5;
+
+    // Initial tetrahedron, built from extreme points
+    let (i0, _) = farthest_by(points, |p| -p.x);
+    let p0 = points[i0];
+    let (i1, d1) = farthest_by(points, |p| p.distance(p0));
+    if d1 <= eps {
+        bail!("Cannot compute the convex hull: all the points are coincident");
+    }
+    let p1 = points[i1];
+    let dir = (p1 - p0).normalize();
+    let (i2, d2) = farthest_by(points, |p| (p - p0).cross(dir).length());
+    if d2 <= eps {
+        bail!("Cannot compute the convex hull: all the points are collinear");
+    }
+    let p2 = points[i2];
+    let plane_normal = (p1 - p0).cross(p2 - p0).normalize();
+    let (i3, d3) = farthest_by(points, |p| (p - p0).dot(plane_normal).abs());
+    if d3 <= eps {
+        bail!("Cannot compute the convex hull: all the points are coplanar");
+    }
+
+    let mut faces: Vec<[usize; 3]> = if (points[i3] - p0).dot(plane_normal) > 0.0 {
+        vec![[i0, i2, i1], [i0, i1, i3], [i1, i2, i3], [i2, i0, i3]]
+    } else {
+        vec![[i0, i1, i2], [i0, i3, i1], [i1, i3, i2], [i2, i3, i0]]
+    };
+
+    // Signed distance from `p` to the plane of `face`, positive outside.
+    let distance = |face: &[usize; 3], p: Vec3| {
+        let [a, b, c] = face.map(|i| points[i]);
+        (b - a).cross(c - a).normalize_or_zero().dot(p - a)
+    };
+
+    // Incrementally add each point outside the current hull. The faces the
+    // point can see are removed, and the hole is closed with a fan of faces
+    // connecting the point to the horizon.
+    for (i, &p) in points.iter().enumerate() {
+        let (visible, kept): (Vec<[usize; 3]>, Vec<[usize; 3]>) =
+            faces.into_iter().partition(|f| distance(f, p) > eps);
+        faces = kept;
+        if visible.is_empty() {
+            continue;
+        }
+        let edges: HashSet<(usize, usize)> = visible
+            .iter()
+            .flat_map(|[a, b, c]| [(*a, *b), (*b, *c), (*c, *a)])
+            .collect();
+        for &(a, b) in &edges {
+            if !edges.contains(&(b, a)) {
+                faces.push([a, b, i]);
+            }
+        }
+    }
+
+    HalfEdgeMesh::build_from_polygons(points, &faces)
+}
@@ -1,5 +1,15 @@
 use super::*;
 
+/// The color used to draw highlighted elements, such as the elements resolved
+/// by a selection, in the viewport.
+pub const HIGHLIGHT_COLOR: Vec3 = glam::const_vec3!([1.0, 0.55, 0.1]);
+
+/// The color used to draw the face normal debug lines in the viewport.
+pub const FACE_NORMAL_COLOR: Vec3 = glam::const_vec3!([0.0, 0.9, 0.9]);
+
+/// The color used to draw the bounding box of the mesh in the viewport.
+pub const BOUNDS_COLOR: Vec3 = glam::const_vec3!([0.9, 0.9, 0.2]);
+
 /// The main representation to draw the halfedge's faces as triangles on the GPU
 /// This is suitable to be rendered with `wgpu::PrimitiveTopology::TriangleList`
 #[derive(Clone, Debug)]
@@ -31,6 +41,15 @@ pub struct LineBuffers {
     pub colors: Vec<Vec3>,
 }
 
+/// This representation is used to draw the mesh faces with the colors stored
+/// in the vertex color channel. Triangles are not indexed, so each group of
+/// three consecutive elements forms a triangle.
+pub struct VertexColorBuffers {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub colors: Vec<Vec3>,
+}
+
 /// This representation is used to draw highlighted flat triangles over a base
 /// mesh. It is used to draw a selection of faces.
 pub struct FaceOverlayBuffers {
@@ -79,9 +98,18 @@ impl HalfEdgeMesh {
         }
     }
 
+    /// Generates the [`VertexIndexBuffers`] for this mesh, with one normal per
+    /// vertex. Normals are read from the vertex normal channel when the mesh
+    /// has one, e.g. after computing normals or importing an OBJ file.
+    /// Otherwise, or for vertices with a zero normal, the normals of the
+    /// adjacent faces are averaged.
     pub fn generate_triangle_buffers_smooth(&self) -> Result<VertexIndexBuffers> {
         let positions_ch = self.read_positions();
         let conn = self.read_connectivity();
+        let normals_ch = self
+            .channels
+            .read_channel_by_name::<VertexId, Vec3>(wavefront_obj::NORMAL_CHANNEL)
+            .ok();
 
         let mut v_id_to_idx =
             slotmap::SecondaryMap::<VertexId, u32>::with_capacity(conn.vertices.capacity());
@@ -94,6 +122,15 @@ impl HalfEdgeMesh {
                 v_id_to_idx.insert(id, idx as u32);
                 positions.push(pos);
 
+                if let Some(normal) = normals_ch
+                    .as_ref()
+                    .map(|ch| ch[id].normalize_or_zero())
+                    .filter(|n| *n != Vec3::ZERO)
+                {
+                    normals.push(normal);
+                    return Ok(());
+                }
+
                 let adjacent_faces = conn.at_vertex(id).adjacent_faces()?;
                 let mut normal = Vec3::ZERO;
                 for face in adjacent_faces.iter_cpy() {
@@ -121,31 +158,87 @@ impl HalfEdgeMesh {
         })
     }
 
-    pub fn generate_face_overlay_buffers(&self) -> FaceOverlayBuffers {
+    /// Returns a function giving the color of each vertex. Colors are read
+    /// from `colors` when given, or from the vertex color channel otherwise.
+    /// Returns `None` when there are no colors to read.
+    fn vertex_colors<'a>(
+        &'a self,
+        colors: Option<&'a slotmap::SecondaryMap<VertexId, Vec3>>,
+    ) -> Option<Box<dyn Fn(VertexId) -> Vec3 + 'a>> {
+        match colors {
+            Some(colors) => Some(Box::new(move |v: VertexId| {
+                colors.get(v).copied().unwrap_or(Vec3::ONE)
+            })),
+            None => {
+                let colors_ch = self
+                    .channels
+                    .read_channel_by_name::<VertexId, Vec3>(VERTEX_COLOR_CHANNEL)
+                    .ok()?;
+                Some(Box::new(move |v: VertexId| colors_ch[v]))
+            }
+        }
+    }
+
+    /// Generates the [`VertexColorBuffers`] for this mesh. Vertex colors are
+    /// taken from `colors` when given, instead of the vertex color channel.
+    /// Vertices are drawn white when they have no color.
+    pub fn generate_vertex_color_buffers(
+        &self,
+        colors: Option<&slotmap::SecondaryMap<VertexId, Vec3>>,
+    ) -> VertexColorBuffers {
         let positions_ch = self.read_positions();
         let conn = self.read_connectivity();
+        let color = self.vertex_colors(colors);
 
         let mut positions = vec![];
+        let mut normals = vec![];
         let mut colors = vec![];
 
-        for (_, (face_id, _face)) in conn.faces.iter().enumerate() {
-            // TODO: Add criteria to select highlighted faces
-            if false {
-                let vertices = conn.face_vertices(face_id);
-                let v1 = vertices[0];
-                for (&v2, &v3) in vertices[1..].iter().tuple_windows() {
-                    let v1_pos = positions_ch[v1];
-                    let v2_pos = positions_ch[v2];
-                    let v3_pos = positions_ch[v3];
-
-                    positions.push(v1_pos);
-                    positions.push(v2_pos);
-                    positions.push(v3_pos);
-                    colors.push(Vec3::new(0.2, 0.8, 0.2));
+        for (face_id, _face) in conn.faces.iter() {
+            let normal = conn
+                .face_normal(&positions_ch, face_id)
+                .unwrap_or(Vec3::ZERO);
+            let vertices = conn.face_vertices(face_id);
+            let v1 = vertices[0];
+            for (&v2, &v3) in vertices[1..].iter().tuple_windows() {
+                for v in [v1, v2, v3] {
+                    positions.push(positions_ch[v]);
+                    normals.push(normal);
+                    colors.push(color.as_ref().map(|color| color(v)).unwrap_or(Vec3::ONE));
                 }
             }
         }
 
+        VertexColorBuffers {
+            positions,
+            normals,
+            colors,
+        }
+    }
+
+    /// Generates the [`FaceOverlayBuffers`] to highlight the given faces.
+    pub fn generate_face_overlay_buffers(&self, highlighted: &[FaceId]) -> FaceOverlayBuffers {
+        let positions_ch = self.read_positions();
+        let conn = self.read_connectivity();
+
+        let mut positions = vec![];
+        let mut colors = vec![];
+
+        for &face_id in highlighted {
+            let vertices = conn.face_vertices(face_id);
+            let v1 = vertices[0];
+            for (&v2, &v3) in vertices[1..].iter().tuple_windows() {
+                let v1_pos = positions_ch[v1];
+                let v2_pos = positions_ch[v2];
+                let v3_pos = positions_ch[v3];
+
+                positions.push(v1_pos);
+                positions.push(v2_pos);
+                positions.push(v3_pos);
+                colors.push(HIGHLIGHT_COLOR);
+            }
+        }
+
         FaceOverlayBuffers { positions, colors }
     }
 
@@ -162,8 +255,124 @@ impl HalfEdgeMesh {
         PointBuffers { positions }
     }
 
+    /// Returns the vertex colors of this mesh, in the same order as the
+    /// positions returned by [`Self::generate_point_buffers`]. Colors are
+    /// taken from `colors` when given, instead of the vertex color channel.
+    /// Returns `None` when there are no colors to use.
+    pub fn generate_point_colors(
+        &self,
+        colors: Option<&slotmap::SecondaryMap<VertexId, Vec3>>,
+    ) -> Option<Vec<Vec3>> {
+        let color = self.vertex_colors(colors)?;
+        Some(
+            self.read_connectivity()
+                .iter_vertices()
+                .map(|(v, _)| color(v))
+                .collect(),
+        )
+    }
+
+    /// Generates the [`PointBuffers`] to highlight the given vertices.
+    pub fn generate_highlighted_point_buffers(&self, highlighted: &[VertexId]) -> PointBuffers {
+        let positions_ch = self.read_positions();
+        PointBuffers {
+            positions: highlighted.iter().map(|&v| positions_ch[v]).collect(),
+        }
+    }
+
+    /// Generates the [`LineBuffers`] to highlight the given halfedges. Each
+    /// halfedge is drawn as a line along its full edge.
+    pub fn generate_highlighted_line_buffers(
+        &self,
+        highlighted: &[HalfEdgeId],
+    ) -> Result<LineBuffers> {
+        let positions_ch = self.read_positions();
+        let conn = self.read_connectivity();
+
+        let mut positions = Vec::new();
+        let mut colors = Vec::new();
+        for &h in highlighted {
+            let (src, dst) = conn.at_halfedge(h).src_dst_pair()?;
+            positions.push(positions_ch[src]);
+            positions.push(positions_ch[dst]);
+            colors.push(HIGHLIGHT_COLOR);
+        }
+
+        Ok(LineBuffers { colors, positions })
+    }
+
+    /// Returns the color used to draw the given halfedge in the wireframe.
+    /// Debug edges use their debug color. Otherwise, the color is read from
+    /// the edge color channel when the mesh has one, or defaults to white.
+    fn edge_color(
+        &self,
+        conn: &MeshConnectivity,
+        edge_colors_ch: Option<&Channel<HalfEdgeId, Vec3>>,
+        h: HalfEdgeId,
+    ) -> Vec3 {
+        if let Some(dbg_edge) = conn.debug_edges.get(&h) {
+            glam::Vec3::new(
+                dbg_edge.color.r() as f32 / 255.0,
+                dbg_edge.color.g() as f32 / 255.0,
+                dbg_edge.color.b() as f32 / 255.0,
+            )
+        } else if let Some(color) = edge_colors_ch.and_then(|ch| ch.get(h)) {
+            *color
+        } else {
+            Vec3::splat(1.0)
+        }
+    }
+
+    /// Generates [`LineBuffers`] with the 12 edges of the axis-aligned
+    /// bounding box of this mesh. The buffers are empty for an empty mesh.
+    pub fn generate_bounding_box_buffers(&self) -> LineBuffers {
+        let mut positions = vec![];
+        let mut colors = vec![];
+        if let Some((min, max)) = self.bounding_box() {
+            let corner = |i: usize| {
+                Vec3::new(
+                    if i & 1 == 0 { min.x } else { max.x },
+                    if i & 2 == 0 { min.y } else { max.y },
+                    if i & 4 == 0 { min.z } else { max.z },
+                )
+            };
+            // Each edge links two corners whose index differs by a single bit
+            for i in 0..8 {
+                for bit in [1, 2, 4] {
+                    if i & bit == 0 {
+                        positions.push(corner(i));
+                        positions.push(corner(i | bit));
+                        colors.push(BOUNDS_COLOR);
+                    }
+                }
+            }
+        }
+        LineBuffers { positions, colors }
+    }
+
+    /// Generates [`LineBuffers`] with one line per face, going from the face
+    /// centroid along the face normal, with the given `length`. Used to debug
+    /// face winding. Degenerate faces, with no normal, are skipped.
+    pub fn generate_face_normal_buffers(&self, length: f32) -> LineBuffers {
+        let positions_ch = self.read_positions();
+        let conn = self.read_connectivity();
+
+        let mut positions = vec![];
+        let mut colors = vec![];
+        for (face_id, _face) in conn.iter_faces() {
+            if let Some(normal) = conn.face_normal(&positions_ch, face_id) {
+                let centroid = conn.face_vertex_average(&positions_ch, face_id);
+                positions.push(centroid);
+                positions.push(centroid + normal * length);
+                colors.push(FACE_NORMAL_COLOR);
+            }
+        }
+
+        LineBuffers { positions, colors }
+    }
+
     /// Generates the [`LineBuffers`] for this mesh. Suitable to be uploaded to
-    /// the GPU.
+    /// the GPU. Edges are colored using the edge color channel, if any.
     ///
     /// # Panics
     /// This method panics if the mesh is malformed:
@@ -172,6 +381,10 @@ impl HalfEdgeMesh {
     pub fn generate_line_buffers(&self) -> Result<LineBuffers> {
         let positions_ch = self.read_positions();
         let conn = self.read_connectivity();
+        let edge_colors_ch = self
+            .channels
+            .read_channel_by_name::<HalfEdgeId, Vec3>(EDGE_COLOR_CHANNEL)
+            .ok();
 
         let mut visited = HashSet::new();
         let mut positions = Vec::new();
@@ -194,16 +407,7 @@ impl HalfEdgeMesh {
             positions.push(positions_ch[src]);
             positions.push(positions_ch[dst]);
 
-            if let Some(dbg_edge) = conn.debug_edges.get(&h) {
-                let color = glam::Vec3::new(
-                    dbg_edge.color.r() as f32 / 255.0,
-                    dbg_edge.color.g() as f32 / 255.0,
-                    dbg_edge.color.b() as f32 / 255.0,
-                );
-                colors.push(color)
-            } else {
-                colors.push(Vec3::splat(1.0))
-            }
+            colors.push(self.edge_color(&conn, edge_colors_ch.as_deref(), h));
         }
 
         Ok(LineBuffers { colors, positions })
@@ -215,6 +419,10 @@ impl HalfEdgeMesh {
     pub fn generate_halfedge_arrow_buffers(&self) -> Result<LineBuffers> {
         let positions_ch = self.read_positions();
         let conn = self.read_connectivity();
+        let edge_colors_ch = self
+            .channels
+            .read_channel_by_name::<HalfEdgeId, Vec3>(EDGE_COLOR_CHANNEL)
+            .ok();
 
         let mut colors = vec![];
         let mut positions = vec![];
@@ -264,18 +472,9 @@ impl HalfEdgeMesh {
                 dst_pos + 0.30 * edge_length * tangent.lerp(-bitangent, 2.0 / 3.0),
             ]);
 
-            if let Some(dbg_edge) = conn.debug_edges.get(&h) {
-                let color = glam::Vec3::new(
-                    dbg_edge.color.r() as f32 / 255.0,
-                    dbg_edge.color.g() as f32 / 255.0,
-                    dbg_edge.color.b() as f32 / 255.0,
-                );
-                colors.push(color);
-                colors.push(color);
-            } else {
-                colors.push(Vec3::splat(1.0));
-                colors.push(Vec3::splat(1.0));
-            }
+            let color = self.edge_color(&conn, edge_colors_ch.as_deref(), h);
+            colors.push(color);
+            colors.push(color);
         }
 
         Ok(LineBuffers { colors, positions })
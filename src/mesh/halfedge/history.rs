@@ -0,0 +1,57 @@
+use crate::prelude::*;
+
+use super::selection::SelectionExpression;
+
+/// An argument of an operation stored in a [`MeshHistory`]. Only the kinds of
+/// values accepted by the mesh operations can be recorded.
+#[derive(Clone, Debug)]
+pub enum HistoryValue {
+    Nil,
+    Bool(bool),
+    Integer(i64),
+    Number(f64),
+    String(String),
+    Vec3(Vec3),
+    Selection(SelectionExpression),
+    /// Some operations take other meshes as arguments, like `merge`. A copy of
+    /// the mesh, as it was when the operation was applied, is stored.
+    Mesh(HalfEdgeMesh),
+}
+
+/// A single operation applied to a mesh.
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    /// The name of the operation, as found in the `Ops` Lua table
+    pub op: String,
+    /// The arguments the operation was called with, except for the mesh it
+    /// was applied to
+    pub args: Vec<HistoryValue>,
+}
+
+/// The journal of the in-place operations applied to a mesh, in order. Meshes
+/// only keep a history when it has been enabled for them, which can only be
+/// done while they are still empty.
+///
+/// The operations are recorded on the Lua side, when they are called through
+/// the `Ops` table. Only in-place operations are recorded: meshes created by
+/// primitives or by operations returning a new mesh enter the history through
+/// `Ops.merge`, which stores a copy of them. Replaying the history on an empty
+/// mesh then reproduces the original mesh.
+#[derive(Clone, Debug, Default)]
+pub struct MeshHistory {
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl MeshHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a new operation to the end of the history
+    pub fn record(&mut self, op: impl Into<String>, args: Vec<HistoryValue>) {
+        self.entries.push(HistoryEntry {
+            op: op.into(),
+            args,
+        });
+    }
+}
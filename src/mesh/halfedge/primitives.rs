@@ -63,3 +63,222 @@ impl Quad {
             .expect("Quad construction should not fail")
     }
 }
+
+pub struct UvSphere;
+impl UvSphere {
+    /// Builds a latitude / longitude sphere. The mesh has `rings` horizontal
+    /// bands and `segments` vertical slices. The bands at the poles are made
+    /// of triangles, and the rest of quads.
+    pub fn build(center: Vec3, radius: f32, rings: u32, segments: u32) -> Result<HalfEdgeMesh> {
+        if rings < 3 || segments < 3 {
+            bail!(
+                "A UV sphere needs at least 3 rings and 3 segments, got {} and {}",
+                rings,
+                segments
+            );
+        }
+        let (rings_n, segments_n) = (rings as usize, segments as usize);
+        check_element_limit((rings_n - 1) * segments_n + 2, "vertices")?;
+        check_element_limit(rings_n * segments_n, "faces")?;
+
+        let mut positions = vec![center + Vec3::Y * radius];
+        for ring in 1..rings {
+            let theta = std::f32::consts::PI * ring as f32 / rings as f32;
+            for segment in 0..segments {
+                let phi = std::f32::consts::TAU * segment as f32 / segments as f32;
+                positions.push(
+                    center
+                        + radius
+                            * Vec3::new(
+                                theta.sin() * phi.cos(),
+                                theta.cos(),
+                                theta.sin() * phi.sin(),
+                            ),
+                );
+            }
+        }
+        let bottom_pole = positions.len() as u32;
+        positions.push(center - Vec3::Y * radius);
+
+        // Index of the vertex at the given segment of the given ring. Rings
+        // are numbered starting at 1, the top pole is not part of any ring.
+        let vertex = |ring: u32, segment: u32| 1 + (ring - 1) * segments + segment % segments;
+
+        let mut polygons: Vec<Vec<u32>> = vec![];
+        for segment in 0..segments {
+            polygons.push(vec![0, vertex(1, segment + 1), vertex(1, segment)]);
+        }
+        for ring in 1..rings - 1 {
+            for segment in 0..segments {
+                polygons.push(vec![
+                    vertex(ring, segment),
+                    vertex(ring, segment + 1),
+                    vertex(ring + 1, segment + 1),
+                    vertex(ring + 1, segment),
+                ]);
+            }
+        }
+        for segment in 0..segments {
+            polygons.push(vec![
+                vertex(rings - 1, segment),
+                vertex(rings - 1, segment + 1),
+                bottom_pole,
+            ]);
+        }
+
+        HalfEdgeMesh::build_from_polygons(&positions, &polygons)
+    }
+}
+
+pub struct Icosphere;
+impl Icosphere {
+    /// Builds a sphere by subdividing an icosahedron `subdivisions` times.
+    /// Each subdivision splits every triangle in four, and new vertices are
+    /// projected onto the sphere, so all triangles have roughly the same size.
+    pub fn build(center: Vec3, radius: f32, subdivisions: u32) -> Result<HalfEdgeMesh> {
+        let num_faces = 4usize
+            .checked_pow(subdivisions)
+            .and_then(|n| n.checked_mul(20))
+            .unwrap_or(usize::MAX);
+        check_element_limit(num_faces, "faces")?;
+
+        let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+        let mut positions = [
+            Vec3::new(-1.0, t, 0.0),
+            Vec3::new(1.0, t, 0.0),
+            Vec3::new(-1.0, -t, 0.0),
+            Vec3::new(1.0, -t, 0.0),
+            Vec3::new(0.0, -1.0, t),
+            Vec3::new(0.0, 1.0, t),
+            Vec3::new(0.0, -1.0, -t),
+            Vec3::new(0.0, 1.0, -t),
+            Vec3::new(t, 0.0, -1.0),
+            Vec3::new(t, 0.0, 1.0),
+            Vec3::new(-t, 0.0, -1.0),
+            Vec3::new(-t, 0.0, 1.0),
+        ]
+        .iter()
+        .map(|p| p.normalize())
+        .collect_vec();
+        let mut triangles: Vec<[u32; 3]> = vec![
+            [0, 11, 5],
+            [0, 5, 1],
+            [0, 1, 7],
+            [0, 7, 10],
+            [0, 10, 11],
+            [1, 5, 9],
+            [5, 11, 4],
+            [11, 10, 2],
+            [10, 7, 6],
+            [7, 1, 8],
+            [3, 9, 4],
+            [3, 4, 2],
+            [3, 2, 6],
+            [3, 6, 8],
+            [3, 8, 9],
+            [4, 9, 5],
+            [2, 4, 11],
+            [6, 2, 10],
+            [8, 6, 7],
+            [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            // Maps each edge, as a sorted pair of vertices, to its midpoint.
+            // Shared edges get a single midpoint vertex.
+            let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+            let mut midpoint = |a: u32, b: u32| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    positions
+                        .push(((positions[a as usize] + positions[b as usize]) * 0.5).normalize());
+                    positions.len() as u32 - 1
+                })
+            };
+            triangles = triangles
+                .iter()
+                .flat_map(|&[a, b, c]| {
+                    let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        let positions = positions.iter().map(|p| center + *p * radius).collect_vec();
+        HalfEdgeMesh::build_from_polygons(&positions, &triangles)
+    }
+}
+
+/// Returns `segments` points evenly spaced on a horizontal circle
+fn circle_points(center: Vec3, radius: f32, segments: u32) -> impl Iterator<Item = Vec3> {
+    (0..segments).map(move |segment| {
+        let phi = std::f32::consts::TAU * segment as f32 / segments as f32;
+        center + radius * Vec3::new(phi.cos(), 0.0, phi.sin())
+    })
+}
+
+pub struct Cylinder;
+impl Cylinder {
+    /// Builds a cylinder aligned with the Y axis. The sides are made of quads
+    /// and both ends are capped with an n-gon.
+    pub fn build(center: Vec3, radius: f32, height: f32, segments: u32) -> Result<HalfEdgeMesh> {
+        if segments < 3 {
+            bail!("A cylinder needs at least 3 segments, got {}", segments);
+        }
+        check_element_limit(segments as usize * 2, "vertices")?;
+        check_element_limit(segments as usize + 2, "faces")?;
+        let half_height = Vec3::Y * height * 0.5;
+        let positions = circle_points(center - half_height, radius, segments)
+            .chain(circle_points(center + half_height, radius, segments))
+            .collect_vec();
+
+        let bottom = |segment: u32| segment % segments;
+        let top = |segment: u32| segments + segment % segments;
+
+        let mut polygons: Vec<Vec<u32>> = vec![
+            (0..segments).map(bottom).collect(),
+            (0..segments).rev().map(top).collect(),
+        ];
+        for segment in 0..segments {
+            polygons.push(vec![
+                top(segment),
+                top(segment + 1),
+                bottom(segment + 1),
+                bottom(segment),
+            ]);
+        }
+
+        HalfEdgeMesh::build_from_polygons(&positions, &polygons)
+    }
+}
+
+pub struct Cone;
+impl Cone {
+    /// Builds a cone aligned with the Y axis, with its apex on top. The sides
+    /// are a fan of triangles and the base is an n-gon.
+    pub fn build(
+        center: Vec3,
+        base_radius: f32,
+        height: f32,
+        segments: u32,
+    ) -> Result<HalfEdgeMesh> {
+        if segments < 3 {
+            bail!("A cone needs at least 3 segments, got {}", segments);
+        }
+        check_element_limit(segments as usize + 1, "vertices")?;
+        check_element_limit(segments as usize + 1, "faces")?;
+        let half_height = Vec3::Y * height * 0.5;
+        let mut positions =
+            circle_points(center - half_height, base_radius, segments).collect_vec();
+        let apex = positions.len() as u32;
+        positions.push(center + half_height);
+
+        let base = |segment: u32| segment % segments;
+
+        let mut polygons: Vec<Vec<u32>> = vec![(0..segments).map(base).collect()];
+        for segment in 0..segments {
+            polygons.push(vec![apex, base(segment + 1), base(segment)]);
+        }
+
+        HalfEdgeMesh::build_from_polygons(&positions, &polygons)
+    }
+}
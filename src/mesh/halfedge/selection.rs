@@ -17,6 +17,7 @@ pub enum SelectionExpression {
     Explicit(Vec<SelectionFragment>),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SelectionKind {
     Vertices,
     Faces,
@@ -186,12 +187,514 @@ impl MeshConnectivity {
             ResolvedSelection::Explicit(v) => v,
         }
     }
+
+    fn selection_from_ids<T: slotmap::Key, U>(
+        data: &SlotMap<T, U>,
+        ids: &[T],
+    ) -> SelectionExpression {
+        let ids: HashSet<T> = ids.iter().copied().collect();
+        let fragments = data
+            .iter()
+            .enumerate()
+            .filter(|(_, (id, _))| ids.contains(id))
+            .map(|(i, _)| SelectionFragment::Single(i as u32))
+            .collect_vec();
+        if fragments.is_empty() {
+            SelectionExpression::None
+        } else {
+            SelectionExpression::Explicit(fragments)
+        }
+    }
+
+    /// Returns a selection expression that selects exactly the given faces.
+    /// This is the inverse of [`MeshConnectivity::resolve_face_selection_full`].
+    pub fn face_selection_from_ids(&self, ids: &[FaceId]) -> SelectionExpression {
+        Self::selection_from_ids(&self.faces, ids)
+    }
+
+    /// Returns a selection expression that selects exactly the given vertices.
+    pub fn vertex_selection_from_ids(&self, ids: &[VertexId]) -> SelectionExpression {
+        Self::selection_from_ids(&self.vertices, ids)
+    }
+
+    /// Returns a selection expression that selects exactly the given halfedges.
+    pub fn halfedge_selection_from_ids(&self, ids: &[HalfEdgeId]) -> SelectionExpression {
+        Self::selection_from_ids(&self.halfedges, ids)
+    }
+
+    /// Returns the vertices connected to `v` by an edge
+    fn vertex_neighbors(&self, v: VertexId) -> Result<SVec<VertexId>> {
+        self.at_vertex(v)
+            .outgoing_halfedges()?
+            .iter()
+            .map(|h| Ok(self.at_halfedge(*h).dst_vertex().try_end()?))
+            .collect()
+    }
+
+    /// Grows the vertex selection by one ring, adding the neighbors of all
+    /// selected vertices.
+    pub fn grow_vertex_selection(&self, verts: &[VertexId]) -> Result<Vec<VertexId>> {
+        let mut selected: HashSet<VertexId> = verts.iter().copied().collect();
+        for &v in verts {
+            selected.extend(self.vertex_neighbors(v)?);
+        }
+        Ok(self
+            .iter_vertices()
+            .map(|(v, _)| v)
+            .filter(|v| selected.contains(v))
+            .collect())
+    }
+
+    /// Shrinks the vertex selection by one ring, removing every vertex with
+    /// an unselected neighbor.
+    pub fn shrink_vertex_selection(&self, verts: &[VertexId]) -> Result<Vec<VertexId>> {
+        let selected: HashSet<VertexId> = verts.iter().copied().collect();
+        let mut result = vec![];
+        for &v in verts {
+            if self
+                .vertex_neighbors(v)?
+                .iter()
+                .all(|w| selected.contains(w))
+            {
+                result.push(v);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Grows the face selection by one ring, adding all the faces that share
+    /// a vertex with a selected face.
+    pub fn grow_face_selection(&self, faces: &[FaceId]) -> Result<Vec<FaceId>> {
+        let mut selected: HashSet<FaceId> = faces.iter().copied().collect();
+        for &f in faces {
+            for v in self.at_face(f).vertices()? {
+                selected.extend(self.at_vertex(v).adjacent_faces()?);
+            }
+        }
+        Ok(self
+            .iter_faces()
+            .map(|(f, _)| f)
+            .filter(|f| selected.contains(f))
+            .collect())
+    }
+
+    /// Shrinks the face selection by one ring, removing every face that
+    /// shares a vertex with an unselected face.
+    pub fn shrink_face_selection(&self, faces: &[FaceId]) -> Result<Vec<FaceId>> {
+        let selected: HashSet<FaceId> = faces.iter().copied().collect();
+        let mut result = vec![];
+        'faces: for &f in faces {
+            for v in self.at_face(f).vertices()? {
+                for neighbor in self.at_vertex(v).adjacent_faces()? {
+                    if !selected.contains(&neighbor) {
+                        continue 'faces;
+                    }
+                }
+            }
+            result.push(f);
+        }
+        Ok(result)
+    }
+
+    /// Returns all the halfedges on the boundary of the mesh. That is, the
+    /// halfedges that belong to a face but whose twin has no face. Halfedges
+    /// without a twin are also considered to be on the boundary.
+    pub fn boundary_halfedges(&self) -> Vec<HalfEdgeId> {
+        self.iter_halfedges()
+            .filter(|(_, h)| h.face.is_some())
+            .filter(|(_, h)| match h.twin {
+                Some(twin) => self[twin].face.is_none(),
+                None => true,
+            })
+            .map(|(h, _)| h)
+            .collect()
+    }
+}
+
+/// Returns all the vertices that are connected to any of the `seeds` by a path
+/// of edges. That is, the connected components containing the seeds.
+pub fn select_linked(mesh: &MeshConnectivity, seeds: &[VertexId]) -> Result<Vec<VertexId>> {
+    let mut visited = HashSet::<VertexId>::new();
+    let mut result = vec![];
+    let mut stack = seeds.to_vec();
+    while let Some(v) = stack.pop() {
+        if !visited.insert(v) {
+            continue;
+        }
+        result.push(v);
+        for h in mesh.at_vertex(v).outgoing_halfedges()? {
+            let dst = mesh.at_halfedge(h).dst_vertex().try_end()?;
+            if !visited.contains(&dst) {
+                stack.push(dst);
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Returns all the faces in the connected components containing any of the
+/// `seeds`.
+pub fn select_linked_faces(mesh: &MeshConnectivity, seeds: &[FaceId]) -> Result<Vec<FaceId>> {
+    let mut seed_vertices = vec![];
+    for &face in seeds {
+        seed_vertices.extend(mesh.at_face(face).vertices()?);
+    }
+    let linked: HashSet<VertexId> = select_linked(mesh, &seed_vertices)?.into_iter().collect();
+    let mut result = vec![];
+    for (face, _) in mesh.iter_faces() {
+        if mesh
+            .at_face(face)
+            .vertices()?
+            .iter()
+            .any(|v| linked.contains(v))
+        {
+            result.push(face);
+        }
+    }
+    Ok(result)
+}
+
+/// Returns the edge loop going through the edge of `start`. The loop is
+/// walked in both directions, crossing each vertex to the edge on the opposite
+/// side. The walk stops at vertices where the opposite edge is not defined,
+/// that is, vertices that don't have exactly four edges or are on a boundary.
+///
+/// Each edge in the loop is returned once, as the halfedge pointing in the
+/// same direction as `start`, ordered along the loop.
+pub fn edge_loop(mesh: &MeshConnectivity, start: HalfEdgeId) -> Result<Vec<HalfEdgeId>> {
+    // Returns the halfedge that continues the loop after `h`, if any
+    let continue_loop = |h: HalfEdgeId| -> Result<Option<HalfEdgeId>> {
+        let w = mesh.at_halfedge(h).dst_vertex().try_end()?;
+        let outgoing = mesh.at_vertex(w).outgoing_halfedges()?;
+        if outgoing.len() != 4 {
+            return Ok(None);
+        }
+        for &o in &outgoing {
+            let twin = mesh.at_halfedge(o).twin().try_end()?;
+            if mesh[o].face.is_none() || mesh[twin].face.is_none() {
+                return Ok(None);
+            }
+        }
+        Ok(Some(mesh.at_halfedge(h).next().twin().next().try_end()?))
+    };
+
+    let mut visited = HashSet::new();
+    visited.insert(start);
+    let mut forward = vec![start];
+    let mut h = start;
+    while let Some(next) = continue_loop(h)? {
+        if !visited.insert(next) {
+            // Closed loop
+            return Ok(forward);
+        }
+        forward.push(next);
+        h = next;
+    }
+
+    // The loop is open, so it also extends backwards from the start
+    let mut backward = vec![];
+    let mut h = mesh.at_halfedge(start).twin().try_end()?;
+    while let Some(next) = continue_loop(h)? {
+        let next_twin = mesh.at_halfedge(next).twin().try_end()?;
+        if !visited.insert(next_twin) {
+            break;
+        }
+        backward.push(next_twin);
+        h = next;
+    }
+    backward.reverse();
+    backward.extend(forward);
+    Ok(backward)
+}
+
+/// Returns all the faces whose normal is within `angle_degrees` of the given
+/// `direction`. Normals are computed from the positions of all the vertices of
+/// the face, so non-planar polygons use their average normal. Degenerate faces
+/// with zero area have no defined normal and are never selected.
+pub fn select_by_normal(
+    mesh: &MeshConnectivity,
+    positions: &Positions,
+    direction: Vec3,
+    angle_degrees: f32,
+) -> Result<Vec<FaceId>> {
+    let direction = direction.normalize_or_zero();
+    if direction == Vec3::ZERO {
+        bail!("Direction for select_by_normal cannot be zero");
+    }
+    let min_cos = angle_degrees.to_radians().cos();
+    let mut result = vec![];
+    for (face, _) in mesh.iter_faces() {
+        let normal = mesh.face_area_normal(positions, face).normalize_or_zero();
+        if normal != Vec3::ZERO && normal.dot(direction) >= min_cos {
+            result.push(face);
+        }
+    }
+    Ok(result)
+}
+
+/// One of the three coordinate axes. Used to define a [`SymmetryPlane`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymmetryAxis {
+    X,
+    Y,
+    Z,
+}
+
+/// A plane of symmetry, perpendicular to one of the coordinate axes and placed
+/// at `offset` units from the origin along that axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SymmetryPlane {
+    pub axis: SymmetryAxis,
+    pub offset: f32,
+}
+
+impl SymmetryPlane {
+    /// Returns the mirror image of `point` with respect to this plane.
+    pub fn reflect(&self, point: Vec3) -> Vec3 {
+        let mut point = point;
+        let coord = match self.axis {
+            SymmetryAxis::X => &mut point.x,
+            SymmetryAxis::Y => &mut point.y,
+            SymmetryAxis::Z => &mut point.z,
+        };
+        *coord = 2.0 * self.offset - *coord;
+        point
+    }
+}
+
+/// Positions closer than this distance are considered to be the same when
+/// matching elements with their mirror image.
+const SYMMETRY_TOLERANCE: f32 = 1e-4;
+
+fn quantize(point: Vec3) -> Vec3Ord {
+    (point / SYMMETRY_TOLERANCE).round().to_ord()
+}
+
+impl MeshConnectivity {
+    /// Returns a map from each vertex to the vertex at its mirror position.
+    /// Vertices without a mirror counterpart are not present in the map.
+    fn mirror_vertex_map(
+        &self,
+        positions: &Positions,
+        plane: SymmetryPlane,
+    ) -> HashMap<VertexId, VertexId> {
+        let by_position: HashMap<Vec3Ord, VertexId> = self
+            .iter_vertices()
+            .map(|(v, _)| (quantize(positions[v]), v))
+            .collect();
+        self.iter_vertices()
+            .filter_map(|(v, _)| {
+                by_position
+                    .get(&quantize(plane.reflect(positions[v])))
+                    .map(|&mirror| (v, mirror))
+            })
+            .collect()
+    }
+
+    /// Returns the given vertices, plus their mirror images.
+    pub fn mirror_vertex_selection(
+        &self,
+        positions: &Positions,
+        ids: &[VertexId],
+        plane: SymmetryPlane,
+    ) -> Vec<VertexId> {
+        let mirror = self.mirror_vertex_map(positions, plane);
+        ids.iter_cpy()
+            .chain(ids.iter().filter_map(|v| mirror.get(v).copied()))
+            .unique()
+            .collect()
+    }
+
+    /// Returns the given halfedges, plus their mirror images. Reflection flips
+    /// the orientation of faces, so the mirror of a halfedge going from `a` to
+    /// `b` goes from the mirror of `b` to the mirror of `a`.
+    pub fn mirror_halfedge_selection(
+        &self,
+        positions: &Positions,
+        ids: &[HalfEdgeId],
+        plane: SymmetryPlane,
+    ) -> Result<Vec<HalfEdgeId>> {
+        let mirror = self.mirror_vertex_map(positions, plane);
+        let mut by_endpoints = HashMap::new();
+        for (h, _) in self.iter_halfedges() {
+            by_endpoints.insert(self.at_halfedge(h).src_dst_pair()?, h);
+        }
+        let mut result = ids.to_vec();
+        for &h in ids {
+            let (src, dst) = self.at_halfedge(h).src_dst_pair()?;
+            if let (Some(&m_src), Some(&m_dst)) = (mirror.get(&src), mirror.get(&dst)) {
+                if let Some(&m_h) = by_endpoints.get(&(m_dst, m_src)) {
+                    result.push(m_h);
+                }
+            }
+        }
+        Ok(result.into_iter().unique().collect())
+    }
+
+    /// Returns the given faces, plus their mirror images.
+    pub fn mirror_face_selection(
+        &self,
+        positions: &Positions,
+        ids: &[FaceId],
+        plane: SymmetryPlane,
+    ) -> Result<Vec<FaceId>> {
+        let mirror = self.mirror_vertex_map(positions, plane);
+        let mut by_vertices = HashMap::new();
+        for (f, _) in self.iter_faces() {
+            let mut vertices = self.at_face(f).vertices()?.to_vec();
+            vertices.sort();
+            by_vertices.insert(vertices, f);
+        }
+        let mut result = ids.to_vec();
+        for &f in ids {
+            let mirrored: Option<Vec<VertexId>> = self
+                .at_face(f)
+                .vertices()?
+                .iter()
+                .map(|v| mirror.get(v).copied())
+                .collect();
+            if let Some(mut mirrored) = mirrored {
+                mirrored.sort();
+                if let Some(&m_f) = by_vertices.get(&mirrored) {
+                    result.push(m_f);
+                }
+            }
+        }
+        Ok(result.into_iter().unique().collect())
+    }
+}
+
+impl HalfEdgeMesh {
+    /// When this mesh has a symmetry plane, extends the vertex selection with
+    /// the mirror image of the vertices. Otherwise, returns it unchanged.
+    pub fn symmetric_vertex_selection(&self, ids: Vec<VertexId>) -> Vec<VertexId> {
+        match self.symmetry {
+            Some(plane) => self.read_connectivity().mirror_vertex_selection(
+                &self.read_positions(),
+                &ids,
+                plane,
+            ),
+            None => ids,
+        }
+    }
+
+    /// When this mesh has a symmetry plane, pairs each of the given vertices
+    /// with its mirror image. Mirror images that are part of `ids` are left
+    /// out, so every vertex shows up on at most one side of the pairs.
+    ///
+    /// Ops that move vertices around compute the pairs before moving them,
+    /// and then call [`HalfEdgeMesh::mirror_positions`].
+    pub fn symmetric_vertex_pairs(&self, ids: &[VertexId]) -> Vec<(VertexId, VertexId)> {
+        let plane = match self.symmetry {
+            Some(plane) => plane,
+            None => return vec![],
+        };
+        let mirror = self
+            .read_connectivity()
+            .mirror_vertex_map(&self.read_positions(), plane);
+        let selected: HashSet<VertexId> = ids.iter_cpy().collect();
+        ids.iter()
+            .filter_map(|v| mirror.get(v).map(|&m| (*v, m)))
+            .filter(|(_, m)| !selected.contains(m))
+            .collect()
+    }
+
+    /// Moves the second vertex of each pair to the mirror image of the first
+    /// one. Does nothing when this mesh has no symmetry plane.
+    pub fn mirror_positions(&self, pairs: &[(VertexId, VertexId)]) {
+        if let Some(plane) = self.symmetry {
+            let mut positions = self.write_positions();
+            for &(v, m) in pairs {
+                positions[m] = plane.reflect(positions[v]);
+            }
+        }
+    }
+
+    /// Same as [`HalfEdgeMesh::symmetric_vertex_selection`], for halfedges.
+    pub fn symmetric_halfedge_selection(&self, ids: Vec<HalfEdgeId>) -> Result<Vec<HalfEdgeId>> {
+        match self.symmetry {
+            Some(plane) => self.read_connectivity().mirror_halfedge_selection(
+                &self.read_positions(),
+                &ids,
+                plane,
+            ),
+            None => Ok(ids),
+        }
+    }
+
+    /// Same as [`HalfEdgeMesh::symmetric_vertex_selection`], for faces.
+    pub fn symmetric_face_selection(&self, ids: Vec<FaceId>) -> Result<Vec<FaceId>> {
+        match self.symmetry {
+            Some(plane) => {
+                self.read_connectivity()
+                    .mirror_face_selection(&self.read_positions(), &ids, plane)
+            }
+            None => Ok(ids),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn test_mirror_selection() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        let plane = SymmetryPlane {
+            axis: SymmetryAxis::X,
+            offset: 0.0,
+        };
+        let conn = mesh.read_connectivity();
+        let positions = mesh.read_positions();
+
+        let (v, _) = conn.iter_vertices().next().unwrap();
+        let mirrored = conn.mirror_vertex_selection(&positions, &[v], plane);
+        assert_eq!(mirrored.len(), 2);
+        assert!(positions[mirrored[1]].distance(plane.reflect(positions[v])) < 1e-5);
+
+        let (f, _) = conn.iter_faces().next().unwrap();
+        let mirrored = conn.mirror_face_selection(&positions, &[f], plane).unwrap();
+        assert!(!mirrored.is_empty() && mirrored.len() <= 2);
+    }
+
+    #[test]
+    fn test_mirror_positions() {
+        let mut mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        mesh.symmetry = Some(SymmetryPlane {
+            axis: SymmetryAxis::X,
+            offset: 0.0,
+        });
+        let right: Vec<VertexId> = {
+            let positions = mesh.read_positions();
+            mesh.read_connectivity()
+                .iter_vertices()
+                .map(|(v, _)| v)
+                .filter(|v| positions[*v].x > 0.0)
+                .collect()
+        };
+        let pairs = mesh.symmetric_vertex_pairs(&right);
+        assert_eq!(pairs.len(), 4);
+        // Mirror images already in the selection are not paired again
+        let all: Vec<VertexId> = mesh
+            .read_connectivity()
+            .iter_vertices()
+            .map(|(v, _)| v)
+            .collect();
+        assert!(mesh.symmetric_vertex_pairs(&all).is_empty());
+
+        // Moving one side outwards moves the other side outwards too
+        for &v in &right {
+            mesh.write_positions()[v].x += 1.0;
+        }
+        mesh.mirror_positions(&pairs);
+        let (min, max) = mesh.bounding_box().unwrap();
+        assert!((min.x + 1.5).abs() < 1e-5 && (max.x - 1.5).abs() < 1e-5);
+
+        mesh.symmetry = None;
+        assert!(mesh.symmetric_vertex_pairs(&right).is_empty());
+    }
+
     #[test]
     #[rustfmt::skip]
     fn test_all() {
@@ -226,6 +729,24 @@ mod test {
             expl(&[Range(1..5), Range(7..10), Range(15..16), Single(18), Single(22), Single(27)]));
     }
 
+    #[test]
+    fn test_select_linked() {
+        let mut mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+        mesh.merge_with(&primitives::Box::build(Vec3::X * 3.0, Vec3::ONE));
+        let conn = mesh.read_connectivity();
+        let (seed, _) = conn.iter_vertices().next().unwrap();
+
+        assert_eq!(select_linked(&conn, &[seed]).unwrap().len(), 8);
+        let (seed, _) = conn.iter_faces().next().unwrap();
+        assert_eq!(select_linked_faces(&conn, &[seed]).unwrap().len(), 6);
+
+        let all_vertices = conn.iter_vertices().map(|(v, _)| v).collect_vec();
+        assert_eq!(
+            conn.resolve_vertex_selection_full(conn.vertex_selection_from_ids(&all_vertices)),
+            all_vertices
+        );
+    }
+
     #[test]
     #[rustfmt::skip]
     fn test_error() {
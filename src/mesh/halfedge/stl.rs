@@ -0,0 +1,100 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use crate::prelude::*;
+
+impl HalfEdgeMesh {
+    /// Writes this mesh as an STL file. When `binary` is true, the compact
+    /// binary variant of the format is used, otherwise the file is ASCII.
+    pub fn to_stl(&self, path: PathBuf, binary: bool) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        if binary {
+            self.write_binary_stl(&mut writer)
+        } else {
+            self.write_ascii_stl(&mut writer)
+        }
+    }
+
+    /// Returns the triangles of this mesh, with their facet normal. STL only
+    /// supports triangles, so polygons are fan-triangulated.
+    fn stl_triangles(&self) -> Result<Vec<(Vec3, [Vec3; 3])>> {
+        let conn = self.read_connectivity();
+        let positions = self.read_positions();
+        let mut triangles = vec![];
+        for (face, _) in conn.iter_faces() {
+            let verts = conn.at_face(face).vertices()?;
+            for i in 1..verts.len().saturating_sub(1) {
+                let tri = [
+                    positions[verts[0]],
+                    positions[verts[i]],
+                    positions[verts[i + 1]],
+                ];
+                let normal = (tri[1] - tri[0]).cross(tri[2] - tri[0]).normalize_or_zero();
+                triangles.push((normal, tri));
+            }
+        }
+        Ok(triangles)
+    }
+
+    fn write_ascii_stl<W: Write>(&self, writer: &mut BufWriter<W>) -> Result<()> {
+        writeln!(writer, "solid blackjack")?;
+        for (normal, tri) in self.stl_triangles()? {
+            writeln!(
+                writer,
+                "facet normal {:e} {:e} {:e}",
+                normal.x, normal.y, normal.z
+            )?;
+            writeln!(writer, "  outer loop")?;
+            for v in tri {
+                writeln!(writer, "    vertex {:e} {:e} {:e}", v.x, v.y, v.z)?;
+            }
+            writeln!(writer, "  endloop")?;
+            writeln!(writer, "endfacet")?;
+        }
+        writeln!(writer, "endsolid blackjack")?;
+        Ok(())
+    }
+
+    fn write_binary_stl<W: Write>(&self, writer: &mut BufWriter<W>) -> Result<()> {
+        let triangles = self.stl_triangles()?;
+        let mut header = [0u8; 80];
+        let title = b"Generated by Blackjack";
+        header[..title.len()].copy_from_slice(title);
+        writer.write_all(&header)?;
+        writer.write_all(&u32::try_from(triangles.len())?.to_le_bytes())?;
+        for (normal, tri) in triangles {
+            for v in std::iter::once(normal).chain(tri) {
+                for c in v.to_array() {
+                    writer.write_all(&c.to_le_bytes())?;
+                }
+            }
+            // Attribute byte count, unused
+            writer.write_all(&0u16.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_stl_export() {
+        let mesh = primitives::Box::build(Vec3::ZERO, Vec3::ONE);
+
+        let mut ascii = BufWriter::new(Vec::new());
+        mesh.write_ascii_stl(&mut ascii).unwrap();
+        let ascii = String::from_utf8(ascii.into_inner().unwrap()).unwrap();
+        assert_eq!(ascii.matches("facet normal").count(), 12);
+
+        let mut binary = BufWriter::new(Vec::new());
+        mesh.write_binary_stl(&mut binary).unwrap();
+        let binary = binary.into_inner().unwrap();
+        assert_eq!(binary.len(), 80 + 4 + 12 * 50);
+        assert_eq!(binary[80..84], 12u32.to_le_bytes());
+    }
+}
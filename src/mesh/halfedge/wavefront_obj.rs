@@ -1,6 +1,6 @@
 use std::{
     fs::File,
-    io::{BufReader, BufWriter, Write},
+    io::{BufReader, BufWriter, Read, Write},
     path::PathBuf,
 };
 use wavefront_rs::obj::{
@@ -10,17 +10,36 @@ use wavefront_rs::obj::{
 
 use crate::prelude::*;
 
+/// The name of the optional vertex channel exported as OBJ vertex normals
+pub const NORMAL_CHANNEL: &str = "normal";
+/// The name of the optional vertex channel exported as OBJ texture
+/// coordinates. Only the `x` and `y` components are used.
+pub const UV_CHANNEL: &str = "uv";
+
 impl HalfEdgeMesh {
     pub fn to_wavefront_obj(&self, path: PathBuf) -> Result<()> {
         let mut writer = BufWriter::new(File::create(path)?);
+        self.write_wavefront_obj(&mut writer)
+    }
 
+    /// Returns the contents of the OBJ file for this mesh as a string.
+    pub fn to_wavefront_obj_string(&self) -> Result<String> {
+        let mut writer = BufWriter::new(Vec::new());
+        self.write_wavefront_obj(&mut writer)?;
+        let bytes = writer
+            .into_inner()
+            .map_err(|err| anyhow!("Error writing OBJ data: {}", err))?;
+        Ok(String::from_utf8(bytes)?)
+    }
+
+    fn write_wavefront_obj<W: Write>(&self, writer: &mut BufWriter<W>) -> Result<()> {
         // We need to store the mapping between vertex ids and indices in the
         // generated OBJ
         // NOTE: OBJ Wavefront indices start at 1
         let mut imap = HashMap::<VertexId, i32>::new();
 
         obj::format_writer::FormatWriter::write(
-            &mut writer,
+            writer,
             &Entity::Comment {
                 content: "Generated by Blackjack: https://github.com/setzer22/blackjack".into(),
             },
@@ -28,6 +47,14 @@ impl HalfEdgeMesh {
         writer.write_all("\n".as_ref())?;
 
         let conn = self.read_connectivity();
+        let normals = self
+            .channels
+            .read_channel_by_name::<VertexId, Vec3>(NORMAL_CHANNEL)
+            .ok();
+        let uvs = self
+            .channels
+            .read_channel_by_name::<VertexId, Vec3>(UV_CHANNEL)
+            .ok();
 
         for (idx, (v_id, _, pos)) in conn
             .iter_vertices_with_channel(&self.read_positions())
@@ -35,7 +62,7 @@ impl HalfEdgeMesh {
         {
             imap.insert(v_id, (idx + 1) as i32);
             obj::format_writer::FormatWriter::write(
-                &mut writer,
+                writer,
                 &Entity::Vertex {
                     x: pos.x as f64,
                     y: pos.y as f64,
@@ -45,17 +72,31 @@ impl HalfEdgeMesh {
             );
             writer.write_all("\n".as_ref())?;
         }
+        // Normals and texture coordinates are stored per vertex, so they share
+        // the vertex indices.
+        if let Some(normals) = &normals {
+            for (v_id, _) in conn.iter_vertices() {
+                let n = normals[v_id];
+                writeln!(writer, "vn {} {} {}", n.x, n.y, n.z)?;
+            }
+        }
+        if let Some(uvs) = &uvs {
+            for (v_id, _) in conn.iter_vertices() {
+                let uv = uvs[v_id];
+                writeln!(writer, "vt {} {}", uv.x, uv.y)?;
+            }
+        }
         for (face_id, _) in conn.iter_faces() {
             let vertices = conn
                 .face_vertices(face_id)
                 .iter()
                 .map(|v_id| FaceVertex {
                     vertex: imap[v_id] as i64,
-                    normal: None,
-                    texture: None,
+                    normal: normals.as_ref().map(|_| imap[v_id] as i64),
+                    texture: uvs.as_ref().map(|_| imap[v_id] as i64),
                 })
                 .collect();
-            obj::format_writer::FormatWriter::write(&mut writer, &Entity::Face { vertices });
+            obj::format_writer::FormatWriter::write(writer, &Entity::Face { vertices });
             writer.write_all("\n".as_ref())?;
         }
         Ok(())
@@ -63,21 +104,100 @@ impl HalfEdgeMesh {
 
     pub fn from_wavefront_obj(path: PathBuf) -> Result<HalfEdgeMesh> {
         let mut reader = BufReader::new(File::open(path)?);
+        Self::read_wavefront_obj(&mut reader)
+    }
+
+    /// Same as [`HalfEdgeMesh::from_wavefront_obj`], but reads the OBJ data
+    /// from a string instead of a file.
+    pub fn from_wavefront_obj_str(obj: &str) -> Result<HalfEdgeMesh> {
+        let mut reader = BufReader::new(obj.as_bytes());
+        Self::read_wavefront_obj(&mut reader)
+    }
+
+    fn read_wavefront_obj<R: Read>(reader: &mut BufReader<R>) -> Result<HalfEdgeMesh> {
         let mut positions = vec![];
+        let mut normals = vec![];
+        let mut uvs = vec![];
         let mut polygons = vec![];
-        obj::read_lexer::ReadLexer::read_to_end(&mut reader, |entity| match entity {
+        // For each polygon corner, the (optional) normal and uv indices
+        let mut corner_attributes = vec![];
+        let mut invalid_index = false;
+        obj::read_lexer::ReadLexer::read_to_end(reader, |entity| match entity {
             Entity::Vertex { x, y, z, w: _w } => {
                 positions.push(Vec3::new(x as f32, y as f32, z as f32));
             }
+            Entity::VertexNormal { x, y, z } => {
+                normals.push(Vec3::new(x as f32, y as f32, z as f32));
+            }
+            Entity::VertexTexture { x, y, z: _z } => {
+                uvs.push(Vec3::new(x as f32, y.unwrap_or(0.0) as f32, 0.0));
+            }
             Entity::Face { vertices } => {
-                // NOTE: OBJ Wavefront indices start at 1
-                let polygon: SVec<usize> =
-                    vertices.iter().map(|v| (v.vertex - 1) as usize).collect();
+                let mut polygon = SVec::<usize>::new();
+                let mut attributes = SVec::<(Option<usize>, Option<usize>)>::new();
+                for v in &vertices {
+                    match resolve_obj_index(v.vertex, positions.len()) {
+                        Some(idx) => polygon.push(idx),
+                        None => invalid_index = true,
+                    }
+                    attributes.push((
+                        v.normal.and_then(|n| resolve_obj_index(n, normals.len())),
+                        v.texture.and_then(|t| resolve_obj_index(t, uvs.len())),
+                    ));
+                }
                 polygons.push(polygon);
+                corner_attributes.push(attributes);
             }
             _ => {}
         })?;
-        halfedge::HalfEdgeMesh::build_from_polygons(&positions, &polygons)
+        if invalid_index {
+            bail!("The OBJ data contains faces with invalid vertex indices");
+        }
+
+        let mut mesh = halfedge::HalfEdgeMesh::build_from_polygons(&positions, &polygons)?;
+
+        // Vertices are allocated in order of first appearance in the polygon
+        // list, so we can recover the vertex id for each OBJ index.
+        let index_to_vertex: HashMap<usize, VertexId> = polygons
+            .iter()
+            .flatten()
+            .copied()
+            .unique()
+            .zip(mesh.read_connectivity().iter_vertices().map(|(v, _)| v))
+            .collect();
+
+        // Normals and uvs are stored per vertex. When a vertex has different
+        // values on different faces, the last one wins.
+        if !normals.is_empty() || !uvs.is_empty() {
+            let normal_ch = mesh
+                .channels
+                .ensure_channel::<VertexId, Vec3>(NORMAL_CHANNEL);
+            let uv_ch = mesh.channels.ensure_channel::<VertexId, Vec3>(UV_CHANNEL);
+            let mut normal_ch = mesh.channels.write_channel(normal_ch)?;
+            let mut uv_ch = mesh.channels.write_channel(uv_ch)?;
+            for (polygon, attributes) in polygons.iter().zip(corner_attributes.iter()) {
+                for (idx, (normal, uv)) in polygon.iter().zip(attributes.iter()) {
+                    let v = index_to_vertex[idx];
+                    if let Some(normal) = normal.and_then(|n| normals.get(n)) {
+                        normal_ch[v] = *normal;
+                    }
+                    if let Some(uv) = uv.and_then(|t| uvs.get(t)) {
+                        uv_ch[v] = *uv;
+                    }
+                }
+            }
+        }
+        Ok(mesh)
+    }
+}
+
+/// Converts an OBJ index into a zero-based index. OBJ indices start at 1, and
+/// negative indices are relative to the end of the `len` elements read so far.
+fn resolve_obj_index(index: i64, len: usize) -> Option<usize> {
+    match index {
+        i if i > 0 && (i as usize) <= len => Some(i as usize - 1),
+        i if i < 0 && (i.unsigned_abs() as usize) <= len => Some(len - i.unsigned_abs() as usize),
+        _ => None,
     }
 }
 
@@ -92,4 +212,58 @@ mod tests {
             .to_wavefront_obj("/tmp/wat.obj".into())
             .unwrap();
     }
+
+    #[test]
+    pub fn test_export_normals_and_uvs() {
+        let mut mesh = primitives::Quad::build(Vec3::ZERO, Vec3::Y, Vec3::X, Vec2::ONE);
+        let obj = mesh.to_wavefront_obj_string().unwrap();
+        assert!(!obj.contains("vn ") && !obj.contains("vt "));
+
+        let normals = mesh
+            .channels
+            .ensure_channel::<VertexId, Vec3>(NORMAL_CHANNEL);
+        let uvs = mesh.channels.ensure_channel::<VertexId, Vec3>(UV_CHANNEL);
+        {
+            let conn = mesh.read_connectivity();
+            let mut normals = mesh.channels.write_channel(normals).unwrap();
+            let mut uvs = mesh.channels.write_channel(uvs).unwrap();
+            for (v, _) in conn.iter_vertices() {
+                normals[v] = Vec3::Y;
+                uvs[v] = Vec3::new(0.5, 0.5, 0.0);
+            }
+        }
+        let obj = mesh.to_wavefront_obj_string().unwrap();
+        assert_eq!(obj.matches("vn 0 1 0").count(), 4);
+        assert_eq!(obj.matches("vt 0.5 0.5").count(), 4);
+
+        let reloaded = HalfEdgeMesh::from_wavefront_obj_str(&obj).unwrap();
+        assert_eq!(reloaded.read_connectivity().num_faces(), 1);
+    }
+
+    #[test]
+    pub fn test_import_relative_indices() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nf -4 -2 -1\n";
+        assert!(HalfEdgeMesh::from_wavefront_obj_str(obj).is_err());
+
+        let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+                   vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+                   vn 0 0 1\n\
+                   f -4/-4/-1 -3/-3/-1 -2/-2/-1 -1/-1/-1\n";
+        let mesh = HalfEdgeMesh::from_wavefront_obj_str(obj).unwrap();
+        let conn = mesh.read_connectivity();
+        assert_eq!(conn.num_faces(), 1);
+        let positions = mesh.read_positions();
+        let normals = mesh
+            .channels
+            .read_channel_by_name::<VertexId, Vec3>(NORMAL_CHANNEL)
+            .unwrap();
+        let uvs = mesh
+            .channels
+            .read_channel_by_name::<VertexId, Vec3>(UV_CHANNEL)
+            .unwrap();
+        for (v, _) in conn.iter_vertices() {
+            assert_eq!(normals[v], Vec3::Z);
+            assert_eq!(uvs[v], positions[v]);
+        }
+    }
 }
@@ -26,6 +26,10 @@ pub struct RenderContext {
     pub surface: Arc<Surface>,
     pub texture_format: TextureFormat,
     pub shader_manager: ShaderManager,
+    /// The MSAA sample count the viewport routines were created with
+    pub sample_count: r3::SampleCount,
+    /// Whether the face routine was created with backface culling
+    pub cull_backfaces: bool,
 
     pub objects: Vec<r3::ObjectHandle>,
     lights: Vec<r3::DirectionalLightHandle>,
@@ -68,12 +72,20 @@ impl RenderContext {
         drop(data_core); // Release the lock
 
         let shader_manager = ShaderManager::new(&renderer.device);
-        let grid_routine = GridRoutine::new(&renderer.device);
+        let sample_count = r3::SampleCount::One;
+        let grid_routine = GridRoutine::new(&renderer.device, sample_count);
         let wireframe_routine =
-            WireframeRoutine::new(&renderer.device, &base_graph, &shader_manager);
+            WireframeRoutine::new(&renderer.device, &base_graph, &shader_manager, sample_count);
         let point_cloud_routine =
-            PointCloudRoutine::new(&renderer.device, &base_graph, &shader_manager);
-        let face_routine = FaceRoutine::new(&renderer, &base_graph, &shader_manager);
+            PointCloudRoutine::new(&renderer.device, &base_graph, &shader_manager, sample_count);
+        let cull_backfaces = true;
+        let face_routine = FaceRoutine::new(
+            &renderer,
+            &base_graph,
+            &shader_manager,
+            sample_count,
+            cull_backfaces,
+        );
 
         RenderContext {
             renderer,
@@ -87,11 +99,51 @@ impl RenderContext {
             surface,
             texture_format: format,
             shader_manager,
+            sample_count,
+            cull_backfaces,
             objects: vec![],
             lights: vec![],
         }
     }
 
+    /// Recreates the viewport routines so that their pipelines match the given
+    /// MSAA sample count. Does nothing if the sample count didn't change.
+    pub fn set_sample_count(&mut self, sample_count: r3::SampleCount) {
+        if self.sample_count == sample_count {
+            return;
+        }
+        self.sample_count = sample_count;
+        let device = &self.renderer.device;
+        self.grid_routine = GridRoutine::new(device, sample_count);
+        self.wireframe_routine =
+            WireframeRoutine::new(device, &self.base_graph, &self.shader_manager, sample_count);
+        self.point_cloud_routine =
+            PointCloudRoutine::new(device, &self.base_graph, &self.shader_manager, sample_count);
+        self.face_routine = FaceRoutine::new(
+            &self.renderer,
+            &self.base_graph,
+            &self.shader_manager,
+            sample_count,
+            self.cull_backfaces,
+        );
+    }
+
+    /// Recreates the face routine so that its pipelines cull backfaces, or
+    /// draw them. Does nothing if the setting didn't change.
+    pub fn set_cull_backfaces(&mut self, cull_backfaces: bool) {
+        if self.cull_backfaces == cull_backfaces {
+            return;
+        }
+        self.cull_backfaces = cull_backfaces;
+        self.face_routine = FaceRoutine::new(
+            &self.renderer,
+            &self.base_graph,
+            &self.shader_manager,
+            self.sample_count,
+            cull_backfaces,
+        );
+    }
+
     pub fn clear_objects(&mut self) {
         self.objects.clear();
         self.point_cloud_routine.clear();
@@ -132,11 +184,14 @@ impl RenderContext {
         });
     }
 
+    /// Returns the current view-projection matrix of the camera
+    pub fn view_proj(&self) -> Mat4 {
+        self.renderer.data_core.lock().camera_manager.view_proj()
+    }
+
     #[allow(dead_code)]
     pub fn project_point(&self, point: Vec3, screen_size: Vec2) -> Vec2 {
-        let camera_manager = &self.renderer.data_core.lock().camera_manager;
-
-        let clip = camera_manager.view_proj().project_point3(point);
+        let clip = self.view_proj().project_point3(point);
         let clip = Vec2::new(clip.x, -clip.y);
         let zero_to_one = (Vec2::new(clip.x, clip.y) + Vec2::ONE) * 0.5;
         zero_to_one * screen_size
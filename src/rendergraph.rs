@@ -53,24 +53,29 @@ pub fn blackjack_viewport_rendergraph<'node>(
     state.pbr_forward_rendering(graph, routines.pbr, samples);
 
     use crate::application::viewport_3d::EdgeDrawMode::*;
-    if matches!(settings.edge_mode, FullEdge | HalfEdge) {
+    if matches!(settings.edge_mode, FullEdge | HalfEdge)
+        || settings.show_face_normals
+        || settings.show_bounds
+    {
         routines.wireframe.add_to_graph(graph, &state);
     }
     if settings.render_vertices {
         routines.point_cloud.add_to_graph(graph, &state);
     }
     use crate::application::viewport_3d::FaceDrawMode::*;
-    if matches!(settings.face_mode, Flat | Smooth) {
+    if matches!(settings.face_mode, Flat | Smooth | VertexColor | XRay) {
         routines.face.add_to_graph(graph, &state, settings);
     }
 
-    routines.grid.add_to_graph(graph, &state);
+    routines.grid.add_to_graph(graph, &state, settings);
 
     // Make the reference to the surface
     let output = graph.add_render_target(r3::RenderTargetDescriptor {
         label: Some("Blackjack Viewport Output".into()),
         resolution,
-        samples,
+        // The output is sampled by egui, so it must not be multisampled. The
+        // tonemapping pass reads from the resolved target when using MSAA.
+        samples: r3::SampleCount::One,
         format: r3::TextureFormat::Bgra8UnormSrgb,
         usage: r3::TextureUsages::RENDER_ATTACHMENT | r3::TextureUsages::TEXTURE_BINDING,
     });
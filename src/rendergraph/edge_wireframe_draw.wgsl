@@ -17,16 +17,54 @@ var<storage> lines: Vec3Array;
 [[group(1), binding(1)]]
 var<storage> colors: Vec3Array;
 
+struct WireframeSettings {
+    line_width: f32;
+};
+
+[[group(1), binding(2)]]
+var<storage> settings: WireframeSettings;
+
+// Each line is drawn as a screen-space quad made of two triangles. The x
+// coordinate selects the line endpoint, and the y coordinate selects the side
+// of the line the vertex is offset towards.
+var<private> line_quad: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(0.0, -1.0),
+    vec2<f32>(1.0, -1.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(0.0, -1.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(0.0, 1.0),
+);
+
 [[stage(vertex)]]
 fn vs_main(
     [[builtin(instance_index)]] instance_idx: u32,
     [[builtin(vertex_index)]] vertex_idx: u32,
 ) -> VertexOutput {
-    var point = unpack_v3(lines.inner[instance_idx * 2u + vertex_idx]);
-    var color = unpack_v3(colors.inner[instance_idx]);
+    let quad_vertex = line_quad[vertex_idx];
+    let color = unpack_v3(colors.inner[instance_idx]);
+
+    // Compute the clip space position of both endpoints of the line
+    let start = uniforms.view_proj * vec4<f32>(unpack_v3(lines.inner[instance_idx * 2u]), 1.0);
+    let end = uniforms.view_proj * vec4<f32>(unpack_v3(lines.inner[instance_idx * 2u + 1u]), 1.0);
+
+    // Find the line direction in pixel space, and its perpendicular
+    let resolution = vec2<f32>(f32(uniforms.resolution.x), f32(uniforms.resolution.y));
+    let start_px = (start.xy / start.w) * resolution;
+    let end_px = (end.xy / end.w) * resolution;
+    let dir = normalize(end_px - start_px);
+    let normal = vec2<f32>(-dir.y, dir.x);
+
+    // Offset the current vertex sideways by half the line width. Clip space
+    // spans two units across the viewport, so one pixel is 2 / resolution.
+    let offset = normal * quad_vertex.y * settings.line_width / resolution;
+    var point_clip = start;
+    if (quad_vertex.x > 0.5) {
+        point_clip = end;
+    }
 
     var output : VertexOutput;
-    output.clip_position = uniforms.view_proj * vec4<f32>(point, 1.0);
+    output.clip_position = point_clip + vec4<f32>(offset * point_clip.w, 0.0, 0.0);
     output.color = color;
     return output;
 }
@@ -58,6 +58,52 @@ impl ViewportBuffers<BASE_MESH_NUM_BUFFERS, BASE_MESH_NUM_TEXTURES> for MeshBuff
     }
 }
 
+/// Settings for the x-ray shader. Uploaded to the GPU alongside each x-ray
+/// mesh buffer.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct XRaySettings {
+    /// The opacity of the faces, between 0 and 1
+    pub alpha: f32,
+}
+
+/// Represents the buffers to draw a semi-transparent base mesh, for the x-ray
+/// face mode. Same as [`MeshBuffer`], with an additional settings buffer.
+pub struct XRayMeshBuffer {
+    indices: Buffer,
+    positions: Buffer,
+    normals: Buffer,
+    /// Contains a single `XRaySettings` element
+    settings: Buffer,
+    matcaps: Arc<Vec<TextureHandle>>,
+    num_indices: usize,
+}
+
+const XRAY_NUM_BUFFERS: usize = 3;
+const XRAY_NUM_TEXTURES: usize = 1;
+impl ViewportBuffers<XRAY_NUM_BUFFERS, XRAY_NUM_TEXTURES> for XRayMeshBuffer {
+    type Settings = Viewport3dSettings;
+
+    fn get_wgpu_buffers(&self, _settings: &Viewport3dSettings) -> [&Buffer; XRAY_NUM_BUFFERS] {
+        [&self.positions, &self.normals, &self.settings]
+    }
+
+    fn get_wgpu_textures<'a>(
+        &'a self,
+        texture_manager: &'a TextureManager,
+        settings: &Viewport3dSettings,
+    ) -> [&'a TextureView; XRAY_NUM_TEXTURES] {
+        [texture_manager.get_view(self.matcaps[settings.matcap % NUM_MATCAPS].get_raw())]
+    }
+
+    fn get_draw_type(&self, _settings: &Self::Settings) -> DrawType<'_> {
+        DrawType::UseIndices {
+            indices: &self.indices,
+            num_indices: self.num_indices,
+        }
+    }
+}
+
 const OVERLAY_NUM_BUFFERS: usize = 2;
 const OVERLAY_NUM_TEXTURES: usize = 0;
 
@@ -95,11 +141,55 @@ impl ViewportBuffers<OVERLAY_NUM_BUFFERS, OVERLAY_NUM_TEXTURES> for FaceOverlayB
     }
 }
 
+const VERTEX_COLOR_NUM_BUFFERS: usize = 3;
+const VERTEX_COLOR_NUM_TEXTURES: usize = 0;
+
+/// Represents the buffers to draw a mesh using per-vertex colors instead of a
+/// matcap. Triangles are not indexed.
+pub struct VertexColorMeshBuffer {
+    positions: Buffer,
+    normals: Buffer,
+    colors: Buffer,
+    /// The number of vertices, three per triangle
+    len: usize,
+}
+
+impl ViewportBuffers<VERTEX_COLOR_NUM_BUFFERS, VERTEX_COLOR_NUM_TEXTURES>
+    for VertexColorMeshBuffer
+{
+    type Settings = ();
+
+    fn get_wgpu_buffers(&self, _settings: &Self::Settings) -> [&Buffer; VERTEX_COLOR_NUM_BUFFERS] {
+        [&self.positions, &self.normals, &self.colors]
+    }
+
+    fn get_wgpu_textures<'a>(
+        &'a self,
+        _texture_manager: &'a TextureManager,
+        _settings: &'a Self::Settings,
+    ) -> [&'a TextureView; VERTEX_COLOR_NUM_TEXTURES] {
+        []
+    }
+
+    fn get_draw_type(&self, _settings: &Self::Settings) -> DrawType<'_> {
+        DrawType::UseInstances {
+            num_vertices: self.len,
+            num_instances: 1,
+        }
+    }
+}
+
 pub struct FaceRoutine {
     matcaps: Arc<Vec<TextureHandle>>,
     base_mesh_routine: Viewport3dRoutine<MeshBuffer, BASE_MESH_NUM_BUFFERS, BASE_MESH_NUM_TEXTURES>,
     face_overlay_routine:
         Viewport3dRoutine<FaceOverlayBuffer, OVERLAY_NUM_BUFFERS, OVERLAY_NUM_TEXTURES>,
+    vertex_color_routine: Viewport3dRoutine<
+        VertexColorMeshBuffer,
+        VERTEX_COLOR_NUM_BUFFERS,
+        VERTEX_COLOR_NUM_TEXTURES,
+    >,
+    xray_routine: Viewport3dRoutine<XRayMeshBuffer, XRAY_NUM_BUFFERS, XRAY_NUM_TEXTURES>,
 }
 
 impl FaceRoutine {
@@ -107,7 +197,14 @@ impl FaceRoutine {
         renderer: &r3::Renderer,
         base: &BaseRenderGraph,
         shader_manager: &ShaderManager,
+        samples: r3::SampleCount,
+        cull_backfaces: bool,
     ) -> Self {
+        let cull_mode = if cull_backfaces {
+            Some(Face::Back)
+        } else {
+            None
+        };
         let mut matcaps = Vec::new();
         macro_rules! load_matcap {
             ($image:expr) => {
@@ -146,7 +243,10 @@ impl FaceRoutine {
                 shader_manager.get("face_draw"),
                 PrimitiveTopology::TriangleList,
                 FrontFace::Cw,
+                cull_mode,
                 false,
+                true,
+                samples,
             ),
             face_overlay_routine: Viewport3dRoutine::new(
                 "face overlay",
@@ -155,7 +255,35 @@ impl FaceRoutine {
                 shader_manager.get("face_overlay_draw"),
                 PrimitiveTopology::TriangleList,
                 FrontFace::Cw,
+                cull_mode,
+                true,
+                true,
+                samples,
+            ),
+            vertex_color_routine: Viewport3dRoutine::new(
+                "vertex color mesh",
+                &renderer.device,
+                base,
+                shader_manager.get("face_vertex_color_draw"),
+                PrimitiveTopology::TriangleList,
+                FrontFace::Cw,
+                cull_mode,
+                false,
+                true,
+                samples,
+            ),
+            xray_routine: Viewport3dRoutine::new(
+                "x-ray mesh",
+                &renderer.device,
+                base,
+                shader_manager.get("face_xray_draw"),
+                PrimitiveTopology::TriangleList,
+                FrontFace::Cw,
+                cull_mode,
                 true,
+                // Transparent geometry doesn't occlude what's behind it
+                false,
+                samples,
             ),
         }
     }
@@ -193,6 +321,69 @@ impl FaceRoutine {
         });
     }
 
+    pub fn add_xray_mesh(
+        &mut self,
+        renderer: &r3::Renderer,
+        positions: &[Vec3],
+        normals: &[Vec3],
+        indices: &[u32],
+        settings: XRaySettings,
+    ) {
+        let num_indices = indices.len();
+        let [positions, normals] = [positions, normals].map(|data| {
+            renderer.device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(data),
+                usage: BufferUsages::STORAGE,
+            })
+        });
+        let settings = renderer.device.create_buffer_init(&BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&[settings]),
+            usage: BufferUsages::STORAGE,
+        });
+        let indices = renderer.device.create_buffer_init(&BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        self.xray_routine.buffers.push(XRayMeshBuffer {
+            positions,
+            normals,
+            settings,
+            indices,
+            matcaps: self.matcaps.clone(),
+            num_indices,
+        });
+    }
+
+    pub fn add_vertex_color_mesh(
+        &mut self,
+        renderer: &r3::Renderer,
+        positions: &[Vec3],
+        normals: &[Vec3],
+        colors: &[Vec3],
+    ) {
+        let len = positions.len();
+        let [positions, normals, colors] = [positions, normals, colors].map(|data| {
+            renderer.device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(data),
+                usage: BufferUsages::STORAGE,
+            })
+        });
+
+        self.vertex_color_routine
+            .buffers
+            .push(VertexColorMeshBuffer {
+                positions,
+                normals,
+                colors,
+                len,
+            });
+    }
+
     pub fn add_overlay_mesh(
         &mut self,
         renderer: &r3::Renderer,
@@ -221,6 +412,8 @@ impl FaceRoutine {
     pub fn clear(&mut self) {
         self.base_mesh_routine.clear();
         self.face_overlay_routine.clear();
+        self.vertex_color_routine.clear();
+        self.xray_routine.clear();
     }
 
     pub fn add_to_graph<'node>(
@@ -230,6 +423,8 @@ impl FaceRoutine {
         settings: &'node Viewport3dSettings,
     ) {
         self.base_mesh_routine.add_to_graph(graph, state, settings);
+        self.vertex_color_routine.add_to_graph(graph, state, &());
+        self.xray_routine.add_to_graph(graph, state, settings);
         self.face_overlay_routine.add_to_graph(graph, state, &());
     }
 }
@@ -0,0 +1,45 @@
+#include <utils.wgsl>
+#include <rend3_uniforms.wgsl>
+
+struct VertexOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] color: vec3<f32>;
+    [[location(1)]] normal: vec3<f32>;
+};
+
+struct FragmentOutput {
+    [[location(0)]] color: vec4<f32>;
+};
+
+[[group(1), binding(0)]]
+var<storage> positions: Vec3Array;
+[[group(1), binding(1)]]
+var<storage> normals: Vec3Array;
+[[group(1), binding(2)]]
+var<storage> colors: Vec3Array;
+
+[[stage(vertex)]]
+fn vs_main(
+    [[builtin(vertex_index)]] vertex_idx: u32,
+) -> VertexOutput {
+    let position = unpack_v3(positions.inner[vertex_idx]);
+
+    var output : VertexOutput;
+    output.clip_position = uniforms.view_proj * vec4<f32>(position, 1.0);
+    output.normal = unpack_v3(normals.inner[vertex_idx]);
+    output.color = unpack_v3(colors.inner[vertex_idx]);
+    return output;
+}
+
+[[stage(fragment)]]
+fn fs_main(input: VertexOutput) -> FragmentOutput {
+    var out : FragmentOutput;
+
+    // Simple headlight shading, so the shape is still readable while the
+    // colors stay close to their actual values.
+    let view_normal = (uniforms.view * vec4<f32>(normalize(input.normal), 0.0)).xyz;
+    let shading = 0.4 + 0.6 * abs(view_normal.z);
+
+    out.color = vec4<f32>(input.color * shading, 1.0);
+    return out;
+}
@@ -1,4 +1,4 @@
-use crate::prelude::*;
+use crate::{application::viewport_3d::Viewport3dSettings, prelude::*};
 use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
     BindGroup, BindGroupLayout, Color, Device, RenderPipeline,
@@ -6,6 +6,7 @@ use wgpu::{
 
 pub struct GridRoutine {
     pipeline: RenderPipeline,
+    background_pipeline: RenderPipeline,
     bgl: BindGroupLayout,
 }
 
@@ -16,10 +17,22 @@ pub struct GridRoutineUniform {
     pub proj: [[f32; 4]; 4],
     pub inv_view: [[f32; 4]; 4],
     pub inv_proj: [[f32; 4]; 4],
+    pub background_top: [f32; 4],
+    pub background_bottom: [f32; 4],
+    pub grid_color: [f32; 4],
+    /// The distance between grid lines in the x component. The other
+    /// components are unused padding.
+    pub grid_spacing: [f32; 4],
+}
+
+/// Converts an sRGB egui color to a linear color, as expected by the shaders.
+fn linear_rgba(color: egui::Color32) -> [f32; 4] {
+    let rgba = egui::Rgba::from(color);
+    [rgba.r(), rgba.g(), rgba.b(), rgba.a()]
 }
 
 impl GridRoutine {
-    pub fn new(device: &Device) -> Self {
+    pub fn new(device: &Device, samples: r3::SampleCount) -> Self {
         use wgpu::*;
         let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
             label: Some("Shader"),
@@ -78,7 +91,7 @@ impl GridRoutine {
                 bias: DepthBiasState::default(),
             }),
             multisample: MultisampleState {
-                count: 1,
+                count: samples as u32,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -94,7 +107,54 @@ impl GridRoutine {
             multiview: None,
         });
 
-        Self { pipeline, bgl }
+        let background_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Background Pipeline"),
+            layout: Some(&layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: Some(Face::Back),
+                unclipped_depth: false,
+                polygon_mode: PolygonMode::Fill,
+                conservative: false,
+            },
+            // The background is drawn at the far plane (depth 0, reverse z),
+            // so it only passes the depth test where nothing was drawn.
+            depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::GreaterEqual,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState {
+                count: samples as u32,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_background",
+                targets: &[wgpu::ColorTargetState {
+                    format: TextureFormat::Rgba16Float,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            background_pipeline,
+            bgl,
+        }
     }
 
     fn grid_pass<'node>(
@@ -133,6 +193,8 @@ impl GridRoutine {
                 let grid_uniform_bg = graph_data.get_data(temps, grid_uniform_handle).unwrap();
 
                 rpass.set_bind_group(0, grid_uniform_bg, &[]);
+                rpass.set_pipeline(&this.background_pipeline);
+                rpass.draw(0..6, 0..1);
                 rpass.set_pipeline(&this.pipeline);
                 rpass.draw(0..6, 0..1);
             },
@@ -143,6 +205,7 @@ impl GridRoutine {
         &'node self,
         graph: &mut r3::RenderGraph<'node>,
         grid_uniform_bg: r3::DataHandle<BindGroup>,
+        settings: &'node Viewport3dSettings,
     ) {
         use wgpu::*;
         let mut builder = graph.add_node("build grid uniforms");
@@ -158,6 +221,14 @@ impl GridRoutine {
                     proj: camera_manager.proj().to_cols_array_2d(),
                     inv_view: camera_manager.view().inverse().to_cols_array_2d(),
                     inv_proj: camera_manager.proj().inverse().to_cols_array_2d(),
+                    background_top: linear_rgba(settings.background_color),
+                    background_bottom: if settings.background_gradient {
+                        linear_rgba(settings.background_color_bottom)
+                    } else {
+                        linear_rgba(settings.background_color)
+                    },
+                    grid_color: linear_rgba(settings.grid_color),
+                    grid_spacing: [settings.grid_spacing, 0.0, 0.0, 0.0],
                 };
 
                 let buffer = renderer.device.create_buffer_init(&BufferInitDescriptor {
@@ -184,9 +255,10 @@ impl GridRoutine {
         &'node self,
         graph: &mut r3::RenderGraph<'node>,
         state: &r3::BaseRenderGraphIntermediateState,
+        settings: &'node Viewport3dSettings,
     ) {
         let grid_uniform_bg = graph.add_data::<BindGroup>();
-        self.create_bind_groups(graph, grid_uniform_bg);
+        self.create_bind_groups(graph, grid_uniform_bg, settings);
         self.grid_pass(
             graph,
             state.color,
@@ -4,6 +4,7 @@ struct VertexOutput {
     [[builtin(position)]] clip_position: vec4<f32>;
     [[location(0)]] near_point: vec3<f32>;
     [[location(1)]] far_point: vec3<f32>;
+    [[location(2)]] screen_pos: vec2<f32>;
 };
 
 struct FragmentOutput {
@@ -16,6 +17,11 @@ struct GridRoutineUniform {
     proj: mat4x4<f32>;
     inv_view: mat4x4<f32>;
     inv_proj: mat4x4<f32>;
+    background_top: vec4<f32>;
+    background_bottom: vec4<f32>;
+    grid_color: vec4<f32>;
+    // Only the x component is used
+    grid_spacing: vec4<f32>;
 };
 
 [[group(0), binding(0)]]
@@ -45,6 +51,7 @@ fn vs_main(
     let pos = vec4<f32>(pos_xy.x, pos_xy.y, 0.0, 1.0);
 
     out.clip_position = pos;
+    out.screen_pos = pos_xy;
     // TODO: Compute near_point / far_point
     out.near_point = unproject_point(vec3<f32>(pos.x, pos.y, 0.1), matrices.inv_view, matrices.inv_proj).xyz; 
     out.far_point = unproject_point(vec3<f32>(pos.x, pos.y, 1.0), matrices.inv_view, matrices.inv_proj).xyz; 
@@ -61,7 +68,7 @@ fn grid(frag_pos_3d: vec3<f32>, scale: f32) -> vec4<f32> {
     let line = min(grid.x, grid.y);
     let minimumz = min(derivative.y, 1.0);
     let minimumx = min(derivative.x, 1.0);
-    var color = vec4<f32>(0.2, 0.2, 0.2, 1.0 - min(line, 1.0));
+    var color = vec4<f32>(matrices.grid_color.rgb, 1.0 - min(line, 1.0));
 
     let threshold = 1.0 / scale;
 
@@ -89,6 +96,14 @@ fn fading(frag_pos_3d: vec3<f32>, depth: f32) -> f32 {
     return max(0.0, 1.5 - linear_depth);
 }
 
+// Draws the viewport background. This is drawn with depth testing at the far
+// plane, so it only covers the pixels where nothing else was drawn.
+[[stage(fragment)]]
+fn fs_background(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    let t = in.screen_pos.y * 0.5 + 0.5;
+    return mix(matrices.background_bottom, matrices.background_top, t);
+}
+
 [[stage(fragment)]]
 fn fs_main(in: VertexOutput) -> FragmentOutput {
     let t = -in.near_point.y / (in.far_point.y - in.near_point.y);
@@ -97,7 +112,7 @@ fn fs_main(in: VertexOutput) -> FragmentOutput {
     let depth = compute_depth(frag_pos_3d);
 
     var out: FragmentOutput;
-    out.color = grid(frag_pos_3d, 2.0) * f32(t < 0.0);
+    out.color = grid(frag_pos_3d, 1.0 / matrices.grid_spacing.x) * f32(t < 0.0);
     out.depth = depth;
     out.color.a = out.color.a * fading(frag_pos_3d, depth);
 
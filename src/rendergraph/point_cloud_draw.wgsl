@@ -3,6 +3,7 @@
 
 struct VertexOutput {
     [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] color: vec3<f32>;
 };
 
 struct FragmentOutput {
@@ -13,6 +14,16 @@ struct FragmentOutput {
 [[group(1), binding(0)]]
 var<storage> point_cloud: Vec3Array;
 
+[[group(1), binding(1)]]
+var<storage> color: Vec3Array;
+
+struct PointCloudSettings {
+    point_size: f32;
+};
+
+[[group(1), binding(2)]]
+var<storage> settings: PointCloudSettings;
+
 var<private> screen_quad: array<vec2<f32>, 6> = array<vec2<f32>, 6>( 
     vec2<f32>(0.0, 1.0),
     vec2<f32>(-1.0, 0.0),
@@ -37,7 +48,7 @@ fn vs_main(
     // Get the offset for the current vertex in the quad
     let screen_quad_vertex = screen_quad[vertex_idx];
     let pixel_size = vec2<f32>(1.0 / f32(uniforms.resolution.x), 1.0 / f32(uniforms.resolution.y));
-    let point_size = pixel_size * 5.0;
+    let point_size = pixel_size * settings.point_size;
     let vertex_offset = screen_quad_vertex * point_size;
 
     // The final position is the clip space position for the point, plus the
@@ -46,13 +57,16 @@ fn vs_main(
 
     var output : VertexOutput;
     output.clip_position = clip_position;
+    // There's either one color per point, or a single color for all of them
+    let color_idx = min(instance_idx, arrayLength(&color.inner) - 1u);
+    output.color = unpack_v3(color.inner[color_idx]);
     return output;
 }
 
 [[stage(fragment)]]
 fn fs_main(input: VertexOutput) -> FragmentOutput {
     var out : FragmentOutput;
-    out.color = vec4<f32>(0.2, 0.8, 0.2, 1.0);
+    out.color = vec4<f32>(input.color, 1.0);
     // We want vertices slightly over their actual positions towards the camera.
     // This prevents z-fighting when drawing the wireframe over the mesh.
     // Value is 1.02, which is slightly above the 1.01 used for edges
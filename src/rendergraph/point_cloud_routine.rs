@@ -15,16 +15,30 @@ use super::{
 
 pub struct PointCloudBuffer {
     buffer: Buffer,
+    /// Contains either one Vec3 color per point, or a single Vec3 element
+    /// with the color of all the points
+    color: Buffer,
+    /// Contains a single `PointCloudSettings` element
+    settings: Buffer,
     len: usize,
 }
 
-const NUM_BUFFERS: usize = 1;
+/// Settings for the point cloud shader. Uploaded to the GPU alongside each
+/// point cloud buffer.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PointCloudSettings {
+    /// The width of the points, in pixels
+    pub point_size: f32,
+}
+
+const NUM_BUFFERS: usize = 3;
 const NUM_TEXTURES: usize = 0;
 
 impl ViewportBuffers<NUM_BUFFERS, NUM_TEXTURES> for PointCloudBuffer {
     type Settings = ();
     fn get_wgpu_buffers(&self, _settings: &()) -> [&Buffer; NUM_BUFFERS] {
-        [&self.buffer]
+        [&self.buffer, &self.color, &self.settings]
     }
 
     fn get_wgpu_textures<'a>(
@@ -48,7 +62,12 @@ pub struct PointCloudRoutine {
 }
 
 impl PointCloudRoutine {
-    pub fn new(device: &Device, base: &BaseRenderGraph, shader_manager: &ShaderManager) -> Self {
+    pub fn new(
+        device: &Device,
+        base: &BaseRenderGraph,
+        shader_manager: &ShaderManager,
+        samples: r3::SampleCount,
+    ) -> Self {
         Self {
             inner: Viewport3dRoutine::new(
                 "point cloud",
@@ -57,19 +76,53 @@ impl PointCloudRoutine {
                 shader_manager.get("point_cloud_draw"),
                 PrimitiveTopology::TriangleList,
                 FrontFace::Ccw,
+                Some(Face::Back),
                 false,
+                true,
+                samples,
             ),
         }
     }
 
-    pub fn add_point_cloud(&mut self, device: &Device, points: &[Vec3]) {
+    /// Adds a point cloud where all the points are drawn with the same color
+    pub fn add_point_cloud(
+        &mut self,
+        device: &Device,
+        points: &[Vec3],
+        color: Vec3,
+        settings: PointCloudSettings,
+    ) {
+        self.add_point_cloud_with_colors(device, points, &[color], settings);
+    }
+
+    /// Adds a point cloud with one color per point. When `colors` is shorter
+    /// than `points`, the remaining points use the last color.
+    pub fn add_point_cloud_with_colors(
+        &mut self,
+        device: &Device,
+        points: &[Vec3],
+        colors: &[Vec3],
+        settings: PointCloudSettings,
+    ) {
         let buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: None,
             contents: bytemuck::cast_slice(points),
             usage: BufferUsages::STORAGE,
         });
+        let color = device.create_buffer_init(&BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(colors),
+            usage: BufferUsages::STORAGE,
+        });
+        let settings = device.create_buffer_init(&BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&[settings]),
+            usage: BufferUsages::STORAGE,
+        });
         self.inner.buffers.push(PointCloudBuffer {
             buffer,
+            color,
+            settings,
             len: points.len(),
         });
     }
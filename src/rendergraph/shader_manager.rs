@@ -83,6 +83,8 @@ impl ShaderManager {
         def_shader!("point_cloud_draw", "point_cloud_draw.wgsl");
         def_shader!("face_draw", "face_draw.wgsl");
         def_shader!("face_overlay_draw", "face_overlay_draw.wgsl");
+        def_shader!("face_vertex_color_draw", "face_vertex_color_draw.wgsl");
+        def_shader!("face_xray_draw", "face_xray_draw.wgsl");
 
         Self { shaders }
     }
@@ -58,6 +58,7 @@ impl<
         const NUM_TEXTURES: usize,
     > Viewport3dRoutine<Buffers, NUM_BUFFERS, NUM_TEXTURES>
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         name: &str,
         device: &Device,
@@ -65,7 +66,10 @@ impl<
         shader: &Shader,
         topology: PrimitiveTopology,
         front_face: FrontFace,
+        cull_mode: Option<Face>,
         use_alpha_blend: bool,
+        depth_write: bool,
+        samples: r3::SampleCount,
     ) -> Self {
         let bgl = {
             let mut builder = BindGroupLayoutBuilder::new();
@@ -104,9 +108,12 @@ impl<
             label: Some(&format!("{name} render pipeline")),
             layout: Some(&pipeline_layout),
             vertex: shader.to_vertex_state(&[]),
-            primitive: common::primitive_state(topology, front_face),
-            depth_stencil: Some(common::depth_stencil(true)),
-            multisample: MultisampleState::default(),
+            primitive: common::primitive_state(topology, front_face, cull_mode),
+            depth_stencil: Some(common::depth_stencil(depth_write)),
+            multisample: MultisampleState {
+                count: samples as u32,
+                ..Default::default()
+            },
             fragment: Some(if use_alpha_blend {
                 shader.to_fragment_state_transparent()
             } else {
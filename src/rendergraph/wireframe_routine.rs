@@ -16,17 +16,30 @@ pub struct WireframeBuffer {
     line_positions: Buffer,
     /// Contains len Vec3 elements (color)
     colors: Buffer,
+    /// Contains a single `WireframeSettings` element
+    settings: Buffer,
     /// Number of elements
     len: usize,
 }
 
-const NUM_BUFFERS: usize = 2;
+/// Settings for the wireframe shader. Uploaded to the GPU alongside each
+/// wireframe buffer.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct WireframeSettings {
+    /// The width of the lines, in pixels. Lines are expanded into screen-space
+    /// quads in the vertex shader, because most wgpu backends can only draw
+    /// lines that are one pixel wide.
+    pub line_width: f32,
+}
+
+const NUM_BUFFERS: usize = 3;
 const NUM_TEXTURES: usize = 0;
 
 impl ViewportBuffers<NUM_BUFFERS, NUM_TEXTURES> for WireframeBuffer {
     type Settings = ();
     fn get_wgpu_buffers(&self, _settings: &()) -> [&Buffer; NUM_BUFFERS] {
-        [&self.line_positions, &self.colors]
+        [&self.line_positions, &self.colors, &self.settings]
     }
 
     fn get_wgpu_textures<'a>(
@@ -39,7 +52,7 @@ impl ViewportBuffers<NUM_BUFFERS, NUM_TEXTURES> for WireframeBuffer {
 
     fn get_draw_type(&self, _settings: &Self::Settings) -> DrawType<'_> {
         DrawType::UseInstances {
-            num_vertices: 2,
+            num_vertices: 6,
             num_instances: self.len,
         }
     }
@@ -50,21 +63,35 @@ pub struct WireframeRoutine {
 }
 
 impl WireframeRoutine {
-    pub fn new(device: &Device, base: &BaseRenderGraph, shader_manager: &ShaderManager) -> Self {
+    pub fn new(
+        device: &Device,
+        base: &BaseRenderGraph,
+        shader_manager: &ShaderManager,
+        samples: r3::SampleCount,
+    ) -> Self {
         Self {
             inner: Viewport3dRoutine::new(
                 "edge wireframe",
                 device,
                 base,
                 shader_manager.get("edge_wireframe_draw"),
-                PrimitiveTopology::LineList,
+                PrimitiveTopology::TriangleList,
                 FrontFace::Ccw,
+                Some(Face::Back),
                 false,
+                true,
+                samples,
             ),
         }
     }
 
-    pub fn add_wireframe(&mut self, device: &Device, lines: &[Vec3], colors: &[Vec3]) {
+    pub fn add_wireframe(
+        &mut self,
+        device: &Device,
+        lines: &[Vec3],
+        colors: &[Vec3],
+        settings: WireframeSettings,
+    ) {
         let len = colors.len();
         assert!(
             lines.len() == colors.len() * 2,
@@ -81,11 +108,17 @@ impl WireframeRoutine {
             contents: bytemuck::cast_slice(colors),
             usage: BufferUsages::STORAGE,
         });
+        let settings = device.create_buffer_init(&BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&[settings]),
+            usage: BufferUsages::STORAGE,
+        });
 
         self.inner.buffers.push(WireframeBuffer {
             len,
             line_positions,
             colors,
+            settings,
         });
     }
 
@@ -21,3 +21,99 @@ impl<T: Copy> SliceUtils<T> for [T] {
         self.iter().copied()
     }
 }
+
+/// A handle to report progress and request cancellation of long-running
+/// operations. Cloning the handle gives access to the same shared state, so
+/// one end can be kept by the caller while the other is passed to the
+/// operation.
+///
+/// The active node is evaluated on a worker thread, so the UI can show the
+/// progress and cancel an operation while it runs.
+#[derive(Clone, Default)]
+pub struct Progress {
+    cancelled: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    fraction: std::sync::Arc<atomic_float::AtomicF32>,
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation. Operations check this periodically and stop
+    /// early, returning an error.
+    pub fn cancel(&self) {
+        self.cancelled
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Returns an error if cancellation was requested. Meant to be used with
+    /// the `?` operator at the check points of an operation.
+    pub fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() {
+            bail!("Operation was cancelled")
+        }
+        Ok(())
+    }
+
+    /// Sets the completed fraction of the operation, in the [0, 1] range.
+    pub fn set_fraction(&self, fraction: f32) {
+        self.fraction.store(
+            fraction.clamp(0.0, 1.0),
+            std::sync::atomic::Ordering::Relaxed,
+        );
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.fraction.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Clears the cancellation flag and the progress, so the handle can be
+    /// reused for a new operation.
+    pub fn reset(&self) {
+        self.cancelled
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+        self.set_fraction(0.0);
+    }
+}
+
+/// The default value for the maximum number of elements of each kind (e.g.
+/// vertices, faces) that an operation is allowed to create.
+pub const DEFAULT_ELEMENT_LIMIT: usize = 10_000_000;
+
+/// The highest element limit that can be set from the UI. Meshes this big
+/// already take tens of gigabytes of memory.
+pub const MAX_ELEMENT_LIMIT: usize = 1_000_000_000;
+
+static ELEMENT_LIMIT: std::sync::atomic::AtomicUsize =
+    std::sync::atomic::AtomicUsize::new(DEFAULT_ELEMENT_LIMIT);
+
+/// Returns the maximum number of elements of each kind an operation can
+/// create. This guards against node parameters that would make an operation
+/// allocate huge amounts of memory.
+pub fn element_limit() -> usize {
+    ELEMENT_LIMIT.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+pub fn set_element_limit(limit: usize) {
+    ELEMENT_LIMIT.store(limit, std::sync::atomic::Ordering::Relaxed)
+}
+
+/// Returns an error if creating `count` elements of the given `kind` would
+/// exceed the [`element_limit`]. Operations call this before allocating.
+pub fn check_element_limit(count: usize, kind: &str) -> Result<()> {
+    let limit = element_limit();
+    if count > limit {
+        bail!(
+            "Operation would create {} {}, exceeding the limit of {}",
+            count,
+            kind,
+            limit
+        )
+    }
+    Ok(())
+}